@@ -1,9 +1,57 @@
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::Args;
 use clap::Parser;
 use clap::Subcommand;
-use inquire::Select;
+use clap::ValueEnum;
+use clap_complete::Shell;
+use inquire::{Confirm, MultiSelect, Select};
+use tracing_subscriber::EnvFilter;
 
-use crate::provider::AmarisRegistry;
-use crate::utils::AmarisInitialConfigHandler;
+use crate::bundle::AmarisBundle;
+use crate::ci::AmarisCi;
+use crate::completions::AmarisCompletions;
+use crate::config::AmayaConfig;
+use crate::deps::{AmarisDeps, DepsReport};
+use crate::doctor::AmarisDoctor;
+use crate::error::ConfigError;
+use crate::export::{EXPORT_FILE_NAME, ProjectExport};
+use crate::index::AmarisIndex;
+use crate::install::AmarisInstall;
+use crate::layers::{AmarisContributions, SettingLayers, merge_contributions};
+use crate::lock::AmarisLock;
+use crate::network::AmarisNetwork;
+use crate::npm_providers::{AmarisNpmProviders, NpmProviderSpec, ProviderUpdate};
+use crate::package_manager::redact_env;
+use crate::preview::AmarisPreview;
+use crate::provider::{
+    AmarisAliases, AmarisProvider, AmarisRegistry, ConfigEntry, DynamicProvider, InstallOptions,
+    InstallPhase, PackageScope, ProjectContext, ProviderDiff, ProviderInfo,
+};
+use crate::reporter::Reporter;
+use crate::runner::AmarisScriptRunner;
+use crate::selftest::AmarisSelfTest;
+use crate::state::{AmarisBaseStore, AmarisProjectState, BASE_STORE_DIR, InstallRecord};
+use crate::status::AmarisStatus;
+use crate::stream::AmarisStream;
+use crate::summary::{AmarisEvents, InstallSummary};
+use crate::sync::AmarisSync;
+use crate::templates::{PACKAGE_MANAGERS, PROVIDER_TEMPLATES};
+use crate::transaction::{InstallTransaction, TransactionStep};
+use crate::updates::{AmarisUpdates, PackageUpdate, UpdateKind};
+use crate::upgrade::AmarisUpgrade;
+use crate::utils::{
+    AmarisConfigurationHandler, AmarisFileHandler, AmarisGitHandler, AmarisInitialConfigHandler,
+    AmarisJournal, AmarisPackageJsonHandler, AmarisPathHandler, AmarisPlatformHandler,
+    AmarisVisualStudioCodeHandler, BACKUP_SUFFIX, ConfigChange, Editor, InitConfig,
+    sha256_integrity,
+};
+use crate::validate::AmarisValidate;
+use crate::watch::{AmarisWatcher, print_diff, print_unified_diff};
 
 #[derive(Parser)]
 #[command(name = env!("CARGO_PKG_NAME"))]
@@ -12,83 +60,2990 @@ use crate::utils::AmarisInitialConfigHandler;
 pub struct CLI {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[command(flatten)]
+    pub global: GlobalArgs,
+}
+
+#[derive(Args)]
+pub struct GlobalArgs {
+    /// Disables colored output. The `NO_COLOR` environment variable is also respected.
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Seconds a package manager command may run before it is killed [default: 300]
+    #[arg(long, global = true, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Fails any operation that would access the network.
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Package manager to install provider packages with, instead of the one of the project or provider.
+    #[arg(long, global = true, value_name = "MANAGER")]
+    pub package_manager: Option<String>,
+
+    /// Log level or filter directives like `amaya=debug`, written to stderr. Overrides `AMAYA_LOG` [default: warn]
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = parse_log_level)]
+    pub log_level: Option<EnvFilter>,
+
+    /// Run in the project of this directory instead of the current one. Either way the closest directory with a package.json is used.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Wait up to this many seconds for another amaya process changing the project to finish, instead of failing.
+    #[arg(long, global = true, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "60")]
+    pub wait: Option<u64>,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// Installs a specific configuration.
     ///
-    /// Optionally, you can provide a config file with `--config` or `-c`
-    Install {
-        #[arg(short, long)]
-        config: Option<String>,
+    /// Optionally, you can provide a config file with `--config` or `-c`, or
+    /// the name of a group from the global config to install all of its providers.
+    Install(InstallArgs),
+    /// Upgrades an installed configuration, merging provider changes with local edits.
+    Upgrade(UpgradeArgs),
+    /// Lists all available configurations, deprecated in favour of `amaya providers list`.
+    List {
+        #[command(flatten)]
+        filter: ListArgs,
+    },
+    /// Manages the provider definitions in `~/.amaya/providers`.
+    Providers {
+        #[command(subcommand)]
+        action: ProvidersAction,
     },
-    /// Lists all available configurations.
-    List,
     /// Removes a specific configuration.
     ///
     /// This requires a config file to be specified via `--config` or `-c`
     Remove {
         #[arg(short, long)]
         config: String,
+        /// Sets an environment variable on the package manager, overriding the provider's `environment`.
+        #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        env: Vec<(String, String)>,
+        /// Skip the check for uncommitted changes to files that will be modified.
+        #[arg(long)]
+        allow_dirty: bool,
+        /// Commit the files touched by amaya afterwards.
+        #[arg(long)]
+        commit: bool,
+    },
+    /// Removes every installed configuration and the amaya state from the current project.
+    Reset {
+        /// Also deletes the `.amaya.bak` backups in the project.
+        #[arg(long)]
+        backups: bool,
+        /// Also deletes the project's `.amaya` directory and the cached bundles of its providers.
+        #[arg(long)]
+        hard: bool,
+        /// Skip the confirmation prompt.
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Replaces an installed configuration with another one, putting everything back if a step fails.
+    Switch {
+        /// Installed configuration to remove.
+        from: String,
+        /// Configuration to install in its place.
+        to: String,
+        /// Skip the confirmation prompt.
+        #[arg(short, long)]
+        yes: bool,
+        /// Skip the check for uncommitted changes to files that will be modified.
+        #[arg(long)]
+        allow_dirty: bool,
+        /// Commit the files touched by amaya afterwards.
+        #[arg(long)]
+        commit: bool,
+    },
+    /// Runs a package.json script with the project's package manager, exiting with its exit code.
+    Run {
+        /// Script to run.
+        #[arg(required_unless_present = "list")]
+        script: Option<String>,
+        /// Arguments passed on to the script.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+        /// List the scripts of package.json instead.
+        #[arg(long, conflicts_with = "script")]
+        list: bool,
+    },
+    /// Checks every provider file and the integrity of its configuration sources.
+    Validate {
+        /// Recomputes the `integrity` hashes of all configuration sources after intentional edits.
+        #[arg(long)]
+        update_hashes: bool,
+    },
+    /// Bundles a provider and its configuration sources into `<name>.amaya.tar.gz`.
+    Pack {
+        name: String,
+        /// Directory to write the bundle to, defaults to the current directory.
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+    /// Adds the provider from a bundle created with `amaya pack`.
+    Unpack { file: PathBuf },
+    /// Searches the registry index for providers by name or description.
+    Search { query: String },
+    /// Adds a provider from a bundle file, from an npm package given as
+    /// `npm:<package>[@<version>]`, or by name from the registry with `--from-registry`.
+    Add {
+        source: String,
+        #[arg(long)]
+        from_registry: bool,
+    },
+    /// Updates the providers added from npm packages to their latest version.
+    UpdateProviders {
+        /// Only update these providers, including pinned ones.
+        names: Vec<String>,
+        /// Only list the providers with a newer version.
+        #[arg(long)]
+        check: bool,
+    },
+    /// Writes the providers installed in the project, their sources embedded,
+    /// and the overrides in effect to an `amaya-export.json` file.
+    Export {
+        #[arg(default_value = EXPORT_FILE_NAME)]
+        file: PathBuf,
+    },
+    /// Replays the installs of an `amaya-export.json` file, safe to repeat.
+    Import {
+        #[arg(default_value = EXPORT_FILE_NAME)]
+        file: PathBuf,
+        /// Install even if configuration files of a competing tool are present.
+        #[arg(short, long)]
+        force: bool,
+        /// Skip the check for uncommitted changes to files that will be modified.
+        #[arg(long)]
+        allow_dirty: bool,
+        /// Commit the files touched by amaya afterwards.
+        #[arg(long)]
+        commit: bool,
+    },
+    /// Lists where each configuration of a provider is read from and written to.
+    Configs {
+        name: String,
+        /// Prints the source of the configuration with this file name instead.
+        #[arg(long, value_name = "FILE_NAME")]
+        cat: Option<String>,
+    },
+    /// Renames a provider, its file and configuration directory, keeping the old name as an alias.
+    RenameProvider { old: String, new: String },
+    /// Opens a provider file in `$VISUAL` or `$EDITOR` and validates it afterwards.
+    Edit {
+        name: String,
+        /// Configuration source in `~/.amaya/configs/<name>/` to open instead of the provider file.
+        #[arg(long, value_name = "FILE")]
+        config: Option<PathBuf>,
+    },
+    /// Shows the details of a configuration provider.
+    Info {
+        name: String,
+        /// Prints the details as JSON on a single line.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Shows how installing a provider would change the configuration files and scripts of the project, exiting with 1 when it would change anything.
+    Diff {
+        name: String,
+        /// Variant of the provider to compare, the installed or detected one when omitted.
+        #[arg(long)]
+        variant: Option<String>,
+        /// Sets a template variable, overriding provider defaults and project overrides.
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, String)>,
+    },
+    /// Shows the configurations installed in the current project and whether their files are present.
+    Status,
+    /// Prints the provider that manages a file of the project.
+    Which { file: String },
+    /// Writes the configurations of a provider into the project again whenever its provider file or sources change.
+    Watch {
+        name: String,
+        /// Sets a template variable, overriding provider defaults and project overrides.
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, String)>,
+    },
+    /// Installs a provider into a new project in the temporary directory and checks the result like `amaya ci`.
+    TestProvider {
+        name: String,
+        /// Project to copy into the test project first, without its `.git` and `node_modules`.
+        #[arg(long, value_name = "PATH")]
+        fixture: Option<PathBuf>,
+        /// Do not install the provider's packages.
+        #[arg(long)]
+        no_install: bool,
+        /// Keep the test project instead of deleting it once the test passes.
+        #[arg(long)]
+        keep: bool,
+        /// Variant of the provider to install, detected from the project or prompted for when omitted.
+        #[arg(long)]
+        variant: Option<String>,
+        /// Sets a template variable, overriding provider defaults and project overrides.
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, String)>,
+    },
+    /// Shows newer versions of the packages installed providers manage.
+    CheckUpdates {
+        /// Only check the packages of this provider.
+        #[arg(conflicts_with = "provider")]
+        name: Option<String>,
+        /// Only check the packages of this provider.
+        #[arg(long)]
+        provider: Option<String>,
+    },
+    /// Lists which dependencies of package.json installed providers manage, which none do and which are missing.
+    Deps {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// For each provider with missing packages, choose to install them or stop tracking them.
+        #[arg(long, conflicts_with = "output")]
+        prune: bool,
+    },
+    /// Reads or changes a setting in `~/.amaya/config.toml`.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
     /// Runs diagnostic commands to check the system's state.
     Doctor,
+    /// Checks the installed configurations non-interactively and prints a JSON summary, exiting with 1 on any problem.
+    Ci,
     /// Populates the configuration root directory with default configurations.
-    Init,
+    Init {
+        /// Starter providers to create, prompted for when omitted.
+        #[arg(long, value_delimiter = ',', value_name = "PROVIDERS")]
+        providers: Option<Vec<String>>,
+        /// Skip the questions: create the Biome and Prettier + ESLint providers for bun and leave `~/.amaya/config.toml` alone.
+        #[arg(long)]
+        defaults: bool,
+    },
+    /// Runs init, install, status, check and remove against a temporary amaya
+    /// home and project, reporting each step and exiting with 1 on a failure.
+    #[command(hide = true)]
+    Selftest,
+    /// Prints the completion script for a shell, completing provider names known at generation time.
+    Completions {
+        /// Shell to complete for, detected from `$SHELL` with `--install` when omitted.
+        #[arg(required_unless_present = "install")]
+        shell: Option<Shell>,
+        /// Write the script to the shell's completion directory instead of printing it.
+        #[arg(long)]
+        install: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InstallOutput {
+    Text,
+    JsonLines,
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Only show configurations installed in the current project.
+    #[arg(long, conflicts_with = "not_installed")]
+    installed: bool,
+    /// Only show configurations not installed in the current project.
+    #[arg(long)]
+    not_installed: bool,
+    /// Show the version of each configuration.
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// Which phases of an install to apply, all of them by default.
+#[derive(Args)]
+pub struct ScopeArgs {
+    /// Only install the provider's packages.
+    #[arg(long, group = "only")]
+    only_packages: bool,
+    /// Only write the provider's configuration files and editor settings.
+    #[arg(long, group = "only")]
+    only_configs: bool,
+    /// Only add the provider's scripts to package.json.
+    #[arg(long, group = "only")]
+    only_scripts: bool,
+    /// Do not install the provider's packages.
+    #[arg(long, conflicts_with = "only")]
+    skip_packages: bool,
+    /// Do not write the provider's configuration files and editor settings.
+    #[arg(long, conflicts_with = "only")]
+    skip_configs: bool,
+    /// Do not add the provider's scripts to package.json.
+    #[arg(long, conflicts_with = "only")]
+    skip_scripts: bool,
+}
+
+impl ScopeArgs {
+    pub fn phases(&self) -> anyhow::Result<Vec<InstallPhase>> {
+        let flags = [
+            (
+                InstallPhase::Packages,
+                self.only_packages,
+                self.skip_packages,
+            ),
+            (InstallPhase::Configs, self.only_configs, self.skip_configs),
+            (InstallPhase::Scripts, self.only_scripts, self.skip_scripts),
+        ];
+
+        if let Some((phase, _, _)) = flags.iter().find(|(_, only, _)| *only) {
+            return Ok(vec![*phase]);
+        }
+
+        let phases: Vec<InstallPhase> = flags
+            .iter()
+            .filter(|(_, _, skip)| !skip)
+            .map(|(phase, _, _)| *phase)
+            .collect();
+
+        if phases.is_empty() {
+            anyhow::bail!(
+                "--skip-packages, --skip-configs and --skip-scripts together leave nothing to install"
+            );
+        }
+
+        Ok(phases)
+    }
+}
+
+#[derive(Args)]
+pub struct InstallArgs {
+    #[arg(short, long)]
+    pub config: Option<String>,
+    /// Configuration or group to install, same as `--config`.
+    #[arg(conflicts_with = "config")]
+    pub name: Option<String>,
+    /// Installs every configuration not installed in the project yet, dependencies first.
+    #[arg(long, conflicts_with_all = ["config", "name", "from_file"])]
+    pub all: bool,
+    /// How many providers of `--all` or a group are checked at once, the
+    /// installs themselves run one at a time.
+    #[arg(long, value_name = "N", default_value = "4")]
+    pub concurrency: NonZeroUsize,
+    /// Installs the provider defined in a local file without adding it to the provider directory.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["config", "name"])]
+    pub from_file: Option<PathBuf>,
+    /// Copies the file given with `--from-file` into the provider directory.
+    #[arg(long, requires = "from_file")]
+    pub save: bool,
+    /// Variant of the provider to install, detected from the project or prompted for when omitted.
+    #[arg(long)]
+    pub variant: Option<String>,
+    /// Install even if configuration files of a competing tool are present.
+    #[arg(short, long)]
+    pub force: bool,
+    /// Install deprecated providers without asking.
+    #[arg(long)]
+    pub allow_deprecated: bool,
+    /// Skip the check for uncommitted changes to files that will be modified.
+    #[arg(long)]
+    pub allow_dirty: bool,
+    /// Commit the files touched by amaya afterwards.
+    #[arg(long)]
+    pub commit: bool,
+    /// Sets a template variable, overriding provider defaults and project overrides.
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+    pub variables: Vec<(String, String)>,
+    /// Sets an environment variable on the package manager, overriding the provider's `environment`.
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_variable)]
+    pub env: Vec<(String, String)>,
+    /// Editors to write settings for, defaults to `editors` in the global config or the editor directories present in the project.
+    #[arg(long, value_delimiter = ',', value_name = "EDITORS")]
+    pub editors: Option<Vec<Editor>>,
+    /// Multi-root workspace file to write VS Code settings to, defaults to the `*.code-workspace` file in the project root.
+    #[arg(long, value_name = "PATH")]
+    pub workspace_file: Option<PathBuf>,
+    /// Installs into a new project in the temporary directory, along with the providers it depends on, and opens a shell there.
+    #[arg(long, conflicts_with_all = ["all", "commit", "workspace_file"])]
+    pub preview: bool,
+    /// Deletes the projects created by `--preview`.
+    #[arg(long, requires = "preview")]
+    pub clean: bool,
+    /// How progress is reported, `json-lines` writes each event as a JSON object to stdout, moves all other output to stderr and takes prompt answers on stdin.
+    #[arg(long, value_enum, default_value_t = InstallOutput::Text, conflicts_with = "preview")]
+    pub output: InstallOutput,
+    #[command(flatten)]
+    pub scope: ScopeArgs,
+}
+
+#[derive(Args)]
+pub struct UpgradeArgs {
+    #[arg(short, long)]
+    pub config: String,
+    /// Keep local changes when both sides changed the same setting.
+    #[arg(long, conflicts_with = "theirs")]
+    pub ours: bool,
+    /// Take the provider's changes when both sides changed the same setting.
+    #[arg(long)]
+    pub theirs: bool,
+    /// Skip the check for uncommitted changes to files that will be modified.
+    #[arg(long)]
+    pub allow_dirty: bool,
+    /// Commit the files touched by amaya afterwards.
+    #[arg(long)]
+    pub commit: bool,
+    /// Sets a template variable, overriding provider defaults and project overrides.
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+    pub variables: Vec<(String, String)>,
+    /// Sets an environment variable on the package manager, overriding the provider's `environment`.
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_variable)]
+    pub env: Vec<(String, String)>,
+    /// Editors to write settings for, defaults to `editors` in the global config or the editor directories present in the project.
+    #[arg(long, value_delimiter = ',', value_name = "EDITORS")]
+    pub editors: Option<Vec<Editor>>,
+}
+
+#[derive(Subcommand)]
+pub enum ProvidersAction {
+    /// Lists all available providers.
+    List {
+        #[command(flatten)]
+        filter: ListArgs,
+    },
+    /// Copies a provider file into the provider directory.
+    Add { path: PathBuf },
+    /// Deletes a provider file from the provider directory.
+    Remove { name: String },
+    /// Prints the file a provider is defined in.
+    Show { name: String },
+    /// Shares providers with a team through the git repository `provider_git_remote` in config.toml.
+    Sync {
+        /// Copy edits to the project's managed files back into the provider sources, then commit and push `~/.amaya/providers` and `~/.amaya/configs`.
+        #[arg(long, conflicts_with = "pull", required_unless_present = "pull")]
+        push: bool,
+        /// Update `~/.amaya/providers` and `~/.amaya/configs` from the repository.
+        #[arg(long)]
+        pull: bool,
+    },
+    /// Checks provider files against the provider schema, their fields and
+    /// configuration sources, exiting with 1 if any fails. Checks every
+    /// provider when neither a name nor a file is given.
+    Validate {
+        /// Provider name, or a provider file outside the provider directory.
+        target: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Stores a setting, e.g. `amaya config set network.ca_bundle ./corp-ca.pem`.
+    Set { key: String, value: String },
+    /// Prints the value of a setting.
+    Get { key: String },
 }
 
 impl Commands {
-    pub async fn execute(&self, registry: &AmarisRegistry) -> anyhow::Result<()> {
+    /// Whether the command writes to the current project and has to hold its
+    /// lock. Commands that only read it, or only touch `~/.amaya`, do not.
+    fn mutates_project(&self) -> bool {
         match self {
-            Commands::Install { config } => {
-                let config_name = match config {
-                    Some(name) => name.clone(),
-                    None => {
-                        let configs: Vec<(&str, &str)> = registry.available_configs();
-                        let options: Vec<_> = configs.iter().map(|(_, desc)| *desc).collect();
-                        let selection =
-                            Select::new("Select configuration to install:", options).prompt()?;
-
-                        configs
-                            .iter()
-                            .find(|(_, desc)| *desc == selection)
-                            .map(|(name, _)| name.to_string())
-                            .unwrap()
+            Commands::Install(args) => !args.preview,
+            Commands::Deps { prune, .. } => *prune,
+            Commands::Upgrade(_)
+            | Commands::Remove { .. }
+            | Commands::Reset { .. }
+            | Commands::Switch { .. }
+            | Commands::Import { .. }
+            | Commands::Watch { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether events are streamed to stdout with `--output json-lines`.
+    pub fn streams_events(&self) -> bool {
+        matches!(
+            self,
+            Commands::Install(InstallArgs {
+                output: InstallOutput::JsonLines,
+                ..
+            })
+        )
+    }
+
+    pub async fn execute(
+        &self,
+        registry: &AmarisRegistry,
+        global: &GlobalArgs,
+        project: &ProjectContext,
+    ) -> anyhow::Result<()> {
+        let reporter = Reporter::global();
+        let timeout = Duration::from_secs(match global.timeout {
+            Some(timeout) => timeout,
+            None => AmayaConfig::load(project).await?.timeout,
+        });
+        let _lock = match self.mutates_project() {
+            true => Some(
+                AmarisLock::acquire(&project.root, global.wait.map(Duration::from_secs)).await?,
+            ),
+            false => None,
+        };
+
+        match self {
+            Commands::Install(args) => AmarisInstall::run(project, registry, args, timeout).await?,
+            Commands::Upgrade(args) => AmarisUpgrade::run(project, registry, args, timeout).await?,
+            Commands::List { filter } => {
+                reporter.warn("Warning: `amaya list` is deprecated, use `amaya providers list`");
+                list_providers(project, registry, filter).await?;
+            }
+            Commands::Providers { action } => match action {
+                ProvidersAction::List { filter } => {
+                    list_providers(project, registry, filter).await?
+                }
+                ProvidersAction::Add { path } => {
+                    let provider = DynamicProvider::load_from_file(project, path).await?;
+                    if registry.get_provider(&provider.name).is_some() {
+                        return Err(ConfigError::AlreadyExists(provider.name).into());
+                    }
+
+                    let target = provider_save_path(project, path).await?;
+                    tokio::fs::copy(path, &target)
+                        .await
+                        .map_err(|e| ConfigError::FileWriteError(e.to_string()))?;
+
+                    reporter.success(format!(
+                        "Added provider {} to {}",
+                        reporter.provider(&provider.name),
+                        target.display()
+                    ));
+                }
+                ProvidersAction::Remove { name } => {
+                    let path = provider_file(project, registry, name).await?;
+
+                    for other in DynamicProvider::read_all(project, None).await? {
+                        if other.extends.contains(name) || other.depends_on.contains(name) {
+                            reporter.warn(format!(
+                                "Warning: {} extends or depends on {}",
+                                reporter.provider(&other.name),
+                                name
+                            ));
+                        }
+                    }
+
+                    if AmarisProjectState::load(project).await?.is_installed(name) {
+                        reporter.warn(format!(
+                            "Warning: {} is still installed in this project",
+                            reporter.provider(name)
+                        ));
+                    }
+
+                    tokio::fs::remove_file(&path)
+                        .await
+                        .map_err(|e| ConfigError::FileWriteError(e.to_string()))?;
+                    AmarisNpmProviders::forget(project, name).await?;
+
+                    reporter.success(format!("Removed {}", path.display()));
+                }
+                ProvidersAction::Show { name } => {
+                    let path = provider_file(project, registry, name).await?;
+                    let content = AmarisFileHandler::read_text(&path).await?;
+
+                    reporter.info(content.trim_end());
+                }
+                ProvidersAction::Sync { push, pull } => {
+                    let remote = AmayaConfig::load(project).await?.provider_git_remote;
+
+                    if *push {
+                        let updated = AmarisSync::push_project(project, registry).await?;
+                        for source in &updated {
+                            reporter.success(format!("Updated {}", source.display()));
+                        }
+                        if updated.is_empty() {
+                            reporter.info("The provider sources match the project");
+                        }
+                    }
+
+                    let Some(remote) = remote else {
+                        if *pull {
+                            anyhow::bail!(
+                                "No provider_git_remote is configured, set one with `amaya config set provider_git_remote <url>`"
+                            );
+                        }
+                        reporter.info(
+                            "Set provider_git_remote to share the providers with `amaya providers sync`",
+                        );
+                        return Ok(());
+                    };
+
+                    let changes = match pull {
+                        true => AmarisSync::pull(project, &remote).await?,
+                        false => AmarisSync::push_remote(project, &remote).await?,
+                    };
+                    for change in &changes {
+                        reporter.info(format!(
+                            "{} {}",
+                            match change.added {
+                                true => "Added",
+                                false => "Updated",
+                            },
+                            change.path.display()
+                        ));
+                    }
+
+                    match (changes.is_empty(), pull) {
+                        (true, _) => reporter.success("Already up to date"),
+                        (false, true) => reporter.success(format!(
+                            "Pulled {} file(s) from {}",
+                            changes.len(),
+                            remote
+                        )),
+                        (false, false) => reporter.success(format!(
+                            "Pushed {} file(s) to {}",
+                            changes.len(),
+                            remote
+                        )),
+                    }
+                }
+                ProvidersAction::Validate { target } => {
+                    let files = match target {
+                        Some(target) if Path::new(target).is_file() => vec![PathBuf::from(target)],
+                        Some(name) => vec![provider_file_by_name(project, name).await?],
+                        None => DynamicProvider::files(project, None).await?,
+                    };
+
+                    let mut failed = 0;
+                    for file in &files {
+                        let problems = validate_provider_file(project, file).await?;
+                        match problems {
+                            0 => reporter.success(format!("{} is valid", file.display())),
+                            _ => failed += 1,
+                        }
+                    }
+
+                    if failed > 0 {
+                        anyhow::bail!("{} of {} provider(s) failed", failed, files.len());
+                    }
+                }
+            },
+            Commands::Remove {
+                config,
+                env,
+                allow_dirty,
+                commit,
+            } => {
+                if let Some(provider) = registry.get_provider(config) {
+                    let state = AmarisProjectState::load(project).await?;
+                    let variant_provider = state
+                        .get(provider.name())
+                        .and_then(|record| record.variant.as_deref())
+                        .map(|variant| provider.with_variant(variant))
+                        .transpose()?;
+                    let provider = variant_provider.as_deref().unwrap_or(provider);
+
+                    if !*allow_dirty
+                        && !confirm_dirty_worktree(
+                            project,
+                            &[(
+                                provider,
+                                state.workspace_file(project, provider.name()).as_deref(),
+                            )],
+                        )
+                        .await?
+                    {
+                        reporter.warn("Aborted.");
+                        return Ok(());
+                    }
+
+                    let options = InstallOptions {
+                        timeout,
+                        workspace_file: state.workspace_file(project, provider.name()),
+                        phases: state.phases(provider.name()),
+                        package_scopes: state.package_scopes(provider.name()),
+                        original_scripts: state.original_scripts(provider.name()),
+                        installed_files: state.installed_files(provider.name()),
+                        extra_env: env.iter().cloned().collect(),
+                        ..Default::default()
+                    };
+
+                    uninstall_provider(project, provider, &options).await?;
+
+                    commit_changes(
+                        project,
+                        *commit,
+                        "remove",
+                        provider.name(),
+                        provider.metadata().version.as_deref(),
+                    )
+                    .await?;
+                }
+            }
+            Commands::Switch {
+                from,
+                to,
+                yes,
+                allow_dirty,
+                commit,
+            } => {
+                switch_providers(project, registry, from, to, *yes, *allow_dirty, timeout).await?;
+
+                commit_changes(
+                    project,
+                    *commit,
+                    "switch",
+                    &format!("{} to {}", from, to),
+                    None,
+                )
+                .await?;
+            }
+            Commands::Run { script, args, list } => {
+                let scripts = AmarisPackageJsonHandler::get_scripts(project).await?;
+
+                if *list {
+                    if scripts.is_empty() {
+                        reporter.info("package.json has no scripts.");
+                        return Ok(());
+                    }
+
+                    let width = scripts
+                        .iter()
+                        .map(|(name, _)| name.len())
+                        .max()
+                        .unwrap_or(0);
+                    for (name, command) in &scripts {
+                        reporter.info(format!("  {:<width$}  {}", name, command, width = width));
+                    }
+                    return Ok(());
+                }
+
+                let Some(script) = script else {
+                    anyhow::bail!("No script given, pass one or --list");
+                };
+
+                if AmarisPackageJsonHandler::get_script(project, script)
+                    .await?
+                    .is_none()
+                {
+                    if scripts.is_empty() {
+                        anyhow::bail!("Unknown script '{}', package.json has no scripts", script);
+                    }
+
+                    let names: Vec<&str> = scripts.iter().map(|(name, _)| name.as_str()).collect();
+                    anyhow::bail!(
+                        "Unknown script '{}', available: {}",
+                        script,
+                        names.join(", ")
+                    );
+                }
+
+                let manager = match &global.package_manager {
+                    Some(manager) => manager.clone(),
+                    None => AmarisPackageJsonHandler::detect_package_manager(project).await?,
+                };
+                let code = AmarisScriptRunner::run(&manager, script, args).await?;
+
+                if code != 0 {
+                    std::process::exit(code);
+                }
+            }
+            Commands::Reset { backups, hard, yes } => {
+                let state = AmarisProjectState::load(project).await?;
+
+                if state.installed_providers.is_empty()
+                    && !AmarisProjectState::get_default_path(project).exists()
+                {
+                    reporter.info("No configurations installed in this project.");
+                    return Ok(());
+                }
+
+                let names: Vec<&str> = state
+                    .installed_providers
+                    .iter()
+                    .map(|record| record.name.as_str())
+                    .collect();
+                reporter.info(format!(
+                    "This removes {} and the amaya state from this project.",
+                    match names.is_empty() {
+                        true => "no configurations".to_string(),
+                        false => names.join(", "),
+                    }
+                ));
+
+                if !*yes && !Confirm::new("Continue?").with_default(false).prompt()? {
+                    reporter.warn("Aborted.");
+                    return Ok(());
+                }
+
+                // Removed last to first, so each provider's shared settings are on top
+                let mut failures = 0;
+                let mut remaining = AmarisProjectState::load(project).await?;
+                while let Some(record) = remaining.installed_providers.last().cloned() {
+                    let options = InstallOptions {
+                        timeout,
+                        workspace_file: remaining.workspace_file(project, &record.name),
+                        phases: record.phases.clone(),
+                        package_scopes: record.package_scopes.clone(),
+                        original_scripts: record.original_scripts.clone(),
+                        installed_files: record.files.clone(),
+                        ..Default::default()
+                    };
+
+                    reporter.info(format!("Removing {}...", reporter.provider(&record.name)));
+                    let layers = SettingLayers::capture(project, &remaining, &record.name).await?;
+                    match reset_provider(project, registry, &record, &options).await {
+                        Ok(()) => layers.restore(project, &mut remaining).await?,
+                        Err(e) => {
+                            reporter.error(format!("{}: {}", record.name, e));
+                            failures += 1;
+                        }
+                    }
+                    remaining.record_remove(&record.name);
+                }
+
+                AmarisFileHandler::remove_file(
+                    project,
+                    AmarisProjectState::get_default_path(project),
+                )
+                .await?;
+
+                if *backups {
+                    for backup in find_backups(&project.root) {
+                        AmarisFileHandler::remove_file(project, backup).await?;
+                    }
+                }
+
+                if *hard {
+                    let base_store = project.path(BASE_STORE_DIR);
+                    if let Some(project_dir) = base_store.parent()
+                        && project_dir.exists()
+                    {
+                        tokio::fs::remove_dir_all(project_dir).await?;
                     }
+
+                    for record in &state.installed_providers {
+                        AmarisIndex::clear_cached_bundles(project, &record.name).await?;
+                    }
+                }
+
+                if failures > 0 {
+                    anyhow::bail!("{} provider(s) could not be removed cleanly", failures);
+                }
+
+                reporter.success("Project reset.");
+            }
+            Commands::Validate { update_hashes } => {
+                let problems = AmarisValidate::run(project, registry, *update_hashes).await?;
+                if problems > 0 {
+                    anyhow::bail!("{} problem(s) found", problems);
+                }
+
+                reporter.success("All providers are valid");
+            }
+            Commands::Pack { name, output } => {
+                let output_dir = output.clone().unwrap_or_else(|| PathBuf::from("."));
+                let bundle = AmarisBundle::pack(project, name, &output_dir).await?;
+
+                reporter.success(format!(
+                    "Packed {} into {}",
+                    reporter.provider(name),
+                    bundle.display()
+                ));
+            }
+            Commands::Unpack { file } => {
+                let name = AmarisBundle::unpack(project, file).await?;
+
+                reporter.success(format!(
+                    "Added provider {} from {}",
+                    reporter.provider(&name),
+                    file.display()
+                ));
+            }
+            Commands::Config { action } => match action {
+                ConfigAction::Set { key, value } => {
+                    let value = match key.as_str() {
+                        "network.ca_bundle" => {
+                            let path = std::path::absolute(value)?;
+                            AmarisNetwork::load_ca_bundle(&path).await?;
+                            path.display().to_string()
+                        }
+                        _ => value.clone(),
+                    };
+
+                    AmayaConfig::set(project, key, &value).await?;
+                    reporter.success(format!("Set {} to {}", key, value));
+                }
+                ConfigAction::Get { key } => match AmayaConfig::get(project, key).await? {
+                    Some(value) => reporter.info(value),
+                    None => reporter.info(format!("{} is not set", key)),
+                },
+            },
+            Commands::Search { query } => {
+                let index = AmarisIndex::load(project).await?;
+                let results = AmarisIndex::search(&index, query);
+
+                if results.is_empty() {
+                    reporter.info(format!("No providers matching '{}'", query));
+                }
+
+                for entry in results {
+                    reporter.info(format!(
+                        "- {} {}: {}",
+                        reporter.provider(&entry.name),
+                        entry.version,
+                        entry.description
+                    ));
+                }
+            }
+            Commands::Add {
+                source,
+                from_registry,
+            } => {
+                if let Some(spec) = NpmProviderSpec::parse(source)? {
+                    let (name, version) = AmarisNpmProviders::add(project, &spec).await?;
+                    reporter.success(format!(
+                        "Added provider {} {} from {}",
+                        reporter.provider(&name),
+                        version,
+                        spec.package
+                    ));
+                } else if *from_registry {
+                    let entry = AmarisIndex::add(project, source).await?;
+                    reporter.success(format!(
+                        "Added provider {} {} from the registry",
+                        reporter.provider(&entry.name),
+                        entry.version
+                    ));
+                } else {
+                    let name = AmarisBundle::unpack(project, Path::new(source)).await?;
+                    reporter.success(format!(
+                        "Added provider {} from {}",
+                        reporter.provider(&name),
+                        source
+                    ));
+                }
+            }
+            Commands::Export { file } => {
+                let export = ProjectExport::create(project).await?;
+                export.save(file).await?;
+
+                reporter.success(format!(
+                    "Exported {} {} to {}",
+                    export.providers.len(),
+                    match export.providers.len() {
+                        1 => "provider",
+                        _ => "providers",
+                    },
+                    file.display()
+                ));
+                for line in export.overrides.describe() {
+                    reporter.info(format!("Overrides in effect for {}", line));
+                }
+            }
+            Commands::Import {
+                file,
+                force,
+                allow_dirty,
+                commit,
+            } => {
+                let export = ProjectExport::load(file).await?;
+                let names: Vec<String> = export
+                    .providers
+                    .iter()
+                    .map(|provider| provider.name.clone())
+                    .collect();
+
+                let mut import_registry = AmarisRegistry::new();
+                for provider in export.providers {
+                    import_registry.register(provider.into());
+                }
+
+                let providers: Vec<&dyn AmarisProvider> = import_registry
+                    .resolve_install_order(&names)?
+                    .iter()
+                    .filter_map(|name| import_registry.get_provider(name))
+                    .collect();
+
+                for provider in &providers {
+                    check_installable(project, *provider, *force).await?;
+                }
+
+                let options = InstallOptions {
+                    timeout,
+                    workspace_file: AmarisVisualStudioCodeHandler::find_workspace_file(project),
+                    ..Default::default()
                 };
 
-                if let Some(provider) = registry.get_provider(&config_name) {
-                    provider.check_prerequisites().await?;
-                    provider.install().await?;
+                let planned: Vec<_> = providers
+                    .iter()
+                    .map(|provider| (*provider, options.workspace_file.as_deref()))
+                    .collect();
+                if !*allow_dirty && !confirm_dirty_worktree(project, &planned).await? {
+                    reporter.warn("Aborted.");
+                    return Ok(());
+                }
+
+                for provider in &providers {
+                    install_provider(project, *provider, None, &options)
+                        .await
+                        .map_err(|e| e.context(format!("Failed to import {}", provider.name())))?;
                 }
+
+                reporter.success(format!(
+                    "Imported {} configurations from {}: {}",
+                    providers.len(),
+                    file.display(),
+                    names.join(", ")
+                ));
+
+                commit_changes(project, *commit, "import", &names.join(", "), None).await?;
             }
-            Commands::List => {
-                println!("Available configurations:");
-                for (name, description) in registry.available_configs() {
-                    println!("- {}: {}", name, description);
+            Commands::Info { name, json } => {
+                let Some(provider) = registry.get_provider(name) else {
+                    anyhow::bail!("Unknown configuration '{}'", name);
+                };
+
+                if *json {
+                    let state = AmarisProjectState::load(project).await?;
+                    println!(
+                        "{}",
+                        serde_json::to_string(&ProviderInfo::new(provider, &state))?
+                    );
+                    return Ok(());
+                }
+
+                reporter.info(format!(
+                    "{}: {}",
+                    reporter.provider(provider.name()),
+                    provider.description()
+                ));
+
+                reporter.info(match provider.is_builtin() {
+                    true => "Type: built-in",
+                    false => "Type: user-defined",
+                });
+
+                let metadata = provider.metadata();
+                for (label, value) in [
+                    ("Version", &metadata.version),
+                    ("Author", &metadata.author),
+                    ("Homepage", &metadata.homepage),
+                    ("License", &metadata.license),
+                ] {
+                    if let Some(value) = value {
+                        reporter.info(format!("{}: {}", label, value));
+                    }
+                }
+                if let Some(deprecation) = &metadata.deprecation {
+                    reporter.warn(format!("Deprecated: {}", deprecation.describe()));
+                }
+
+                let lineage = provider.lineage();
+                if !lineage.is_empty() {
+                    reporter.info(format!(
+                        "Extends: {} → {}",
+                        lineage.join(" → "),
+                        provider.name()
+                    ));
+                }
+
+                let dependencies = provider.dependencies();
+                if !dependencies.is_empty() {
+                    reporter.info(format!("Depends on: {}", dependencies.join(", ")));
+                }
+
+                let environment: Vec<String> = redact_env(&provider.environment())
+                    .into_iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                if !environment.is_empty() {
+                    reporter.info(format!("Environment: {}", environment.join(", ")));
+                }
+
+                let packages: Vec<String> = provider
+                    .package_entries()
+                    .into_iter()
+                    .map(|package| match package.scope {
+                        PackageScope::Dev => package.name,
+                        scope => format!("{} ({})", package.name, scope.as_str()),
+                    })
+                    .collect();
+                reporter.info(format!("Packages: {}", packages.join(", ")));
+
+                reporter.info("Configuration:");
+                for config in provider.config_files() {
+                    match &config.source_provider {
+                        Some(source) => {
+                            reporter.info(format!("  - {} (from {})", config.file_location, source))
+                        }
+                        None => reporter.info(format!("  - {}", config.file_location)),
+                    }
+                }
+
+                reporter.info("Scripts:");
+                for script in provider.scripts() {
+                    reporter.info(format!("  - {}: {}", script.name, script.script));
+                }
+
+                let variants = provider.variants();
+                if !variants.is_empty() {
+                    reporter.info("Variants:");
+                    for variant in variants {
+                        match variant.detect.is_empty() {
+                            true => reporter.info(format!("  - {}", variant.name)),
+                            false => reporter.info(format!(
+                                "  - {} (detected by {})",
+                                variant.name,
+                                variant.detect.join(", ")
+                            )),
+                        }
+                    }
                 }
             }
-            Commands::Remove { config } => {
-                if let Some(provider) = registry.get_provider(config) {
-                    provider.remove().await?;
+            Commands::Diff {
+                name,
+                variant,
+                variables,
+            } => {
+                let Some(provider) = registry.get_provider(name) else {
+                    anyhow::bail!("Unknown configuration '{}'", name);
+                };
+                let state = AmarisProjectState::load(project).await?;
+                let variant = match variant {
+                    Some(variant) => Some(variant.clone()),
+                    None => match state
+                        .get(provider.name())
+                        .and_then(|record| record.variant.clone())
+                    {
+                        Some(installed) => Some(installed),
+                        None => select_variant(project, provider, None).await?,
+                    },
+                };
+                let variant_provider = variant
+                    .as_deref()
+                    .map(|variant| provider.with_variant(variant))
+                    .transpose()?;
+                let provider = variant_provider.as_deref().unwrap_or(provider);
+
+                let options = InstallOptions {
+                    variables: variables.iter().cloned().collect(),
+                    workspace_file: state
+                        .workspace_file(project, provider.name())
+                        .or_else(|| AmarisVisualStudioCodeHandler::find_workspace_file(project)),
+                    ..Default::default()
+                };
+                let diff = provider.diff(project, &options).await?;
+                print_provider_diff(&diff);
+
+                if !diff.is_identical() {
+                    std::process::exit(1);
                 }
             }
-            Commands::Doctor => {
-                for (name, _) in registry.available_configs() {
-                    if let Some(provider) = registry.get_provider(&name) {
-                        match provider.check_prerequisites().await {
-                            Ok(_) => println!("All prerequisites met for {}", name),
-                            Err(e) => println!("All prerequisites are not met for {}\n{}", name, e),
+            Commands::Configs { name, cat } => {
+                let Some(provider) = registry.get_provider(name) else {
+                    anyhow::bail!("Unknown configuration '{}'", name);
+                };
+                let configs = provider.config_files();
+
+                if let Some(file_name) = cat {
+                    let Some(config) = configs.iter().find(|config| {
+                        &config.file_name == file_name || &config.file_location == file_name
+                    }) else {
+                        anyhow::bail!("{} has no configuration named '{}'", name, file_name);
+                    };
+
+                    if config.source_from.is_empty() && config.inline_content.is_none() {
+                        anyhow::bail!("{} generates {} without a source", name, file_name);
+                    }
+
+                    print!(
+                        "{}",
+                        AmarisConfigurationHandler::read_source(project, name, config).await?
+                    );
+                    return Ok(());
+                }
+
+                if configs.is_empty() {
+                    reporter.info(format!(
+                        "{} has no configuration sources",
+                        reporter.provider(name)
+                    ));
+                    return Ok(());
+                }
+
+                let root = &project.root;
+                let mut problems = 0;
+
+                reporter.info(format!("Configurations of {}:", reporter.provider(name)));
+                for config in &configs {
+                    reporter.info(format!(
+                        "- {} -> {}",
+                        config.file_name,
+                        root.join(&config.file_location).display()
+                    ));
+
+                    if config.inline_content.is_some() {
+                        reporter.info("    source: inline");
+                        continue;
+                    }
+
+                    // Built-in providers generate their files instead of copying a source
+                    if config.source_from.is_empty() {
+                        reporter.info("    source: generated");
+                        continue;
+                    }
+
+                    let source = AmarisConfigurationHandler::resolve_source(project, name, config)?;
+                    match tokio::fs::read(&source).await {
+                        Ok(content) => reporter.info(format!(
+                            "    source: {} ({} bytes, {})",
+                            source.display(),
+                            content.len(),
+                            sha256_integrity(&content)
+                        )),
+                        Err(_) => {
+                            reporter.error(format!("    source: {} (missing)", source.display()));
+                            problems += 1;
                         }
                     }
                 }
+
+                if problems > 0 {
+                    anyhow::bail!("{} source(s) missing", problems);
+                }
             }
-            Commands::Init => {
-                AmarisInitialConfigHandler::ensure_dirs().await?;
-                AmarisInitialConfigHandler::create_initial_config().await?;
+            Commands::RenameProvider { old, new } => {
+                let path = rename_provider(project, registry, old, new).await?;
+
+                reporter.success(format!(
+                    "Renamed {} to {} in {}",
+                    old,
+                    reporter.provider(new),
+                    path.display()
+                ));
+                reporter.info(format!(
+                    "'{}' stays an alias until it is removed from `aliases`",
+                    old
+                ));
             }
-        }
-        Ok(())
-    }
+            Commands::Edit { name, config } => {
+                let provider_path = provider_file(project, registry, name).await?;
+                let path = match config {
+                    Some(file) => AmarisPathHandler::ensure_config_dir(project)
+                        .await?
+                        .join(name)
+                        .join(file),
+                    None => provider_path.clone(),
+                };
+
+                if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+                    anyhow::bail!(
+                        "Cannot open an editor without a terminal, edit {} directly",
+                        path.display()
+                    );
+                }
+
+                loop {
+                    open_in_editor(&path).await?;
+
+                    let problems =
+                        match DynamicProvider::load_from_file(project, &provider_path).await {
+                            Ok(mut provider) => {
+                                check_provider_sources(project, &mut provider, false).await?
+                            }
+                            Err(e) => {
+                                reporter.error(e);
+                                1
+                            }
+                        };
+
+                    if problems == 0 {
+                        reporter.success(format!("{} is valid", provider_path.display()));
+                        break;
+                    }
+
+                    let reopen = Confirm::new("Re-open the file to fix it?")
+                        .with_default(true)
+                        .prompt()?;
+                    if !reopen {
+                        anyhow::bail!("{} problem(s) found", problems);
+                    }
+                }
+            }
+            Commands::Status => AmarisStatus::run(project, registry).await?,
+            Commands::Watch { name, variables } => {
+                let options = InstallOptions {
+                    variables: variables.iter().cloned().collect(),
+                    timeout,
+                    phases: vec![InstallPhase::Configs],
+                    workspace_file: AmarisVisualStudioCodeHandler::find_workspace_file(project),
+                    ..Default::default()
+                };
+
+                watch_provider(project, registry, name, &options).await?;
+            }
+            Commands::TestProvider {
+                name,
+                fixture,
+                no_install,
+                keep,
+                variant,
+                variables,
+            } => {
+                let mut phases = InstallPhase::ALL.to_vec();
+                if *no_install {
+                    phases.retain(|phase| *phase != InstallPhase::Packages);
+                }
+
+                let options = InstallOptions {
+                    variables: variables.iter().cloned().collect(),
+                    timeout,
+                    phases,
+                    ..Default::default()
+                };
+
+                test_provider(
+                    registry,
+                    name,
+                    fixture.as_deref(),
+                    variant.as_deref(),
+                    *keep,
+                    &options,
+                )
+                .await?;
+            }
+            Commands::Which { file } => {
+                let (installed, available) = file_owners(project, registry, file).await?;
+
+                if installed.is_empty() && available.is_empty() {
+                    reporter.info("not managed by amaya.");
+                    return Ok(());
+                }
+
+                for name in &installed {
+                    reporter.success(reporter.provider(name));
+                }
+                for name in &available {
+                    reporter.info(format!(
+                        "{} (not installed in this project)",
+                        reporter.provider(name)
+                    ));
+                }
+            }
+            Commands::CheckUpdates { name, provider } => {
+                if AmarisNetwork::global().is_offline() {
+                    reporter.warn("Warning: --offline is set, not checking for package updates");
+                    return Ok(());
+                }
+
+                let provider = name.as_ref().or(provider.as_ref());
+                if let Some(provider) = provider
+                    && registry.get_provider(provider).is_none()
+                {
+                    anyhow::bail!("Unknown configuration '{}'", provider);
+                }
+                let provider = provider.map(|name| {
+                    AmarisAliases::resolve(name)
+                        .map(String::from)
+                        .unwrap_or_else(|| name.clone())
+                });
+
+                let updates = AmarisUpdates::check(project, registry, provider.as_deref()).await?;
+                if updates.is_empty() {
+                    reporter.info("No packages of installed configurations in package.json.");
+                    return Ok(());
+                }
+
+                print_updates(&updates);
+            }
+            Commands::UpdateProviders { names, check } => {
+                if AmarisNetwork::global().is_offline() {
+                    reporter.warn("Warning: --offline is set, not checking for provider updates");
+                    return Ok(());
+                }
+
+                let updates = AmarisNpmProviders::check(project, names).await?;
+                if updates.is_empty() {
+                    reporter.info("No providers were added from npm.");
+                    return Ok(());
+                }
+
+                update_providers(project, &updates, !names.is_empty(), *check).await?;
+            }
+            Commands::Deps { output, prune } => {
+                let report = AmarisDeps::report(project, registry).await?;
+
+                if *output == OutputFormat::Json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                    return Ok(());
+                }
+
+                print_deps(&report);
+                if *prune {
+                    prune_deps(project, registry, &report, timeout).await?;
+                }
+            }
+            Commands::Ci => {
+                let report = AmarisCi::run(project, registry).await?;
+                println!("{}", serde_json::to_string_pretty(&report)?);
+
+                if !report.success {
+                    let mut problems = 0;
+                    for provider in &report.providers {
+                        for problem in provider.problems() {
+                            reporter.error(format!("{}: {}", provider.name, problem));
+                            problems += 1;
+                        }
+                    }
+
+                    anyhow::bail!("{} problem(s) found", problems);
+                }
+            }
+            Commands::Doctor => AmarisDoctor::run(project, registry).await?,
+            Commands::Selftest => {
+                let report = AmarisSelfTest::run().await?;
+
+                if let Some(step) = report.steps.iter().find(|step| step.error.is_some()) {
+                    anyhow::bail!("Self test failed at {}", step.name);
+                }
+                reporter.success(format!("All {} steps passed", report.steps.len()));
+            }
+            Commands::Init {
+                providers,
+                defaults,
+            } => {
+                let providers = providers
+                    .as_ref()
+                    .map(|providers| providers.iter().map(String::as_str).collect());
+
+                run_init(project, providers, *defaults).await?;
+            }
+            Commands::Completions { shell, install } => {
+                let providers: Vec<String> = registry
+                    .available_configs_sorted()
+                    .into_iter()
+                    .map(|(name, _)| name.to_string())
+                    .collect();
+                let mut groups: Vec<String> = AmayaConfig::load(project)
+                    .await?
+                    .groups
+                    .into_keys()
+                    .collect();
+                groups.sort();
+
+                match install {
+                    true => {
+                        let Some(shell) = shell.or_else(Shell::from_env) else {
+                            anyhow::bail!("Could not detect the shell, pass it explicitly");
+                        };
+                        let path = AmarisCompletions::install(shell, &providers, &groups).await?;
+                        reporter.success(format!(
+                            "Installed {} completions to {}",
+                            shell,
+                            path.display()
+                        ));
+                        if let Some(hint) = AmarisCompletions::activation_hint(shell) {
+                            reporter.info(format!("To load them, {}", hint));
+                        }
+                    }
+                    false => {
+                        if let Some(shell) = shell {
+                            print!(
+                                "{}",
+                                AmarisCompletions::generate(*shell, &providers, &groups)
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fails when the provider's prerequisites are not met or a competing tool is
+/// configured, `force` downgrades the latter to a warning.
+pub async fn check_installable(
+    project: &ProjectContext,
+    provider: &dyn AmarisProvider,
+    force: bool,
+) -> anyhow::Result<()> {
+    provider.check_prerequisites(project).await?;
+
+    match provider.check_conflicts(project).await {
+        Ok(_) => {}
+        Err(e) if force => Reporter::global().warn(format!("Warning: {}, continuing anyway", e)),
+        Err(e) => return Err(e.into()),
+    }
+
+    let detection = provider.detect(&project.reload().await?).await?;
+    if detection.is_present()
+        && !AmarisProjectState::load(project)
+            .await?
+            .is_installed(provider.name())
+    {
+        let mut found = detection.packages;
+        found.extend(detection.files);
+
+        Reporter::global().warn(format!(
+            "Warning: {} appears to be set up already ({}), existing files will be updated",
+            provider.name(),
+            found.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Installs `name` and the providers it depends on into a new test project
+/// and checks the result. The project is deleted once the test passes unless
+/// `keep` is set, and kept when a step fails.
+async fn test_provider(
+    registry: &AmarisRegistry,
+    name: &str,
+    fixture: Option<&Path>,
+    variant: Option<&str>,
+    keep: bool,
+    options: &InstallOptions,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+    let order = registry.resolve_install_order(&[name.to_string()])?;
+
+    let dir = AmarisPreview::create(name).await?;
+    reporter.info(format!("Created test project {}", dir.display()));
+
+    let result = run_provider_test(registry, name, &order, fixture, &dir, variant, options).await;
+
+    if let Err((step, e)) = result {
+        reporter.error(format!("Test project kept at {}", dir.display()));
+        return Err(e.context(format!("{} failed while {}", name, step)));
+    }
+
+    if keep {
+        reporter.success(format!(
+            "{} passed, test project kept at {}",
+            name,
+            dir.display()
+        ));
+    } else {
+        tokio::fs::remove_dir_all(&dir).await?;
+        reporter.success(format!("{} passed", name));
+    }
+
+    Ok(())
+}
+
+/// The steps of [`test_provider`] inside the test project, failing with the
+/// step that failed.
+async fn run_provider_test(
+    registry: &AmarisRegistry,
+    name: &str,
+    order: &[String],
+    fixture: Option<&Path>,
+    dir: &Path,
+    requested_variant: Option<&str>,
+    options: &InstallOptions,
+) -> Result<(), (String, anyhow::Error)> {
+    if let Some(fixture) = fixture {
+        AmarisPreview::copy_fixture(fixture, dir)
+            .map_err(|e| ("copying the fixture".to_string(), e.into()))?;
+    }
+    let project = &ProjectContext::load(dir.to_path_buf())
+        .await
+        .map_err(|e| ("reading the project".to_string(), e.into()))?;
+
+    for member in order {
+        let step = format!("installing {}", member);
+        let failed = |e: anyhow::Error| (step.clone(), e);
+
+        let Some(provider) = registry.get_provider(member) else {
+            return Err(failed(anyhow::anyhow!(
+                "Unknown configuration '{}'",
+                member
+            )));
+        };
+
+        let requested = requested_variant.filter(|_| member == name);
+        let variant = select_variant(project, provider, requested)
+            .await
+            .map_err(failed)?;
+        let variant_provider = variant
+            .as_deref()
+            .map(|variant| provider.with_variant(variant))
+            .transpose()
+            .map_err(|e| failed(e.into()))?;
+        let provider = variant_provider.as_deref().unwrap_or(provider);
+
+        check_installable(project, provider, false)
+            .await
+            .map_err(failed)?;
+        install_provider(project, provider, variant.as_deref(), options)
+            .await
+            .map_err(failed)?;
+    }
+
+    let checking = |e: anyhow::Error| ("checking the project".to_string(), e);
+    let report = AmarisCi::run(project, registry)
+        .await
+        .map_err(|e| checking(e.into()))?;
+    if !report.success {
+        let reporter = Reporter::global();
+        let mut problems = 0;
+        for provider in &report.providers {
+            for problem in provider.problems() {
+                reporter.error(format!("{}: {}", provider.name, problem));
+                problems += 1;
+            }
+        }
+
+        return Err(checking(anyhow::anyhow!("{} problem(s) found", problems)));
+    }
+
+    Ok(())
+}
+
+/// Warns when `name` is deprecated and asks whether to install it anyway or,
+/// when it names one, its replacement. Returns the provider to install,
+/// `None` when the install is cancelled.
+pub async fn confirm_deprecated(
+    registry: &AmarisRegistry,
+    name: &str,
+    allow_deprecated: bool,
+) -> anyhow::Result<Option<String>> {
+    let Some(deprecation) = registry
+        .get_provider(name)
+        .and_then(|provider| provider.metadata().deprecation)
+    else {
+        return Ok(Some(name.to_string()));
+    };
+
+    let reporter = Reporter::global();
+    reporter.warn(format!(
+        "Warning: {} is deprecated, {}",
+        reporter.provider(name),
+        deprecation.describe()
+    ));
+
+    if allow_deprecated {
+        return Ok(Some(name.to_string()));
+    }
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "{} is deprecated, pass --allow-deprecated to install it anyway",
+            name
+        );
+    }
+
+    let replacement = deprecation
+        .replaced_by
+        .filter(|replacement| registry.get_provider(replacement).is_some());
+    let Some(replacement) = replacement else {
+        let proceed = AmarisStream::confirm(
+            Confirm::new(&format!("Install {} anyway?", name))
+                .with_default(false)
+                .with_help_message("Use --allow-deprecated to skip this prompt"),
+        )?;
+        return Ok(proceed.then(|| name.to_string()));
+    };
+
+    let instead = format!("Install {} instead", replacement);
+    let anyway = format!("Install {} anyway", name);
+    let choice = AmarisStream::select(
+        Select::new(
+            &format!("{} is deprecated:", name),
+            vec![instead.clone(), anyway, "Cancel".to_string()],
+        )
+        .with_help_message("Use --allow-deprecated to skip this prompt"),
+    )?;
+
+    Ok(match choice {
+        choice if choice == instead => Some(replacement),
+        choice if choice == "Cancel" => None,
+        _ => Some(name.to_string()),
+    })
+}
+
+/// Picks the variant to install: the requested one, the one detected from the
+/// project, or one chosen at a prompt. `None` for providers without variants.
+pub async fn select_variant(
+    project: &ProjectContext,
+    provider: &dyn AmarisProvider,
+    requested: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    if let Some(requested) = requested {
+        return Ok(Some(requested.to_string()));
+    }
+
+    let variants = provider.variants();
+    if variants.is_empty() {
+        return Ok(None);
+    }
+
+    if let Some(detected) = provider.detect_variant(project).await? {
+        let reporter = Reporter::global();
+        reporter.info(format!(
+            "Detected variant {} of {}",
+            detected,
+            reporter.provider(provider.name())
+        ));
+        return Ok(Some(detected));
+    }
+
+    let names: Vec<String> = variants.into_iter().map(|variant| variant.name).collect();
+    let selection = AmarisStream::select(
+        Select::new(&format!("Select a variant of {}:", provider.name()), names)
+            .with_help_message("Use --variant to skip this prompt"),
+    )?;
+
+    Ok(Some(selection))
+}
+
+/// The options `install` and `upgrade` share: the variables, environment and
+/// editors given on the command line, with `backups` and `verify` taken from
+/// the global config.
+pub fn install_options(
+    settings: &AmayaConfig,
+    timeout: Duration,
+    variables: &[(String, String)],
+    env: &[(String, String)],
+    editors: &Option<Vec<Editor>>,
+) -> InstallOptions {
+    InstallOptions {
+        variables: variables.iter().cloned().collect(),
+        extra_env: env.iter().cloned().collect(),
+        timeout,
+        editors: editors.clone(),
+        backups: settings.backups,
+        verify: settings.verify,
+        ..Default::default()
+    }
+}
+
+pub async fn install_provider(
+    project: &ProjectContext,
+    provider: &dyn AmarisProvider,
+    variant: Option<&str>,
+    options: &InstallOptions,
+) -> anyhow::Result<()> {
+    // Scripts the provider replaces are put back when it is removed
+    let scripts = provider.scripts();
+    let mut original_scripts = BTreeMap::new();
+    if options.applies(InstallPhase::Scripts) && !scripts.is_empty() {
+        for (name, content) in AmarisPackageJsonHandler::get_scripts(project).await? {
+            if scripts.iter().any(|script| {
+                script.name == name
+                    && script.remove_on_uninstall
+                    && script.original_content.is_none()
+            }) {
+                original_scripts.insert(name, content);
+            }
+        }
+    }
+
+    AmarisEvents::start(project, provider.name());
+    AmarisContributions::start(project);
+    provider.install(project, options).await?;
+    let summary = InstallSummary::finish(project).await?;
+
+    let mut state = AmarisProjectState::load(project).await?;
+    let previous = state.get(provider.name()).cloned();
+
+    // Only recorded when VS Code settings went there, so removal finds them
+    let mut workspace_file = options
+        .workspace_file
+        .as_ref()
+        .filter(|path| {
+            provider
+                .planned_writes(project, Some(path))
+                .contains(&project.relative(path))
+        })
+        .map(|path| project.relative(path).display().to_string());
+    let mut files = managed_files(project, provider);
+    let mut phases = options.phases.clone();
+
+    // A partial install adds to the phases applied by an earlier one
+    if !options.applies(InstallPhase::Configs) {
+        (workspace_file, files) = previous
+            .as_ref()
+            .filter(|previous| previous.phases.contains(&InstallPhase::Configs))
+            .map(|previous| (previous.workspace_file.clone(), previous.files.clone()))
+            .unwrap_or_default();
+    }
+    let mut contributions = vec![];
+    if let Some(previous) = previous {
+        // Installed again, the scripts found now are the provider's own
+        if previous.phases.contains(&InstallPhase::Scripts) {
+            original_scripts = previous.original_scripts;
+        }
+        phases.extend(previous.phases);
+        contributions = previous.contributions;
+    }
+    merge_contributions(&mut contributions, AmarisContributions::take(project));
+    let phases = InstallPhase::ALL
+        .into_iter()
+        .filter(|phase| phases.contains(phase))
+        .collect();
+
+    state.record_install(InstallRecord {
+        name: provider.name().to_string(),
+        files,
+        variant: variant.map(String::from),
+        version: provider.metadata().version,
+        workspace_file,
+        phases,
+        contributions,
+        package_scopes: provider
+            .package_entries()
+            .into_iter()
+            .filter(|package| package.scope != PackageScope::Dev)
+            .map(|package| (package.name, package.scope))
+            .collect(),
+        original_scripts,
+        ..Default::default()
+    });
+    state.save(project).await?;
+
+    summary.render();
+
+    if options.verify && options.applies(InstallPhase::Configs) {
+        for problem in provider.verify(project, options).await? {
+            Reporter::global().warn(format!("Warning: {}", problem));
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets up `~/.amaya` when amaya runs for the first time from a terminal,
+/// unless the command is `amaya init` itself.
+pub async fn first_run(project: &ProjectContext, command: &Commands) -> anyhow::Result<()> {
+    if AmarisPathHandler::is_initialized(project)
+        || matches!(
+            command,
+            Commands::Init { .. } | Commands::Completions { .. } | Commands::Selftest
+        )
+        || !std::io::stdin().is_terminal()
+        || !std::io::stdout().is_terminal()
+    {
+        return Ok(());
+    }
+
+    let setup = Confirm::new("No amaya configuration found, set it up now?")
+        .with_default(true)
+        .prompt()?;
+
+    match setup {
+        true => run_init(project, None, false).await,
+        false => Ok(()),
+    }
+}
+
+/// Asks which starter providers to create, which package manager they use
+/// and the install defaults, then writes the providers and stores the answers.
+/// With `defaults` nothing is asked and the global config is left alone.
+async fn run_init(
+    project: &ProjectContext,
+    providers: Option<Vec<&str>>,
+    defaults: bool,
+) -> anyhow::Result<()> {
+    let mut init = InitConfig::default();
+
+    if !defaults {
+        init.providers = match providers {
+            Some(providers) => providers,
+            None => {
+                let options: Vec<&str> = PROVIDER_TEMPLATES
+                    .iter()
+                    .map(|template| template.description)
+                    .collect();
+                let selected: Vec<usize> = PROVIDER_TEMPLATES
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, template)| template.default)
+                    .map(|(index, _)| index)
+                    .collect();
+
+                MultiSelect::new("Select the providers to set up:", options)
+                    .with_default(&selected)
+                    .prompt()?
+                    .iter()
+                    .filter_map(|selection| {
+                        PROVIDER_TEMPLATES
+                            .iter()
+                            .find(|template| template.description == *selection)
+                            .map(|template| template.name)
+                    })
+                    .collect()
+            }
+        };
+        init.validate()?;
+
+        init.package_manager = Select::new("Default package manager:", PACKAGE_MANAGERS.to_vec())
+            .prompt()?
+            .to_string();
+        init.backups = Confirm::new("Back up existing files before a provider overwrites them?")
+            .with_default(false)
+            .prompt()?;
+        init.verify = Confirm::new("Verify configuration files after every install?")
+            .with_default(false)
+            .prompt()?;
+    } else if let Some(providers) = providers {
+        init.providers = providers;
+    }
+
+    AmarisInitialConfigHandler::ensure_dirs(project, &init).await?;
+    AmarisInitialConfigHandler::create_initial_config(project, &init).await?;
+
+    if !defaults {
+        AmarisInitialConfigHandler::save_settings(project, &init).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes an installed provider, handing the settings it shares with other
+/// providers back to them, and drops it from amaya.json.
+async fn uninstall_provider(
+    project: &ProjectContext,
+    provider: &dyn AmarisProvider,
+    options: &InstallOptions,
+) -> anyhow::Result<()> {
+    let state = AmarisProjectState::load(project).await?;
+    let layers = SettingLayers::capture(project, &state, provider.name()).await?;
+    provider.remove(project, options).await?;
+
+    let mut state = AmarisProjectState::load(project).await?;
+    layers.restore(project, &mut state).await?;
+    state.record_remove(provider.name());
+    state.save(project).await?;
+
+    Ok(())
+}
+
+/// Removes `from` and installs `to` as one [`InstallTransaction`], then
+/// verifies the files `to` wrote.
+async fn switch_providers(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    from: &str,
+    to: &str,
+    yes: bool,
+    allow_dirty: bool,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+    let state = AmarisProjectState::load(project).await?;
+
+    let Some(record) = state.get(from) else {
+        anyhow::bail!("{} is not installed in this project", from);
+    };
+    if state.is_installed(to) {
+        anyhow::bail!("{} is already installed in this project", to);
+    }
+    let Some(from_provider) = registry.get_provider(from) else {
+        anyhow::bail!("Unknown configuration '{}'", from);
+    };
+    let Some(to_provider) = registry.get_provider(to) else {
+        anyhow::bail!("Unknown configuration '{}'", to);
+    };
+
+    let from_variant = record
+        .variant
+        .as_deref()
+        .map(|variant| from_provider.with_variant(variant))
+        .transpose()?;
+    let from_provider = from_variant.as_deref().unwrap_or(from_provider);
+
+    let variant = select_variant(project, to_provider, None).await?;
+    let to_variant = variant
+        .as_deref()
+        .map(|variant| to_provider.with_variant(variant))
+        .transpose()?;
+    let to_provider = to_variant.as_deref().unwrap_or(to_provider);
+
+    // Conflicts with `from` are what the switch resolves
+    to_provider.check_prerequisites(project).await?;
+
+    let to_files = managed_files(project, to_provider);
+    let from_packages = match record.phases.contains(&InstallPhase::Packages) {
+        true => from_provider.packages(),
+        false => vec![],
+    };
+    let to_packages = to_provider.packages();
+    let changes = [
+        (
+            "files removed",
+            record
+                .files
+                .iter()
+                .filter(|file| !to_files.contains(file))
+                .cloned()
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "files added",
+            to_files
+                .iter()
+                .filter(|file| !record.files.contains(file))
+                .cloned()
+                .collect(),
+        ),
+        (
+            "packages removed",
+            from_packages
+                .iter()
+                .filter(|package| !to_packages.contains(package))
+                .cloned()
+                .collect(),
+        ),
+        (
+            "packages added",
+            to_packages
+                .iter()
+                .filter(|package| !from_packages.contains(package))
+                .cloned()
+                .collect(),
+        ),
+    ];
+
+    reporter.info(format!(
+        "Switching from {} to {}:",
+        reporter.provider(from),
+        reporter.provider(to)
+    ));
+    for (label, items) in changes.iter().filter(|(_, items)| !items.is_empty()) {
+        reporter.info(format!("  {:<18}{}", label, items.join(", ")));
+    }
+
+    if !yes && !Confirm::new("Continue?").with_default(true).prompt()? {
+        reporter.warn("Aborted.");
+        return Ok(());
+    }
+
+    let remove_options = InstallOptions {
+        timeout,
+        workspace_file: state.workspace_file(project, from),
+        phases: record.phases.clone(),
+        package_scopes: record.package_scopes.clone(),
+        original_scripts: record.original_scripts.clone(),
+        installed_files: record.files.clone(),
+        ..Default::default()
+    };
+    let install_options = InstallOptions {
+        timeout,
+        workspace_file: AmarisVisualStudioCodeHandler::find_workspace_file(project),
+        ..Default::default()
+    };
+    let remove_workspace = remove_options.workspace_file.as_deref();
+    let install_workspace = install_options.workspace_file.as_deref();
+
+    if !allow_dirty
+        && !confirm_dirty_worktree(
+            project,
+            &[
+                (from_provider, remove_workspace),
+                (to_provider, install_workspace),
+            ],
+        )
+        .await?
+    {
+        reporter.warn("Aborted.");
+        return Ok(());
+    }
+
+    let mut transaction = InstallTransaction::new();
+    transaction.queue_remove(from);
+    transaction.queue_install(to);
+
+    let mut paths = from_provider.planned_writes(project, remove_workspace);
+    paths.extend(from_provider.managed_files(project));
+    paths.extend(to_provider.planned_writes(project, install_workspace));
+    paths.extend(to_provider.managed_files(project));
+    transaction.begin(project, paths).await?;
+
+    for step in transaction.steps().to_vec() {
+        let result = match &step {
+            TransactionStep::Remove(_) => {
+                uninstall_provider(project, from_provider, &remove_options).await
+            }
+            TransactionStep::Install(_) => {
+                install_provider(project, to_provider, variant.as_deref(), &install_options).await
+            }
+        };
+
+        if let Err(e) = result {
+            reporter.warn("Switch failed, restoring the previous state...");
+            transaction.rollback(project).await?;
+            reporter.warn(
+                "Warning: package.json and the lockfile were restored, run your package manager's install to bring node_modules back in line",
+            );
+
+            return Err(e.context(format!("Failed to switch from {} to {}", from, to)));
+        }
+    }
+
+    let state = AmarisProjectState::load(project).await?;
+    let verify_options = InstallOptions {
+        workspace_file: state.workspace_file(project, to),
+        ..Default::default()
+    };
+    let problems = to_provider.verify(project, &verify_options).await?;
+
+    if problems.is_empty() {
+        reporter.success(format!(
+            "Switched from {} to {}, all files verified",
+            reporter.provider(from),
+            reporter.provider(to)
+        ));
+    } else {
+        for problem in &problems {
+            reporter.warn(format!("Warning: {}", problem));
+        }
+        reporter.warn(format!(
+            "Switched from {} to {}, {} file(s) did not verify",
+            reporter.provider(from),
+            reporter.provider(to),
+            problems.len()
+        ));
+    }
+
+    Ok(())
+}
+
+async fn list_providers(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    filter: &ListArgs,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+    let state = AmarisProjectState::load(project).await?;
+
+    if filter.installed {
+        reporter.info("Installed configurations:");
+    } else if filter.not_installed {
+        reporter.info("Configurations not installed:");
+    } else {
+        reporter.info("Available configurations:");
+    }
+
+    for status in registry.list_with_status(&state) {
+        if (filter.installed && !status.installed) || (filter.not_installed && status.installed) {
+            continue;
+        }
+
+        let provider = registry.get_provider(&status.name);
+        let label = match status.version.as_ref().filter(|_| filter.verbose) {
+            Some(version) => format!("{} {}", reporter.provider(&status.name), version),
+            None => reporter.provider(&status.name),
+        };
+        let description = match provider.is_some_and(|provider| provider.is_builtin()) {
+            true => format!("{} (built-in)", status.description),
+            false => status.description.clone(),
+        };
+
+        if let Some(deprecation) = provider.and_then(|provider| provider.metadata().deprecation) {
+            reporter.warn(format!(
+                "- {}: {} (deprecated, {})",
+                label,
+                description,
+                deprecation.describe()
+            ));
+            continue;
+        }
+
+        match status.installed_at {
+            Some(installed_at) if filter.installed => reporter.info(format!(
+                "- {}: {} (installed {})",
+                label,
+                description,
+                installed_at.format("%Y-%m-%d %H:%M UTC")
+            )),
+            Some(_) => reporter.info(format!("- {}: {} (installed)", label, description)),
+            None => reporter.info(format!("- {}: {}", label, description)),
+        }
+    }
+
+    let config = AmayaConfig::load(project).await?;
+    if !filter.installed && !filter.not_installed && !config.groups.is_empty() {
+        let mut groups: Vec<&String> = config.groups.keys().collect();
+        groups.sort();
+
+        reporter.info("Groups:");
+        for group in groups {
+            let members = config.resolve_group(group)?.unwrap_or_default();
+            reporter.info(format!(
+                "- {}: {}",
+                reporter.provider(group),
+                members.join(", ")
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies the configurations of `name` once, then again after every change
+/// to its provider file or sources until Ctrl-C is pressed.
+/// Prints what installing would do to each file and script, with a unified
+/// diff of the files that would be overwritten.
+fn print_provider_diff(diff: &ProviderDiff) {
+    let reporter = Reporter::global();
+
+    for file in &diff.files {
+        match file.change {
+            ConfigChange::Identical => {
+                reporter.info(format!("= {} (identical)", file.path.display()))
+            }
+            ConfigChange::Created => {
+                reporter.success(format!("+ {} (created)", file.path.display()))
+            }
+            ConfigChange::Overwritten => {
+                reporter.warn(format!("~ {} (overwritten)", file.path.display()));
+                print_unified_diff(&file.path, &file.current, &file.expected);
+            }
+        }
+    }
+
+    for script in &diff.scripts {
+        match &script.current {
+            Some(current) => reporter.warn(format!(
+                "~ script {}: {:?} -> {:?}",
+                script.name, current, script.expected
+            )),
+            None => reporter.success(format!("+ script {}: {:?}", script.name, script.expected)),
+        }
+    }
+
+    if diff.is_identical() {
+        reporter.success("Installing would not change anything.");
+    }
+}
+
+async fn watch_provider(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    name: &str,
+    options: &InstallOptions,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+    let path = provider_file(project, registry, name).await?;
+    let config_dir = AmarisPathHandler::ensure_config_dir(project)
+        .await?
+        .join(name);
+    tokio::fs::create_dir_all(&config_dir).await?;
+
+    let mut watcher = AmarisWatcher::new(&path, &config_dir)?;
+    reporter.info(format!(
+        "Watching {} and {}, press Ctrl-C to stop",
+        path.display(),
+        config_dir.display()
+    ));
+
+    // Created once, so an interrupt while applying is not missed
+    let mut interrupt = std::pin::pin!(tokio::signal::ctrl_c());
+    loop {
+        if let Err(e) = apply_watched(project, &path, options).await {
+            reporter.error(format!("Error: {:#}", e));
+            reporter.info("Waiting for the next change...");
+        }
+
+        let changed = tokio::select! {
+            _ = &mut interrupt => break,
+            change = watcher.next_change() => match change {
+                Some(paths) => paths,
+                None => break,
+            },
+        };
+
+        let changed: Vec<String> = changed
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        reporter.info(format!("Changed: {}", changed.join(", ")));
+    }
+
+    reporter.info("Stopped watching.");
+    Ok(())
+}
+
+/// Validates the provider in `path` and writes its configurations, printing
+/// how each file changed. The variant installed in the project is kept.
+async fn apply_watched(
+    project: &ProjectContext,
+    path: &Path,
+    options: &InstallOptions,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+    let mut provider = DynamicProvider::load_from_file(project, path).await?;
+    if check_provider_sources(project, &mut provider, false).await? > 0 {
+        anyhow::bail!("{} has problems with its sources", provider.name);
+    }
+
+    let variant = AmarisProjectState::load(project)
+        .await?
+        .get(&provider.name)
+        .and_then(|record| record.variant.clone());
+    let provider: Box<dyn AmarisProvider> = provider.into();
+    let provider = match &variant {
+        Some(variant) => provider.with_variant(variant)?,
+        None => provider,
+    };
+
+    let files = provider.planned_writes(project, options.workspace_file.as_deref());
+    let mut before = vec![];
+    for file in &files {
+        before.push(
+            tokio::fs::read_to_string(project.path(file))
+                .await
+                .unwrap_or_default(),
+        );
+    }
+
+    provider.install(project, options).await?;
+
+    let mut unchanged = true;
+    for (file, before) in files.iter().zip(before) {
+        let after = tokio::fs::read_to_string(project.path(file))
+            .await
+            .unwrap_or_default();
+        if after != before {
+            print_diff(file, &before, &after);
+            unchanged = false;
+        }
+    }
+    if unchanged {
+        reporter.info("No changes.");
+    }
+
+    Ok(())
+}
+
+/// Checks that every configuration source of `provider` exists and matches
+/// its `integrity`, or records fresh hashes with `update_hashes`. Returns the
+/// number of problems reported.
+pub async fn check_provider_sources(
+    project: &ProjectContext,
+    provider: &mut DynamicProvider,
+    update_hashes: bool,
+) -> anyhow::Result<usize> {
+    let reporter = Reporter::global();
+    let file = provider
+        .source
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    let name = provider.name.clone();
+    let mut problems = 0;
+    let mut changed = false;
+
+    let entries = provider.configuration.iter_mut().chain(
+        provider
+            .variants
+            .iter_mut()
+            .flat_map(|variant| variant.configuration.iter_mut()),
+    );
+
+    for config in entries.filter(|config| config.inline_content.is_none()) {
+        let source = AmarisConfigurationHandler::resolve_source(project, &name, config)?;
+        let Ok(content) = tokio::fs::read(&source).await else {
+            reporter.error(format!("{}: source {} not found", file, source.display()));
+            problems += 1;
+            continue;
+        };
+
+        let actual = sha256_integrity(&content);
+        match &config.integrity {
+            _ if update_hashes => {
+                changed |= config.integrity.as_ref() != Some(&actual);
+                config.integrity = Some(actual);
+            }
+            Some(expected) if *expected != actual => {
+                reporter.error(format!(
+                    "{}: integrity mismatch for {}, expected {} but found {}",
+                    file,
+                    source.display(),
+                    expected,
+                    actual
+                ));
+                problems += 1;
+            }
+            _ => {}
+        }
+    }
+
+    if changed {
+        provider.save().await?;
+        reporter.success(format!("Updated hashes in {}", file));
+    }
+
+    Ok(problems)
+}
+
+/// The file in the provider directory that defines `name`.
+async fn provider_file(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    name: &str,
+) -> anyhow::Result<PathBuf> {
+    let provider = DynamicProvider::read_all(project, None)
+        .await?
+        .into_iter()
+        .find(|provider| provider.name == name);
+
+    match provider.and_then(|provider| provider.source) {
+        Some(path) => Ok(path),
+        None if registry.get_provider(name).is_some() => {
+            anyhow::bail!("{} is built in and has no provider file", name)
+        }
+        None => anyhow::bail!("Unknown configuration '{}'", name),
+    }
+}
+
+/// Finds the provider file defining `name`, or named after it when it
+/// cannot be parsed, without loading the other providers.
+async fn provider_file_by_name(project: &ProjectContext, name: &str) -> anyhow::Result<PathBuf> {
+    for file in DynamicProvider::files(project, None).await? {
+        let content = AmarisFileHandler::read_text(&file)
+            .await
+            .unwrap_or_default();
+        let defines = DynamicProvider::from_content(&content, &file)
+            .is_ok_and(|provider| provider.name == name);
+
+        if defines || file.file_stem().is_some_and(|stem| stem == name) {
+            return Ok(file);
+        }
+    }
+
+    anyhow::bail!("No provider file defines '{}'", name)
+}
+
+/// Checks a provider file against the provider schema, then its fields and
+/// configuration sources, printing each problem and returning how many there were.
+async fn validate_provider_file(project: &ProjectContext, file: &Path) -> anyhow::Result<usize> {
+    let reporter = Reporter::global();
+    let report = |message: String| reporter.error(format!("{}: {}", file.display(), message));
+
+    let content = match AmarisFileHandler::read_text(file).await {
+        Ok(content) => content,
+        Err(e) => {
+            report(e.to_string());
+            return Ok(1);
+        }
+    };
+
+    let schema_issues = match DynamicProvider::schema_issues(&content, file) {
+        Ok(issues) => issues,
+        Err(e) => {
+            report(e.to_string());
+            return Ok(1);
+        }
+    };
+    for issue in &schema_issues {
+        report(issue.to_string());
+    }
+
+    let mut provider = match DynamicProvider::from_content(&content, file) {
+        Ok(provider) => provider,
+        // The schema already explains why it does not parse
+        Err(_) if !schema_issues.is_empty() => return Ok(schema_issues.len()),
+        Err(e) => {
+            report(e.to_string());
+            return Ok(1);
+        }
+    };
+    provider.source = Some(file.to_path_buf());
+
+    let issues = provider.validate();
+    for issue in &issues {
+        report(issue.to_string());
+    }
+    if !schema_issues.is_empty() || !issues.is_empty() {
+        return Ok(schema_issues.len() + issues.len());
+    }
+
+    check_provider_sources(project, &mut provider, false).await
+}
+
+/// Renames the provider file of `old` to `new`, records `old` as an alias and
+/// moves its configuration directory along. Returns the new provider file.
+async fn rename_provider(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    old: &str,
+    new: &str,
+) -> anyhow::Result<PathBuf> {
+    let path = provider_file(project, registry, old).await?;
+    if registry.contains(new) {
+        return Err(ConfigError::AlreadyExists(new.to_string()).into());
+    }
+
+    let mut provider = DynamicProvider::read_all(project, None)
+        .await?
+        .into_iter()
+        .find(|provider| provider.name == old)
+        .ok_or_else(|| anyhow::anyhow!("Unknown configuration '{}'", old))?;
+
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("json");
+    let target = path.with_file_name(format!("{}.{}", new, extension));
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+
+    provider.name = new.to_string();
+    provider.aliases.retain(|alias| alias != new);
+    if !provider.aliases.iter().any(|alias| alias == old) {
+        provider.aliases.push(old.to_string());
+    }
+    provider.check()?;
+
+    provider.source = Some(target.clone());
+    provider.save().await?;
+    tokio::fs::remove_file(&path)
+        .await
+        .map_err(|e| ConfigError::FileWriteError(e.to_string()))?;
+
+    let config_dir = AmarisPathHandler::ensure_config_dir(project).await?;
+    if config_dir.join(old).exists() && !config_dir.join(new).exists() {
+        tokio::fs::rename(config_dir.join(old), config_dir.join(new)).await?;
+    }
+
+    let reporter = Reporter::global();
+    // The package still publishes the old name, updating would bring it back
+    if let Some(package) = AmarisNpmProviders::forget(project, old).await? {
+        reporter.warn(format!(
+            "Warning: {} is no longer updated from {}",
+            reporter.provider(new),
+            package
+        ));
+    }
+    for other in DynamicProvider::read_all(project, None).await? {
+        if other.extends.iter().any(|name| name == old)
+            || other.depends_on.iter().any(|name| name == old)
+        {
+            reporter.warn(format!(
+                "Warning: {} still refers to {} in extends or depends_on",
+                reporter.provider(&other.name),
+                old
+            ));
+        }
+    }
+
+    Ok(target)
+}
+
+/// Opens `path` in `$VISUAL` or `$EDITOR`, which may include arguments such
+/// as `code --wait`, and waits for it to exit.
+async fn open_in_editor(path: &Path) -> anyhow::Result<()> {
+    let Some(editor) = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.trim().is_empty())
+    else {
+        anyhow::bail!(
+            "Neither $VISUAL nor $EDITOR is set, edit {} directly",
+            path.display()
+        );
+    };
+
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+
+    let status = AmarisPlatformHandler::command(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .await
+        .map_err(|e| anyhow::anyhow!("Could not start editor '{}': {}", editor, e))?;
+
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}", editor, status);
+    }
+
+    Ok(())
+}
+
+/// Where `--save` copies a provider file to, refusing to replace an existing provider.
+pub async fn provider_save_path(project: &ProjectContext, path: &Path) -> anyhow::Result<PathBuf> {
+    let Some(file_name) = path.file_name() else {
+        anyhow::bail!("{} is not a file", path.display());
+    };
+
+    let target = AmarisPathHandler::ensure_provider_dir(project)
+        .await?
+        .join(file_name);
+    if target.exists() {
+        return Err(ConfigError::AlreadyExists(target.display().to_string()).into());
+    }
+
+    Ok(target)
+}
+
+/// Asks for confirmation when files the providers are about to touch have
+/// uncommitted changes. Directories outside of a git repository are not checked.
+/// The installed providers that wrote `file`, and the other providers that
+/// would write it, compared after expanding `~` and resolving links.
+async fn file_owners(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    file: &str,
+) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let target = AmarisPathHandler::canonicalize(&AmarisPathHandler::expand_path(file));
+    let state = AmarisProjectState::load(project).await?;
+    let matches = |path: &Path| AmarisPathHandler::canonicalize(&project.path(path)) == target;
+
+    let installed: Vec<String> = state
+        .installed_providers
+        .iter()
+        .filter(|record| {
+            record.files.iter().any(|file| matches(Path::new(file)))
+                || registry.get_provider(&record.name).is_some_and(|provider| {
+                    provider.managed_files(project).iter().any(|f| matches(f))
+                })
+        })
+        .map(|record| record.name.clone())
+        .collect();
+
+    let available = registry
+        .available_configs_sorted()
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| !installed.iter().any(|installed| installed == name))
+        .filter(|name| {
+            registry
+                .get_provider(name)
+                .is_some_and(|provider| provider.managed_files(project).iter().any(|f| matches(f)))
+        })
+        .map(String::from)
+        .collect();
+
+    Ok((installed, available))
+}
+
+fn print_updates(updates: &[PackageUpdate]) {
+    let reporter = Reporter::global();
+    let width = |column: fn(&PackageUpdate) -> &str, header: &str| {
+        updates
+            .iter()
+            .map(|update| column(update).len())
+            .chain([header.len()])
+            .max()
+            .unwrap_or_default()
+    };
+    let package_width = width(|update| &update.package, "Package");
+    let installed_width = width(|update| &update.installed, "Installed");
+    let latest_width = width(|update| &update.latest, "Latest");
+
+    reporter.info(format!(
+        "{:package_width$}  {:installed_width$}  {:latest_width$}  Update",
+        "Package", "Installed", "Latest"
+    ));
+    for update in updates {
+        let line = format!(
+            "{:package_width$}  {:installed_width$}  {:latest_width$}  {}",
+            update.package,
+            update.installed,
+            update.latest,
+            update.kind.as_str()
+        );
+
+        match update.kind {
+            UpdateKind::UpToDate => reporter.info(line),
+            UpdateKind::Major => reporter.warn(line),
+            _ => reporter.success(line),
+        }
+    }
+}
+
+/// Updates the providers of `updates` with a newer version. Pinned ones are
+/// only updated when `named`, and nothing is when `check_only`.
+async fn update_providers(
+    project: &ProjectContext,
+    updates: &[ProviderUpdate],
+    named: bool,
+    check_only: bool,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+
+    for update in updates {
+        let name = reporter.provider(&update.name);
+        if !update.is_available() {
+            reporter.info(format!("{} {} is up to date", name, update.source.version));
+            continue;
+        }
+
+        let line = format!(
+            "{} {} -> {} ({})",
+            name, update.source.version, update.latest, update.source.package
+        );
+        if check_only {
+            reporter.info(line);
+        } else if update.source.pinned && !named {
+            reporter.warn(format!(
+                "{}, pinned, update it with `amaya update-providers {}`",
+                line, update.name
+            ));
+        } else {
+            AmarisNpmProviders::update(project, update).await?;
+            reporter.success(line);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_deps(report: &DepsReport) {
+    let reporter = Reporter::global();
+
+    reporter.info("Managed by providers:");
+    if report.managed.is_empty() {
+        reporter.info("  none");
+    }
+    for dependency in &report.managed {
+        reporter.info(format!(
+            "  {} ({})",
+            dependency.name,
+            reporter.provider(&dependency.provider)
+        ));
+    }
+
+    reporter.info("Not managed by any provider:");
+    if report.unmanaged.is_empty() {
+        reporter.info("  none");
+    }
+    for name in &report.unmanaged {
+        reporter.info(format!("  {}", name));
+    }
+
+    if report.missing.is_empty() {
+        return;
+    }
+    reporter.warn("Declared by providers but not installed:");
+    for dependency in &report.missing {
+        reporter.warn(format!(
+            "  {} ({})",
+            dependency.name,
+            reporter.provider(&dependency.provider)
+        ));
+    }
+}
+
+/// Asks, per provider, whether to install its missing packages or stop
+/// tracking them.
+async fn prune_deps(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    report: &DepsReport,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    const INSTALL: &str = "Install them";
+    const UNTRACK: &str = "Stop tracking them";
+    const SKIP: &str = "Leave them";
+
+    let reporter = Reporter::global();
+    let state = AmarisProjectState::load(project).await?;
+
+    for (name, packages) in report.missing_by_provider() {
+        let choice = Select::new(
+            &format!("{} expects {}:", name, packages.join(", ")),
+            vec![INSTALL, UNTRACK, SKIP],
+        )
+        .prompt()?;
+
+        match choice {
+            INSTALL => {
+                let Some(provider) = registry.get_provider(&name) else {
+                    continue;
+                };
+                let variant_provider = match state.get(&name).and_then(|r| r.variant.as_ref()) {
+                    Some(variant) => Some(provider.with_variant(variant)?),
+                    None => None,
+                };
+                let provider = variant_provider.as_deref().unwrap_or(provider);
+
+                provider
+                    .install(
+                        project,
+                        &InstallOptions {
+                            timeout,
+                            phases: vec![InstallPhase::Packages],
+                            package_scopes: state.package_scopes(&name),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+            }
+            UNTRACK => {
+                AmarisDeps::untrack(project, &name, &packages).await?;
+                reporter.success(format!(
+                    "{} no longer tracks {}",
+                    reporter.provider(&name),
+                    packages.join(", ")
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Asks before going on when files the providers write have uncommitted
+/// changes. Each provider comes with the workspace file its VS Code settings
+/// go to, if any.
+pub async fn confirm_dirty_worktree(
+    project: &ProjectContext,
+    providers: &[(&dyn AmarisProvider, Option<&Path>)],
+) -> anyhow::Result<bool> {
+    let Some(dirty) = AmarisGitHandler::dirty_paths(project).await? else {
+        return Ok(true);
+    };
+
+    let mut touched: Vec<_> = providers
+        .iter()
+        .flat_map(|(provider, workspace_file)| provider.planned_writes(project, *workspace_file))
+        .map(|path| project.path(path))
+        .filter(|path| dirty.contains(path))
+        .collect();
+    touched.sort();
+    touched.dedup();
+
+    if touched.is_empty() {
+        return Ok(true);
+    }
+
+    let reporter = Reporter::global();
+    reporter.warn("The following files have uncommitted changes and will be modified:");
+    for path in &touched {
+        reporter.warn(format!("  {}", project.relative(path).display()));
+    }
+
+    let proceed = AmarisStream::confirm(
+        Confirm::new("Continue anyway?")
+            .with_default(false)
+            .with_help_message("Use --allow-dirty to skip this check"),
+    )?;
+
+    Ok(proceed)
+}
+
+/// Commits the files recorded in the journal. Failing to commit only warns, the
+/// operation itself already succeeded.
+pub async fn commit_changes(
+    project: &ProjectContext,
+    requested: bool,
+    action: &str,
+    provider: &str,
+    version: Option<&str>,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+    let touched = AmarisJournal::take(project);
+    let config = match AmayaConfig::load(project).await {
+        Ok(config) => config,
+        Err(e) => {
+            reporter.warn(format!("Warning: not committing changes: {}", e));
+            return Ok(());
+        }
+    };
+
+    if !requested && !config.commit {
+        return Ok(());
+    }
+
+    let message = config.commit_message(action, provider, version);
+
+    match AmarisGitHandler::commit(project, &touched, &message).await {
+        Ok(_) => reporter.success(format!("Committed changes: {}", message)),
+        Err(e) => reporter.warn(format!("Warning: could not commit changes: {}", e)),
+    }
+
+    Ok(())
+}
+
+pub fn parse_variable(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", value))
+}
+
+fn parse_log_level(value: &str) -> Result<EnvFilter, String> {
+    EnvFilter::try_new(value).map_err(|e| e.to_string())
+}
+
+/// Removes an installed provider for `amaya reset`. When its definition is
+/// gone, the files recorded at install time are removed instead, taking only
+/// the provider's own settings out of `.vscode` files.
+async fn reset_provider(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    record: &InstallRecord,
+    options: &InstallOptions,
+) -> anyhow::Result<()> {
+    if let Some(provider) = registry.get_provider(&record.name) {
+        let variant_provider = record
+            .variant
+            .as_deref()
+            .map(|variant| provider.with_variant(variant))
+            .transpose()?;
+        let provider = variant_provider.as_deref().unwrap_or(provider);
+
+        return Ok(provider.remove(project, options).await?);
+    }
+
+    Reporter::global().warn(format!(
+        "Warning: {} is no longer available, removing the files it installed",
+        record.name
+    ));
+
+    let configs: Vec<ConfigEntry> = record
+        .files
+        .iter()
+        .map(|file| ConfigEntry {
+            file_location: file.clone(),
+            file_name: file.clone(),
+            ..Default::default()
+        })
+        .collect();
+    AmarisConfigurationHandler::remove_configs(
+        project,
+        &record.name,
+        &configs,
+        options.workspace_file.as_deref(),
+    )
+    .await?;
+    AmarisBaseStore::clear(project, &record.name).await?;
+
+    Ok(())
+}
+
+/// Every `.amaya.bak` backup below `dir`, skipping dependencies and VCS data.
+fn find_backups(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut backups = vec![];
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if path.is_dir() {
+            if name != "node_modules" && name != ".git" {
+                backups.extend(find_backups(&path));
+            }
+        } else if name.ends_with(BACKUP_SUFFIX) {
+            backups.push(path);
+        }
+    }
+
+    backups
+}
+
+pub fn managed_files(project: &ProjectContext, provider: &dyn AmarisProvider) -> Vec<String> {
+    provider
+        .managed_files(project)
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
 }
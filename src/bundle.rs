@@ -0,0 +1,596 @@
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ConfigError,
+    provider::{DynamicProvider, ProjectContext},
+    utils::{AmarisPathHandler, CONFIG_DIR_NAME, PROVIDER_DIR_NAME, sha256_integrity},
+};
+
+pub const BUNDLE_EXTENSION: &str = "amaya.tar.gz";
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+/// Upper bound for a bundle on disk and for its unpacked contents.
+pub const MAX_BUNDLE_SIZE: u64 = 10 * 1024 * 1024;
+const MAX_UNPACKED_SIZE: u64 = 50 * 1024 * 1024;
+const MAX_ENTRIES: usize = 1000;
+
+/// Describes the contents of a bundle, stored as `manifest.json` at its root.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub name: String,
+    /// `sha256:<hex>` digest of every file in the bundle, keyed by its path.
+    pub files: BTreeMap<String, String>,
+}
+
+/// Packs a provider and its configuration sources into a single archive for sharing.
+pub struct AmarisBundle;
+
+impl AmarisBundle {
+    /// Writes `<name>.amaya.tar.gz` into `output_dir` with the provider file,
+    /// its `configs/<name>/` tree and a manifest.
+    pub async fn pack(
+        project: &ProjectContext,
+        name: &str,
+        output_dir: &Path,
+    ) -> Result<PathBuf, ConfigError> {
+        let provider = DynamicProvider::read_all(project, None)
+            .await?
+            .into_iter()
+            .find(|provider| provider.name == name)
+            .ok_or_else(|| ConfigError::ValidationError(format!("Unknown provider '{}'", name)))?;
+
+        let source = provider.source.clone().unwrap();
+        let file_name = source.file_name().unwrap().to_string_lossy().to_string();
+
+        let mut files: Vec<(String, Vec<u8>)> = vec![(
+            format!("{}/{}", PROVIDER_DIR_NAME, file_name),
+            tokio::fs::read(&source).await?,
+        )];
+
+        let config_dir = AmarisPathHandler::ensure_config_dir(project)
+            .await?
+            .join(name);
+        if config_dir.exists() {
+            for path in Self::walk(&config_dir)? {
+                let relative = path.strip_prefix(&config_dir).unwrap();
+                let relative: Vec<String> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy().to_string())
+                    .collect();
+
+                files.push((
+                    format!("{}/{}/{}", CONFIG_DIR_NAME, name, relative.join("/")),
+                    tokio::fs::read(&path).await?,
+                ));
+            }
+        }
+
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            name: name.to_string(),
+            files: files
+                .iter()
+                .map(|(path, content)| (path.clone(), sha256_integrity(content)))
+                .collect(),
+        };
+
+        let output = output_dir.join(format!("{}.{}", name, BUNDLE_EXTENSION));
+        let file = std::fs::File::create(&output)
+            .map_err(|e| ConfigError::FileWriteError(format!("{}: {}", output.display(), e)))?;
+
+        let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let manifest = serde_json::to_vec_pretty(&manifest)?;
+
+        for (path, content) in std::iter::once((MANIFEST_NAME.to_string(), manifest)).chain(files) {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            archive.append_data(&mut header, &path, content.as_slice())?;
+        }
+
+        archive.into_inner()?.finish()?;
+
+        Ok(output)
+    }
+
+    /// Validates a bundle and installs its provider and configuration sources,
+    /// refusing to replace an existing provider. Returns the provider name.
+    pub async fn unpack(project: &ProjectContext, path: &Path) -> Result<String, ConfigError> {
+        let invalid = |message: String| {
+            ConfigError::ValidationError(format!("{}: {}", path.display(), message))
+        };
+
+        let size = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| ConfigError::FileReadError(format!("{}: {}", path.display(), e)))?
+            .len();
+        if size > MAX_BUNDLE_SIZE {
+            return Err(invalid(format!(
+                "bundle is {} bytes, larger than the {} byte limit",
+                size, MAX_BUNDLE_SIZE
+            )));
+        }
+
+        let mut entries = Self::read_entries(path).map_err(|e| match e {
+            ConfigError::ValidationError(message) => invalid(message),
+            e => e,
+        })?;
+
+        let manifest: BundleManifest = entries
+            .get(MANIFEST_NAME)
+            .ok_or_else(|| invalid(format!("missing {}", MANIFEST_NAME)))
+            .and_then(|content| {
+                serde_json::from_slice(content)
+                    .map_err(|e| invalid(format!("{}: {}", MANIFEST_NAME, e)))
+            })?;
+
+        if manifest.format_version != BUNDLE_FORMAT_VERSION {
+            return Err(invalid(format!(
+                "unsupported bundle format version {}",
+                manifest.format_version
+            )));
+        }
+
+        for (file, content) in &entries {
+            if file == MANIFEST_NAME {
+                continue;
+            }
+
+            let Some(expected) = manifest.files.get(file) else {
+                return Err(invalid(format!("{} is not listed in the manifest", file)));
+            };
+
+            let actual = sha256_integrity(content);
+            if &actual != expected {
+                return Err(invalid(format!(
+                    "checksum mismatch for {}, expected {} but found {}",
+                    file, expected, actual
+                )));
+            }
+        }
+
+        if let Some(missing) = manifest
+            .files
+            .keys()
+            .find(|file| !entries.contains_key(*file))
+        {
+            return Err(invalid(format!(
+                "{} is listed in the manifest but missing",
+                missing
+            )));
+        }
+
+        entries.remove(MANIFEST_NAME);
+
+        Self::install(project, &entries, Some(&manifest.name), false)
+            .await
+            .map_err(|e| match e {
+                ConfigError::ValidationError(message) => invalid(message),
+                e => e,
+            })
+    }
+
+    /// Writes the provider file under `providers/` of `entries` and its
+    /// sources under `configs/<name>/`, the layout of a bundle, returning the
+    /// provider name. An existing provider of the same name is only replaced
+    /// when `replace` is set, along with all of its sources.
+    pub async fn install(
+        project: &ProjectContext,
+        entries: &BTreeMap<String, Vec<u8>>,
+        expected_name: Option<&str>,
+        replace: bool,
+    ) -> Result<String, ConfigError> {
+        let provider_prefix = format!("{}/", PROVIDER_DIR_NAME);
+        let provider_files: Vec<&String> = entries
+            .keys()
+            .filter(|file| file.starts_with(&provider_prefix))
+            .collect();
+
+        let [provider_file] = provider_files.as_slice() else {
+            return Err(ConfigError::ValidationError(format!(
+                "expected exactly one provider file in {}",
+                provider_prefix
+            )));
+        };
+        let file_name = provider_file.strip_prefix(&provider_prefix).unwrap();
+        if file_name.contains('/') || !DynamicProvider::is_provider_file(Path::new(file_name)) {
+            return Err(ConfigError::ValidationError(format!(
+                "{} is not a provider file",
+                provider_file
+            )));
+        }
+
+        let content = String::from_utf8_lossy(&entries[*provider_file]);
+        let provider = DynamicProvider::from_content(&content, Path::new(provider_file))?;
+        provider.check()?;
+
+        if let Some(expected) = expected_name
+            && provider.name != expected
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "provider is named '{}' but the manifest says '{}'",
+                provider.name, expected
+            )));
+        }
+
+        let config_prefix = format!("{}/{}/", CONFIG_DIR_NAME, provider.name);
+        if let Some(file) = entries
+            .keys()
+            .find(|file| *file != *provider_file && !file.starts_with(&config_prefix))
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "{} is outside of {} and {}",
+                file, provider_prefix, config_prefix
+            )));
+        }
+
+        let provider_dir = AmarisPathHandler::ensure_provider_dir(project).await?;
+        let config_dir = AmarisPathHandler::ensure_config_dir(project).await?;
+
+        let existing = DynamicProvider::read_all(project, None)
+            .await?
+            .into_iter()
+            .find(|existing| existing.name == provider.name);
+        let provider_target = provider_dir.join(file_name);
+        if !replace && (existing.is_some() || provider_target.exists()) {
+            return Err(ConfigError::AlreadyExists(provider.name));
+        }
+
+        // Resolved up front so a symbolic link escape leaves nothing half unpacked
+        let mut targets = vec![];
+        for (file, content) in entries {
+            let target = match file.strip_prefix(&provider_prefix) {
+                Some(name) => AmarisPathHandler::resolve_inside(&provider_dir, name),
+                None => AmarisPathHandler::resolve_inside(
+                    &config_dir,
+                    file.strip_prefix(&format!("{}/", CONFIG_DIR_NAME)).unwrap(),
+                ),
+            }
+            .map_err(|reason| ConfigError::ValidationError(format!("{} {}", file, reason)))?;
+
+            targets.push((target, content));
+        }
+
+        if replace {
+            // The provider may have been written in another format before
+            if let Some(source) = existing.and_then(|existing| existing.source)
+                && source != provider_target
+            {
+                tokio::fs::remove_file(&source).await?;
+            }
+
+            let sources = config_dir.join(&provider.name);
+            if sources.exists() {
+                tokio::fs::remove_dir_all(&sources).await?;
+            }
+        }
+
+        for (target, content) in targets {
+            if let Some(parent) = target.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+
+            tokio::fs::write(&target, content)
+                .await
+                .map_err(|e| ConfigError::FileWriteError(format!("{}: {}", target.display(), e)))?;
+        }
+
+        Ok(provider.name)
+    }
+
+    /// Reads every file of a gzipped tarball into memory, rejecting unsafe
+    /// paths, links and archives that grow past the size limits.
+    pub fn read_entries(path: &Path) -> Result<BTreeMap<String, Vec<u8>>, ConfigError> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| ConfigError::FileReadError(format!("{}: {}", path.display(), e)))?;
+        let mut archive = tar::Archive::new(GzDecoder::new(file));
+
+        let mut entries = BTreeMap::new();
+        let mut total: u64 = 0;
+
+        for entry in archive.entries().map_err(corrupt)? {
+            let mut entry = entry.map_err(corrupt)?;
+
+            if entry.header().entry_type().is_dir() {
+                continue;
+            }
+
+            if !entry.header().entry_type().is_file() {
+                return Err(ConfigError::ValidationError(
+                    "archive may only contain regular files".to_string(),
+                ));
+            }
+
+            let entry_path = entry.path().map_err(corrupt)?.to_path_buf();
+            if entry_path
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_)))
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "unsafe path {} in archive",
+                    entry_path.display()
+                )));
+            }
+
+            if entries.len() >= MAX_ENTRIES {
+                return Err(ConfigError::ValidationError(format!(
+                    "archive has more than {} files",
+                    MAX_ENTRIES
+                )));
+            }
+
+            total += entry.size();
+            if total > MAX_UNPACKED_SIZE {
+                return Err(ConfigError::ValidationError(format!(
+                    "archive unpacks to more than {} bytes",
+                    MAX_UNPACKED_SIZE
+                )));
+            }
+
+            let mut content = vec![];
+            entry
+                .by_ref()
+                .take(MAX_UNPACKED_SIZE)
+                .read_to_end(&mut content)
+                .map_err(corrupt)?;
+
+            let name: Vec<String> = entry_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect();
+            entries.insert(name.join("/"), content);
+        }
+
+        Ok(entries)
+    }
+
+    fn walk(dir: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+        let mut files = vec![];
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                files.extend(Self::walk(&path)?);
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+}
+
+fn corrupt(error: std::io::Error) -> ConfigError {
+    ConfigError::ValidationError(format!("corrupt archive: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TemporaryHome;
+
+    const PROVIDER: &str = r#"{
+  "name": "demo",
+  "description": "Demo provider",
+  "package_manager": "bun",
+  "packages": [],
+  "configuration": [
+    { "file_name": "demo.json", "file_location": "demo.json", "source_from": "demo.json" }
+  ],
+  "scripts": []
+}
+"#;
+
+    fn write(path: &Path, content: &[u8]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// Writes a bundle whose single entry has the raw `name`, which the tar
+    /// builder would refuse, and claims `size` bytes for `content`.
+    fn write_raw_bundle(path: &Path, name: &[u8], size: u64, content: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name);
+        header.set_size(size);
+        header.set_mode(0o644);
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_cksum();
+
+        let file = std::fs::File::create(path).unwrap();
+        let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        archive.append(&header, content).unwrap();
+        archive.into_inner().unwrap().finish().unwrap();
+    }
+
+    fn assert_rejected(result: Result<String, ConfigError>, reason: &str) {
+        match result {
+            Err(ConfigError::ValidationError(message)) => {
+                assert!(
+                    message.contains(reason),
+                    "{} does not say {}",
+                    message,
+                    reason
+                )
+            }
+            result => panic!("expected a validation error, got {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn unpacks_what_was_packed() {
+        let home = TemporaryHome::new().await;
+        let root = home.dir.path();
+        let sources = [
+            ("demo.json", b"{ \"strict\": true }\n".as_slice()),
+            ("nested/notes.txt", b"kept as is\n".as_slice()),
+        ];
+        write(
+            &root.join(PROVIDER_DIR_NAME).join("demo.json"),
+            PROVIDER.as_bytes(),
+        );
+        for (path, content) in sources {
+            write(&root.join(CONFIG_DIR_NAME).join("demo").join(path), content);
+        }
+
+        let output = tempfile::tempdir().unwrap();
+        let bundle = AmarisBundle::pack(&home.project, "demo", output.path())
+            .await
+            .unwrap();
+        assert_eq!(bundle, output.path().join("demo.amaya.tar.gz"));
+
+        std::fs::remove_dir_all(root.join(PROVIDER_DIR_NAME)).unwrap();
+        std::fs::remove_dir_all(root.join(CONFIG_DIR_NAME)).unwrap();
+        assert_eq!(
+            AmarisBundle::unpack(&home.project, &bundle).await.unwrap(),
+            "demo"
+        );
+
+        assert_eq!(
+            std::fs::read_to_string(root.join(PROVIDER_DIR_NAME).join("demo.json")).unwrap(),
+            PROVIDER
+        );
+        for (path, content) in sources {
+            assert_eq!(
+                std::fs::read(root.join(CONFIG_DIR_NAME).join("demo").join(path)).unwrap(),
+                content
+            );
+        }
+
+        assert!(matches!(
+            AmarisBundle::unpack(&home.project, &bundle).await,
+            Err(ConfigError::AlreadyExists(name)) if name == "demo"
+        ));
+    }
+
+    #[tokio::test]
+    async fn refuses_entries_leaving_the_provider_directories() {
+        let home = TemporaryHome::new().await;
+        let provider = (
+            format!("{}/demo.json", PROVIDER_DIR_NAME),
+            PROVIDER.as_bytes().to_vec(),
+        );
+
+        for escape in [
+            "configs/demo/../../../.ssh/authorized_keys",
+            "configs/demo/../other/x",
+        ] {
+            let entries =
+                BTreeMap::from([provider.clone(), (escape.to_string(), b"key\n".to_vec())]);
+            assert_rejected(
+                AmarisBundle::install(&home.project, &entries, None, false).await,
+                escape,
+            );
+        }
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            let config_dir = home.dir.path().join(CONFIG_DIR_NAME);
+            std::fs::create_dir_all(&config_dir).unwrap();
+            std::os::unix::fs::symlink(outside.path(), config_dir.join("demo")).unwrap();
+
+            let entries = BTreeMap::from([
+                provider,
+                (
+                    "configs/demo/authorized_keys".to_string(),
+                    b"key\n".to_vec(),
+                ),
+            ]);
+            assert_rejected(
+                AmarisBundle::install(&home.project, &entries, None, false).await,
+                "through a symbolic link",
+            );
+            assert!(!outside.path().join("authorized_keys").exists());
+            assert!(
+                !home
+                    .dir
+                    .path()
+                    .join(PROVIDER_DIR_NAME)
+                    .join("demo.json")
+                    .exists()
+            );
+        }
+
+        assert!(
+            !home
+                .dir
+                .path()
+                .parent()
+                .unwrap()
+                .join(".ssh/authorized_keys")
+                .exists()
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_a_corrupt_archive() {
+        let home = TemporaryHome::new().await;
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("demo.amaya.tar.gz");
+        std::fs::write(&bundle, b"not a gzipped tarball").unwrap();
+
+        assert_rejected(
+            AmarisBundle::unpack(&home.project, &bundle).await,
+            "corrupt archive",
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_entries_outside_of_the_bundle() {
+        let home = TemporaryHome::new().await;
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("demo.amaya.tar.gz");
+        write_raw_bundle(&bundle, b"../../.ssh/authorized_keys", 4, b"key\n");
+
+        assert_rejected(
+            AmarisBundle::unpack(&home.project, &bundle).await,
+            "unsafe path",
+        );
+        assert!(!dir.path().join(".ssh").exists());
+    }
+
+    #[tokio::test]
+    async fn rejects_a_bundle_over_the_size_limit() {
+        let home = TemporaryHome::new().await;
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("demo.amaya.tar.gz");
+        std::fs::File::create(&bundle)
+            .unwrap()
+            .set_len(MAX_BUNDLE_SIZE + 1)
+            .unwrap();
+
+        assert_rejected(
+            AmarisBundle::unpack(&home.project, &bundle).await,
+            "byte limit",
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_a_bundle_unpacking_past_the_size_limit() {
+        let home = TemporaryHome::new().await;
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("demo.amaya.tar.gz");
+        write_raw_bundle(
+            &bundle,
+            MANIFEST_NAME.as_bytes(),
+            MAX_UNPACKED_SIZE + 1,
+            b"",
+        );
+
+        assert_rejected(
+            AmarisBundle::unpack(&home.project, &bundle).await,
+            "unpacks to more than",
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::SystemTime,
+};
+
+use tokio::sync::RwLock;
+
+use crate::{error::ConfigError, utils::AmarisFileHandler};
+
+static CACHE: OnceLock<Arc<FilesystemCache>> = OnceLock::new();
+
+/// Contents of the project files read during this run, keyed by absolute
+/// path. An entry is only used while the file's modification time is the one
+/// it was read at, so files changed by a package manager are read again.
+#[derive(Debug, Default)]
+pub struct FilesystemCache {
+    entries: RwLock<HashMap<PathBuf, (String, SystemTime)>>,
+}
+
+impl FilesystemCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn init(cache: Arc<FilesystemCache>) {
+        let _ = CACHE.set(cache);
+    }
+
+    pub fn global() -> Arc<FilesystemCache> {
+        CACHE
+            .get_or_init(|| Arc::new(FilesystemCache::new()))
+            .clone()
+    }
+
+    pub async fn read(&self, path: &Path) -> Result<String, ConfigError> {
+        let path = std::path::absolute(path)
+            .map_err(|e| ConfigError::PathError(format!("{}: {}", path.display(), e)))?;
+        let modified = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| ConfigError::FileReadError(format!("{}: {}", path.display(), e)))?;
+
+        if let Some((contents, cached_at)) = self.entries.read().await.get(&path)
+            && *cached_at == modified
+        {
+            return Ok(contents.clone());
+        }
+
+        let contents = AmarisFileHandler::read_text(&path).await?;
+
+        self.entries
+            .write()
+            .await
+            .insert(path, (contents.clone(), modified));
+
+        Ok(contents)
+    }
+
+    /// Drops the entry of a file amaya changed, a write within the same
+    /// modification time tick would otherwise go unnoticed.
+    pub async fn invalidate(&self, path: &Path) {
+        if let Ok(path) = std::path::absolute(path) {
+            self.entries.write().await.remove(&path);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use serde::Serialize;
+
+use crate::{
+    error::ConfigError,
+    provider::{AmarisRegistry, InstallOptions, InstallPhase, ProjectContext},
+    state::AmarisProjectState,
+    utils::AmarisInstaller,
+};
+
+/// The outcome of `amaya ci`, printed as JSON on stdout.
+#[derive(Serialize, Debug, Default)]
+pub struct CiReport {
+    pub success: bool,
+    pub providers: Vec<CiProviderReport>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct CiProviderReport {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The failed prerequisite, checks stop at the first one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prerequisite_error: Option<String>,
+    /// Configuration files that are missing or differ from their templates.
+    pub files: Vec<String>,
+    /// Packages of the provider that package.json does not list.
+    pub missing_packages: Vec<String>,
+}
+
+impl CiProviderReport {
+    pub fn passed(&self) -> bool {
+        self.prerequisite_error.is_none()
+            && self.files.is_empty()
+            && self.missing_packages.is_empty()
+    }
+
+    /// One line per problem, for stderr.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self.prerequisite_error.iter().cloned().collect();
+        problems.extend(self.files.iter().cloned());
+        problems.extend(
+            self.missing_packages
+                .iter()
+                .map(|package| format!("{} is not in package.json", package)),
+        );
+        problems
+    }
+}
+
+/// Checks the providers installed in the current project without prompting
+/// or changing anything.
+pub struct AmarisCi;
+
+impl AmarisCi {
+    pub async fn run(
+        project: &ProjectContext,
+        registry: &AmarisRegistry,
+    ) -> Result<CiReport, ConfigError> {
+        let state = AmarisProjectState::load(project).await?;
+        let mut report = CiReport::default();
+
+        for record in &state.installed_providers {
+            let provider = registry.get_provider(&record.name).ok_or_else(|| {
+                ConfigError::ValidationError(format!(
+                    "{} is installed but no longer available",
+                    record.name
+                ))
+            })?;
+            let variant_provider = match &record.variant {
+                Some(variant) => Some(provider.with_variant(variant)?),
+                None => None,
+            };
+            let provider = variant_provider.as_deref().unwrap_or(provider);
+
+            let mut provider_report = CiProviderReport {
+                name: record.name.clone(),
+                version: record.version.clone(),
+                ..Default::default()
+            };
+
+            if let Err(e) = provider.check_prerequisites(project).await {
+                provider_report.prerequisite_error = Some(e.to_string());
+                report.providers.push(provider_report);
+                return Ok(report);
+            }
+
+            let options = InstallOptions {
+                workspace_file: state.workspace_file(project, &record.name),
+                phases: record.phases.clone(),
+                ..Default::default()
+            };
+
+            if options.applies(InstallPhase::Configs) {
+                provider_report.files = provider.verify(project, &options).await?;
+            }
+
+            if options.applies(InstallPhase::Packages) {
+                // Global packages are not listed in package.json
+                for package in provider.package_entries() {
+                    if !package.is_global()
+                        && !record.untracked_packages.contains(&package.name)
+                        && !AmarisInstaller::check_if_package_installed(project, &package.name)
+                            .await?
+                    {
+                        provider_report.missing_packages.push(package.name);
+                    }
+                }
+            }
+
+            report.providers.push(provider_report);
+        }
+
+        report.success = report.providers.iter().all(CiProviderReport::passed);
+
+        Ok(report)
+    }
+}
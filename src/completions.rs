@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use clap::{Arg, Command, CommandFactory, builder::PossibleValuesParser};
+use clap_complete::Shell;
+
+use crate::{args::CLI, error::ConfigError, utils::AmarisFileHandler};
+
+const BIN_NAME: &str = "amaya";
+
+/// Arguments that take a provider name, as subcommand path and argument id.
+const PROVIDER_ARGS: &[(&[&str], &str)] = &[
+    (&["upgrade"], "config"),
+    (&["remove"], "config"),
+    (&["switch"], "from"),
+    (&["switch"], "to"),
+    (&["pack"], "name"),
+    (&["configs"], "name"),
+    (&["edit"], "name"),
+    (&["rename-provider"], "old"),
+    (&["update-providers"], "names"),
+    (&["test-provider"], "name"),
+    (&["info"], "name"),
+    (&["diff"], "name"),
+    (&["providers", "show"], "name"),
+    (&["providers", "remove"], "name"),
+];
+/// Arguments that take a provider or a group name.
+const GROUP_ARGS: &[(&[&str], &str)] = &[(&["install"], "config"), (&["install"], "name")];
+
+/// Shell completion scripts for amaya. Provider names are read when the
+/// script is generated, so it has to be generated again after adding one.
+pub struct AmarisCompletions;
+
+impl AmarisCompletions {
+    /// The completion script for `shell`, completing `providers` wherever a
+    /// provider name is expected and `groups` where a group is accepted too,
+    /// preceded by how to install it.
+    pub fn generate(shell: Shell, providers: &[String], groups: &[String]) -> String {
+        let mut command = CLI::command();
+        for (path, arg) in PROVIDER_ARGS {
+            command = Self::complete_with(command, path, arg, providers);
+        }
+
+        let installable: Vec<String> = providers.iter().chain(groups).cloned().collect();
+        for (path, arg) in GROUP_ARGS {
+            command = Self::complete_with(command, path, arg, &installable);
+        }
+
+        let mut script = vec![];
+        clap_complete::generate(shell, &mut command, BIN_NAME, &mut script);
+
+        let script = String::from_utf8_lossy(&script);
+
+        // zsh only autoloads the function when `#compdef` is the first line
+        match script.split_once('\n') {
+            Some((first, rest)) if first.starts_with("#compdef") => {
+                format!("{}\n{}\n{}", first, Self::instructions(shell), rest)
+            }
+            _ => format!("{}\n{}", Self::instructions(shell), script),
+        }
+    }
+
+    /// Writes the script for `shell` where the shell picks it up and returns
+    /// the path written.
+    pub async fn install(
+        shell: Shell,
+        providers: &[String],
+        groups: &[String],
+    ) -> Result<PathBuf, ConfigError> {
+        let path = Self::install_path(shell)?;
+        let script = Self::generate(shell, providers, groups);
+        AmarisFileHandler::write_user_file(path.clone(), &script).await?;
+
+        Ok(path)
+    }
+
+    /// What else the shell needs for the installed script to be loaded.
+    pub fn activation_hint(shell: Shell) -> Option<&'static str> {
+        match shell {
+            Shell::Zsh => Some("add `fpath+=~/.zfunc` before `compinit` in ~/.zshrc"),
+            Shell::Elvish => Some("add `use amaya` to ~/.config/elvish/rc.elv"),
+            Shell::PowerShell => Some("dot-source the script from your $PROFILE"),
+            _ => None,
+        }
+    }
+
+    fn install_path(shell: Shell) -> Result<PathBuf, ConfigError> {
+        let missing = |dir: &str| ConfigError::PathError(format!("Could not find the {}", dir));
+
+        Ok(match shell {
+            Shell::Bash => dirs::data_dir()
+                .ok_or_else(|| missing("data directory"))?
+                .join("bash-completion")
+                .join("completions")
+                .join(BIN_NAME),
+            Shell::Zsh => dirs::home_dir()
+                .ok_or_else(|| missing("home directory"))?
+                .join(".zfunc")
+                .join(format!("_{}", BIN_NAME)),
+            Shell::Fish => dirs::config_dir()
+                .ok_or_else(|| missing("config directory"))?
+                .join("fish")
+                .join("completions")
+                .join(format!("{}.fish", BIN_NAME)),
+            Shell::Elvish => dirs::config_dir()
+                .ok_or_else(|| missing("config directory"))?
+                .join("elvish")
+                .join("lib")
+                .join(format!("{}.elv", BIN_NAME)),
+            Shell::PowerShell => dirs::config_dir()
+                .ok_or_else(|| missing("config directory"))?
+                .join("powershell")
+                .join(format!("{}-completions.ps1", BIN_NAME)),
+            other => {
+                return Err(ConfigError::ValidationError(format!(
+                    "No install location known for {}",
+                    other
+                )));
+            }
+        })
+    }
+
+    fn instructions(shell: Shell) -> String {
+        let lines: &[&str] = match shell {
+            Shell::Bash => &[
+                "amaya completions bash > ~/.local/share/bash-completion/completions/amaya",
+                "or: amaya completions --install bash",
+            ],
+            Shell::Zsh => &[
+                "amaya completions zsh > ~/.zfunc/_amaya",
+                "then add `fpath+=~/.zfunc` before `compinit` in ~/.zshrc",
+            ],
+            Shell::Fish => &["amaya completions fish > ~/.config/fish/completions/amaya.fish"],
+            Shell::Elvish => &[
+                "amaya completions elvish > ~/.config/elvish/lib/amaya.elv",
+                "then add `use amaya` to ~/.config/elvish/rc.elv",
+            ],
+            Shell::PowerShell => &[
+                "amaya completions powershell > ~/.config/powershell/amaya-completions.ps1",
+                "then add `. ~/.config/powershell/amaya-completions.ps1` to $PROFILE",
+            ],
+            _ => &[],
+        };
+
+        let mut comment = vec![
+            "# Completions for amaya, install with:".to_string(),
+            "#".to_string(),
+        ];
+        comment.extend(lines.iter().map(|line| format!("#   {}", line)));
+        comment.push("#".to_string());
+        comment.push(
+            "# Provider names are those present when this script was generated, run the"
+                .to_string(),
+        );
+        comment.push("# command again after adding providers.".to_string());
+
+        comment.join("\n")
+    }
+
+    fn complete_with(command: Command, path: &[&str], arg: &str, providers: &[String]) -> Command {
+        let Some((first, rest)) = path.split_first() else {
+            let providers = providers.to_vec();
+            return command.mut_arg(arg, |a: Arg| {
+                a.value_parser(PossibleValuesParser::new(providers))
+            });
+        };
+
+        command.mut_subcommand(*first, |subcommand| {
+            Self::complete_with(subcommand, rest, arg, providers)
+        })
+    }
+}
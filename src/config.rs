@@ -0,0 +1,578 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::ConfigError,
+    provider::{
+        AmarisAliases, AmarisRegistry, MODULE_SYNTAX_VARIABLE, MergeStrategy, ProjectContext,
+        TYPESCRIPT_VERSION_VARIABLE,
+    },
+    retry::{DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_BACKOFF_MS},
+    utils::{AmarisFileHandler, AmarisPathHandler, Editor},
+};
+
+pub const DEFAULT_COMMIT_MESSAGE: &str = "chore: {action} amaya provider {provider} {version}";
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+pub const DEFAULT_MAX_FILE_SIZE_MB: u64 = 8;
+pub const DEFAULT_ERROR_OUTPUT_LINES: usize = 40;
+pub const PROJECT_OVERRIDES_PATH: &str = ".amaya/overrides.json";
+/// Keys accepted by `amaya config set`, dotted keys address nested tables.
+pub const CONFIG_KEYS: &[&str] = &[
+    "commit",
+    "commit_message",
+    "timeout",
+    "registry_url",
+    "network.ca_bundle",
+    "npm.registry",
+    "npm.token_env",
+    "retry.attempts",
+    "retry.backoff_ms",
+    "editors",
+    "package_manager",
+    "backups",
+    "verify",
+    "max_file_size_mb",
+    "error_output_lines",
+    "provider_git_remote",
+];
+
+/// Global settings read from `~/.amaya/config.toml`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct AmayaConfig {
+    /// Commit the files touched by install and remove without passing `--commit`.
+    pub commit: bool,
+    /// Commit message template, `{action}`, `{provider}` and `{version}` are substituted.
+    pub commit_message: Option<String>,
+    /// Seconds a package manager command may run before it is killed.
+    pub timeout: u64,
+    /// Named sets of providers installed together, members may be other groups.
+    pub groups: HashMap<String, Vec<String>>,
+    /// URL of the provider index used by `amaya search` and `amaya add --from-registry`.
+    pub registry_url: Option<String>,
+    /// Git repository `amaya providers sync` shares providers and configuration sources through.
+    pub provider_git_remote: Option<String>,
+    pub network: NetworkConfig,
+    pub npm: NpmConfig,
+    pub retry: RetryConfig,
+    /// Editors whose settings providers configure, detected from the project when unset.
+    pub editors: Option<Vec<Editor>>,
+    /// Package manager of projects without a lockfile or `packageManager` field.
+    pub package_manager: Option<String>,
+    /// Back up project files to `<file>.amaya.bak` before a provider overwrites them.
+    pub backups: bool,
+    /// Verify the configuration files of a provider after installing it.
+    pub verify: bool,
+    /// Files larger than this are refused instead of read, in megabytes.
+    pub max_file_size_mb: u64,
+    /// Lines of a failed command's output kept in errors and logs, the last ones.
+    pub error_output_lines: usize,
+    /// Options set on providers at startup, `[options.biome]` holds those of
+    /// biome. See [`crate::provider::AmarisProvider::set_option`].
+    pub options: HashMap<String, BTreeMap<String, Value>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// PEM file with extra root certificates to trust, e.g. a corporate CA.
+    pub ca_bundle: Option<PathBuf>,
+}
+
+/// The registry provider packages are installed from.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct NpmConfig {
+    /// Registry URL passed to the package manager, the public npm registry when unset.
+    pub registry: Option<String>,
+    /// Name of the environment variable holding the registry auth token. The
+    /// token itself is never stored.
+    pub token_env: Option<String>,
+}
+
+impl Default for AmayaConfig {
+    fn default() -> Self {
+        Self {
+            commit: false,
+            commit_message: None,
+            timeout: DEFAULT_TIMEOUT_SECS,
+            groups: HashMap::new(),
+            registry_url: None,
+            provider_git_remote: None,
+            network: NetworkConfig::default(),
+            npm: NpmConfig::default(),
+            retry: RetryConfig::default(),
+            editors: None,
+            package_manager: None,
+            backups: false,
+            verify: false,
+            max_file_size_mb: DEFAULT_MAX_FILE_SIZE_MB,
+            error_output_lines: DEFAULT_ERROR_OUTPUT_LINES,
+            options: HashMap::new(),
+        }
+    }
+}
+
+/// Retries of package installs and downloads that fail because of the network.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Total attempts, including the first one.
+    pub attempts: u32,
+    /// Wait before the first retry in milliseconds, doubled for every further one.
+    pub backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: DEFAULT_RETRY_ATTEMPTS,
+            backoff_ms: DEFAULT_RETRY_BACKOFF_MS,
+        }
+    }
+}
+
+impl AmayaConfig {
+    pub async fn load(project: &ProjectContext) -> Result<Self, ConfigError> {
+        let path = AmarisPathHandler::get_app_config_path(project)?;
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = AmarisFileHandler::read_text(&path).await?;
+
+        toml::from_str(&content)
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Stores `value` under one of [`CONFIG_KEYS`], keeping the rest of the
+    /// file untouched. Values are read as booleans or numbers when the key
+    /// accepts them, as strings otherwise and as comma separated lists for
+    /// keys that take several values.
+    pub async fn set(project: &ProjectContext, key: &str, value: &str) -> Result<(), ConfigError> {
+        Self::check_key(key)?;
+
+        let path = AmarisPathHandler::get_app_config_path(project)?;
+        let table = Self::read_table(&path).await?;
+
+        let typed = value
+            .parse::<bool>()
+            .map(toml::Value::Boolean)
+            .or_else(|_| value.parse::<i64>().map(toml::Value::Integer))
+            .unwrap_or_else(|_| toml::Value::String(value.to_string()));
+
+        let mut errors = vec![];
+        let list = toml::Value::Array(
+            value
+                .split(',')
+                .map(|item| toml::Value::String(item.trim().to_string()))
+                .collect(),
+        );
+
+        for candidate in [typed, toml::Value::String(value.to_string()), list] {
+            let mut updated = table.clone();
+            Self::insert(&mut updated, key, candidate)?;
+
+            match updated.clone().try_into::<AmayaConfig>() {
+                Ok(_) => {
+                    let content = toml::to_string_pretty(&updated)
+                        .map_err(|e| ConfigError::FileWriteError(e.to_string()))?;
+                    return AmarisFileHandler::write_user_file(path, &content).await;
+                }
+                Err(e) => errors.push(e.message().to_string()),
+            }
+        }
+
+        // A type mismatch only says the value was read the wrong way, any
+        // other error describes what is wrong with it
+        let message = errors
+            .iter()
+            .find(|message| !message.starts_with("invalid type"))
+            .or(errors.first())
+            .cloned()
+            .unwrap_or_default();
+
+        Err(ConfigError::ValidationError(format!(
+            "invalid value '{}' for {}: {}",
+            value, key, message
+        )))
+    }
+
+    /// The value stored under `key`, `None` when it is not set.
+    pub async fn get(project: &ProjectContext, key: &str) -> Result<Option<String>, ConfigError> {
+        Self::check_key(key)?;
+
+        let path = AmarisPathHandler::get_app_config_path(project)?;
+        let table = toml::Value::Table(Self::read_table(&path).await?);
+        let value = key
+            .split('.')
+            .try_fold(&table, |value, part| value.get(part));
+
+        Ok(value.map(|value| match value {
+            toml::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        }))
+    }
+
+    async fn read_table(path: &Path) -> Result<toml::Table, ConfigError> {
+        if !path.exists() {
+            return Ok(toml::Table::new());
+        }
+
+        let content = AmarisFileHandler::read_text(path).await?;
+
+        content
+            .parse()
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))
+    }
+
+    fn insert(table: &mut toml::Table, key: &str, value: toml::Value) -> Result<(), ConfigError> {
+        let mut parts: Vec<&str> = key.split('.').collect();
+        let last = parts.pop().unwrap();
+
+        let mut current = table;
+        for part in parts {
+            let entry = current
+                .entry(part)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            current = entry
+                .as_table_mut()
+                .ok_or_else(|| ConfigError::ValidationError(format!("{} is not a table", part)))?;
+        }
+
+        current.insert(last.to_string(), value);
+        Ok(())
+    }
+
+    fn check_key(key: &str) -> Result<(), ConfigError> {
+        if CONFIG_KEYS.contains(&key) {
+            return Ok(());
+        }
+
+        Err(ConfigError::ValidationError(format!(
+            "Unknown setting '{}', expected one of {}",
+            key,
+            CONFIG_KEYS.join(", ")
+        )))
+    }
+
+    /// The commit message for `action` on `provider`. Without a `version`
+    /// the `{version}` placeholder is dropped along with the space before it.
+    pub fn commit_message(&self, action: &str, provider: &str, version: Option<&str>) -> String {
+        let template = self
+            .commit_message
+            .as_deref()
+            .unwrap_or(DEFAULT_COMMIT_MESSAGE)
+            .replace("{action}", action)
+            .replace("{provider}", provider);
+
+        match version {
+            Some(version) => template.replace("{version}", version),
+            None => template.replace(" {version}", "").replace("{version}", ""),
+        }
+    }
+
+    /// Flattens a group into its providers in declaration order, `None` when
+    /// `name` is not a group.
+    pub fn resolve_group(&self, name: &str) -> Result<Option<Vec<String>>, ConfigError> {
+        if !self.groups.contains_key(name) {
+            return Ok(None);
+        }
+
+        let mut members = vec![];
+        self.flatten_group(name, &mut vec![], &mut members)?;
+
+        Ok(Some(members))
+    }
+
+    fn flatten_group(
+        &self,
+        name: &str,
+        stack: &mut Vec<String>,
+        members: &mut Vec<String>,
+    ) -> Result<(), ConfigError> {
+        if let Some(start) = stack.iter().position(|group| group == name) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(ConfigError::ValidationError(format!(
+                "Circular group: {}",
+                cycle.join(" → ")
+            )));
+        }
+
+        stack.push(name.to_string());
+
+        for member in &self.groups[name] {
+            if self.groups.contains_key(member) {
+                self.flatten_group(member, stack, members)?;
+            } else if !members.contains(member) {
+                members.push(member.clone());
+            }
+        }
+
+        stack.pop();
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct ProviderOverrides {
+    /// Values for template variables, taking precedence over the provider defaults.
+    pub variables: HashMap<String, String>,
+    /// Merge strategy keyed by the `file_location` of a configuration entry.
+    pub merge: HashMap<String, MergeStrategy>,
+}
+
+impl ProviderOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty() && self.merge.is_empty()
+    }
+}
+
+/// Per-project provider overrides read from `.amaya/overrides.json`, keyed by provider name.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(transparent)]
+pub struct ProjectOverrides {
+    pub providers: HashMap<String, ProviderOverrides>,
+}
+
+impl ProjectOverrides {
+    pub fn get_default_path(project: &ProjectContext) -> PathBuf {
+        project.path(PROJECT_OVERRIDES_PATH)
+    }
+
+    pub async fn load(project: &ProjectContext) -> Result<Self, ConfigError> {
+        let path = Self::get_default_path(project);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = AmarisFileHandler::load_file(&path).await?;
+
+        let mut overrides: Self = serde_json::from_str(&content)
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))?;
+
+        // Providers renamed since the overrides were written
+        let renamed: Vec<(String, &str)> = overrides
+            .providers
+            .keys()
+            .filter_map(|name| AmarisAliases::resolve(name).map(|current| (name.clone(), current)))
+            .collect();
+        for (name, current) in renamed {
+            if let Some(provider_overrides) = overrides.providers.remove(&name) {
+                overrides
+                    .providers
+                    .entry(current.to_string())
+                    .or_insert(provider_overrides);
+            }
+        }
+
+        Ok(overrides)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    pub fn get(&self, provider: &str) -> Option<&ProviderOverrides> {
+        self.providers.get(provider)
+    }
+
+    /// Overrides referencing providers, variables or configuration entries
+    /// that do not exist in `registry`, so they would have no effect.
+    pub fn issues(&self, registry: &AmarisRegistry) -> Vec<String> {
+        let mut issues = vec![];
+
+        let mut names: Vec<&String> = self.providers.keys().collect();
+        names.sort();
+        for name in names {
+            let Some(provider) = registry.get_provider(name) else {
+                issues.push(format!("overrides for unknown provider '{}'", name));
+                continue;
+            };
+            let overrides = &self.providers[name];
+            let variants = provider.variants();
+
+            let mut variables: Vec<&String> = overrides.variables.keys().collect();
+            variables.sort();
+            for variable in variables {
+                let known = [TYPESCRIPT_VERSION_VARIABLE, MODULE_SYNTAX_VARIABLE]
+                    .contains(&variable.as_str())
+                    || provider.variables().contains_key(variable)
+                    || variants
+                        .iter()
+                        .any(|variant| variant.variables.contains_key(variable));
+                if !known {
+                    issues.push(format!(
+                        "override for unknown variable '{}' of {}",
+                        variable, name
+                    ));
+                }
+            }
+
+            let mut locations: Vec<&String> = overrides.merge.keys().collect();
+            locations.sort();
+            for file_location in locations {
+                let known = provider
+                    .config_files()
+                    .iter()
+                    .chain(variants.iter().flat_map(|variant| &variant.configuration))
+                    .any(|config| &config.file_location == file_location);
+                if !known {
+                    issues.push(format!(
+                        "merge override for unknown configuration '{}' of {}",
+                        file_location, name
+                    ));
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// One line per provider listing its overrides, `name: variable=value,
+    /// file_location=strategy`, sorted by provider.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .providers
+            .iter()
+            .filter(|(_, overrides)| !overrides.is_empty())
+            .map(|(name, overrides)| {
+                let mut variables: Vec<String> = overrides
+                    .variables
+                    .iter()
+                    .map(|(variable, value)| format!("{}={}", variable, value))
+                    .collect();
+                variables.sort();
+                let mut merges: Vec<String> = overrides
+                    .merge
+                    .iter()
+                    .map(|(location, strategy)| format!("{}={}", location, strategy.as_str()))
+                    .collect();
+                merges.sort();
+
+                format!("{}: {}", name, [variables, merges].concat().join(", "))
+            })
+            .collect();
+        lines.sort();
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{ConfigEntry, DynamicProvider, ProviderVariant};
+
+    fn overrides(json: Value) -> ProjectOverrides {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn tsconfig_registry() -> AmarisRegistry {
+        let mut registry = AmarisRegistry::new();
+        registry.register(
+            DynamicProvider {
+                name: "tsconfig".to_string(),
+                variables: HashMap::from([("target".to_string(), "es2022".to_string())]),
+                configuration: vec![ConfigEntry {
+                    file_location: "tsconfig.json".to_string(),
+                    ..Default::default()
+                }],
+                variants: vec![ProviderVariant {
+                    name: "react".to_string(),
+                    variables: HashMap::from([("jsx".to_string(), "react-jsx".to_string())]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }
+            .into(),
+        );
+        registry
+    }
+
+    #[test]
+    fn flags_overrides_without_a_provider_variable_or_configuration() {
+        let overrides = overrides(serde_json::json!({
+            "tsconfig": {
+                "variables": { "target": "es2020", "jsx": "preserve", "module_syntax": "esm", "root": "src" },
+                "merge": { "tsconfig.json": "merge", "jsconfig.json": "merge" },
+            },
+            "missing": { "variables": { "target": "es2020" } },
+        }));
+
+        assert_eq!(
+            overrides.issues(&tsconfig_registry()),
+            [
+                "overrides for unknown provider 'missing'",
+                "override for unknown variable 'root' of tsconfig",
+                "merge override for unknown configuration 'jsconfig.json' of tsconfig",
+            ]
+        );
+        assert!(
+            ProjectOverrides::default()
+                .issues(&tsconfig_registry())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn describes_the_overrides_of_each_provider() {
+        let overrides = overrides(serde_json::json!({
+            "tsconfig": {
+                "variables": { "target": "es2020", "jsx": "preserve" },
+                "merge": { "tsconfig.json": "merge" },
+            },
+            "biome": { "variables": { "indent": "tab" } },
+            "empty": {},
+        }));
+
+        assert_eq!(
+            overrides.describe(),
+            [
+                "biome: indent=tab",
+                "tsconfig: jsx=preserve, target=es2020, tsconfig.json=merge",
+            ]
+        );
+    }
+
+    #[test]
+    fn default_commit_message_names_the_provider_version() {
+        let config = AmayaConfig::default();
+
+        assert_eq!(
+            config.commit_message("apply", "biome", Some("1.2.0")),
+            "chore: apply amaya provider biome 1.2.0"
+        );
+        assert_eq!(
+            config.commit_message("switch", "eslint to biome", None),
+            "chore: switch amaya provider eslint to biome"
+        );
+    }
+
+    #[test]
+    fn custom_commit_message_substitutes_every_placeholder() {
+        let config = AmayaConfig {
+            commit_message: Some("build({provider}): {action} {version}".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.commit_message("remove", "biome", Some("2.0.0")),
+            "build(biome): remove 2.0.0"
+        );
+        assert_eq!(
+            config.commit_message("remove", "biome", None),
+            "build(biome): remove"
+        );
+    }
+}
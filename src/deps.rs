@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    error::ConfigError,
+    provider::{AmarisRegistry, InstallPhase, ProjectContext},
+    reporter::Reporter,
+    state::AmarisProjectState,
+    utils::AmarisPackageJsonHandler,
+};
+
+/// A package declared by an installed provider.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProviderDependency {
+    pub name: String,
+    pub provider: String,
+}
+
+/// The outcome of `amaya deps`, printed as JSON with `--output json`.
+#[derive(Serialize, Debug, Default)]
+pub struct DepsReport {
+    /// Packages in package.json declared by an installed provider.
+    pub managed: Vec<ProviderDependency>,
+    /// devDependencies no installed provider declares.
+    pub unmanaged: Vec<String>,
+    /// Packages of installed providers package.json does not list.
+    pub missing: Vec<ProviderDependency>,
+}
+
+impl DepsReport {
+    /// The missing packages grouped by the provider declaring them.
+    pub fn missing_by_provider(&self) -> BTreeMap<String, Vec<String>> {
+        let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for dependency in &self.missing {
+            grouped
+                .entry(dependency.provider.clone())
+                .or_default()
+                .push(dependency.name.clone());
+        }
+        grouped
+    }
+}
+
+/// Joins the dependencies of package.json against the packages of the
+/// providers installed in the current project.
+pub struct AmarisDeps;
+
+impl AmarisDeps {
+    pub async fn report(
+        project: &ProjectContext,
+        registry: &AmarisRegistry,
+    ) -> Result<DepsReport, ConfigError> {
+        let state = AmarisProjectState::load(project).await?;
+        let package_json = AmarisPackageJsonHandler::read(project).await?;
+        let listed = |field: &str| -> Vec<String> {
+            package_json
+                .get(field)
+                .and_then(Value::as_object)
+                .map(|deps| deps.keys().cloned().collect())
+                .unwrap_or_default()
+        };
+        let dev_dependencies = listed("devDependencies");
+        let dependencies = listed("dependencies");
+
+        let mut declared: BTreeMap<String, String> = BTreeMap::new();
+        for record in &state.installed_providers {
+            if !record.phases.contains(&InstallPhase::Packages) {
+                continue;
+            }
+
+            let Some(provider) = registry.get_provider(&record.name) else {
+                Reporter::global().warn(format!(
+                    "Warning: {} is installed but no longer available, its packages are not known",
+                    record.name
+                ));
+                continue;
+            };
+            let variant_provider = match &record.variant {
+                Some(variant) => Some(provider.with_variant(variant)?),
+                None => None,
+            };
+            let provider = variant_provider.as_deref().unwrap_or(provider);
+
+            // Global packages are not listed in package.json
+            for package in provider.package_entries() {
+                if !package.is_global() && !record.untracked_packages.contains(&package.name) {
+                    declared
+                        .entry(package.name)
+                        .or_insert_with(|| record.name.clone());
+                }
+            }
+        }
+
+        let mut report = DepsReport::default();
+        for (name, provider) in declared {
+            let dependency = ProviderDependency { name, provider };
+            match dev_dependencies.contains(&dependency.name)
+                || dependencies.contains(&dependency.name)
+            {
+                true => report.managed.push(dependency),
+                false => report.missing.push(dependency),
+            }
+        }
+
+        report.unmanaged = dev_dependencies
+            .into_iter()
+            .filter(|name| !report.managed.iter().any(|managed| managed.name == *name))
+            .collect();
+        report.unmanaged.sort();
+
+        Ok(report)
+    }
+
+    /// Stops expecting `packages` from the installed `provider`, so they are
+    /// no longer reported missing.
+    pub async fn untrack(
+        project: &ProjectContext,
+        provider: &str,
+        packages: &[String],
+    ) -> Result<(), ConfigError> {
+        let mut state = AmarisProjectState::load(project).await?;
+        state.record_untracked(provider, packages);
+        state.save(project).await
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{
+    config::ProjectOverrides,
+    provider::{AmarisRegistry, ProjectContext},
+    reporter::Reporter,
+    utils::{AmarisBiomeHandler, AmarisInstaller, BIOME_PACKAGE},
+};
+
+/// `amaya doctor`: the prerequisites and conflicts of every provider, the
+/// project overrides and a Biome schema behind the installed version.
+pub struct AmarisDoctor;
+
+impl AmarisDoctor {
+    pub async fn run(project: &ProjectContext, registry: &AmarisRegistry) -> anyhow::Result<()> {
+        let reporter = Reporter::global();
+        if let Some(installed) =
+            AmarisInstaller::get_installed_version(project, BIOME_PACKAGE).await
+            && let Some(schema) = AmarisBiomeHandler::get_installed_schema_version(project).await
+            && AmarisBiomeHandler::schema_url(&installed) != AmarisBiomeHandler::schema_url(&schema)
+        {
+            reporter.warn(format!(
+                "Warning: biome.json uses the schema of Biome {}, but {} is installed",
+                schema, installed
+            ));
+        }
+
+        let overrides = ProjectOverrides::load(project).await?;
+        for issue in overrides.issues(registry) {
+            reporter.warn(format!(
+                "Warning: {}: {}",
+                ProjectOverrides::get_default_path(project).display(),
+                issue
+            ));
+        }
+        for line in overrides.describe() {
+            reporter.info(format!("Overrides in effect for {}", line));
+        }
+
+        for (name, _) in registry.available_configs() {
+            if let Some(provider) = registry.get_provider(name) {
+                match provider.check_prerequisites(project).await {
+                    Ok(_) => reporter.success(format!(
+                        "All prerequisites met for {}",
+                        reporter.provider(name)
+                    )),
+                    Err(e) => reporter.error(format!(
+                        "All prerequisites are not met for {}\n{}",
+                        reporter.provider(name),
+                        e
+                    )),
+                }
+
+                if let Err(e) = provider.check_conflicts(project).await {
+                    reporter.warn(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
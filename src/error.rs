@@ -26,6 +26,18 @@ pub enum ConfigError {
 
     #[error("File read error: {0}")]
     FileReadError(String),
+
+    #[error("Git error: {0}")]
+    GitError(String),
+
+    #[error("Network error: {0}")]
+    NetworkError(String),
+
+    #[error("Circular dependency detected: {0}")]
+    CircularDependency(String),
+
+    #[error("Project is locked: {0}")]
+    Locked(String),
 }
 
 impl From<serde_json::Error> for ConfigError {
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ProjectOverrides,
+    error::ConfigError,
+    provider::{DynamicProvider, ProjectContext},
+    state::AmarisProjectState,
+    utils::{AmarisConfigurationHandler, AmarisFileHandler},
+};
+
+pub const EXPORT_FILE_NAME: &str = "amaya-export.json";
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// The providers of a project in a single portable file, configuration
+/// sources may be embedded through `inline_content`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProjectExport {
+    pub format_version: u32,
+    pub providers: Vec<DynamicProvider>,
+    /// Overrides from `.amaya/overrides.json` in effect for the providers,
+    /// keyed by provider name.
+    #[serde(default, skip_serializing_if = "ProjectOverrides::is_empty")]
+    pub overrides: ProjectOverrides,
+}
+
+impl ProjectExport {
+    /// The providers installed in `project`, each with its `extends` resolved
+    /// and its sources embedded, and the overrides in effect for them.
+    pub async fn create(project: &ProjectContext) -> Result<Self, ConfigError> {
+        let state = AmarisProjectState::load(project).await?;
+        let mut overrides = ProjectOverrides::load(project).await?;
+
+        let mut providers = vec![];
+        for mut provider in DynamicProvider::load_all(project, None).await? {
+            if !state.is_installed(&provider.name) {
+                continue;
+            }
+
+            let name = provider.name.clone();
+            let entries = provider.configuration.iter_mut().chain(
+                provider
+                    .variants
+                    .iter_mut()
+                    .flat_map(|variant| variant.configuration.iter_mut()),
+            );
+            for config in entries {
+                if config.inline_content.is_none() {
+                    let source =
+                        AmarisConfigurationHandler::read_source(project, &name, config).await?;
+                    config.inline_content = Some(BASE64_STANDARD.encode(source));
+                    config.source_from = String::new();
+                }
+            }
+            provider.extends.clear();
+            providers.push(provider);
+        }
+
+        let overrides = ProjectOverrides {
+            providers: providers
+                .iter()
+                .filter_map(|provider| {
+                    let provider_overrides = overrides.providers.remove(&provider.name)?;
+                    Some((provider.name.clone(), provider_overrides))
+                })
+                .collect(),
+        };
+
+        Ok(ProjectExport {
+            format_version: EXPORT_FORMAT_VERSION,
+            providers,
+            overrides,
+        })
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?)
+            .await
+            .map_err(|e| ConfigError::FileWriteError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Reads an export file and validates every provider in it.
+    pub async fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content = AmarisFileHandler::load_file(path).await?;
+
+        let mut export: ProjectExport = serde_json::from_str(&content)
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))?;
+
+        if export.format_version != EXPORT_FORMAT_VERSION {
+            return Err(ConfigError::ValidationError(format!(
+                "{}: unsupported export format version {}",
+                path.display(),
+                export.format_version
+            )));
+        }
+
+        for provider in &mut export.providers {
+            provider.source = Some(path.to_path_buf());
+            provider.check()?;
+        }
+
+        Ok(export)
+    }
+}
@@ -0,0 +1,275 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use reqwest::{StatusCode, header};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bundle::{AmarisBundle, BUNDLE_EXTENSION},
+    config::AmayaConfig,
+    error::ConfigError,
+    network::{AmarisNetwork, describe_error},
+    provider::ProjectContext,
+    reporter::Reporter,
+    utils::{AmarisPathHandler, sha256_integrity},
+};
+
+const INDEX_CACHE_FILE_NAME: &str = "index.json";
+const BUNDLE_CACHE_DIR_NAME: &str = "bundles";
+
+/// A provider published in the registry index.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexEntry {
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    /// Where the bundle created by `amaya pack` can be downloaded.
+    pub url: String,
+    /// `sha256:<hex>` digest of the bundle.
+    pub checksum: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct RegistryIndex {
+    pub providers: Vec<IndexEntry>,
+}
+
+/// The last index fetched, stored as `~/.amaya/cache/index.json`.
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedIndex {
+    url: String,
+    etag: Option<String>,
+    fetched_at: DateTime<Utc>,
+    index: RegistryIndex,
+}
+
+/// Fetches the provider index and bundles, falling back to the local cache
+/// when the registry cannot be reached.
+pub struct AmarisIndex;
+
+impl AmarisIndex {
+    pub async fn load(project: &ProjectContext) -> Result<RegistryIndex, ConfigError> {
+        let url = AmayaConfig::load(project)
+            .await?
+            .registry_url
+            .ok_or_else(|| {
+                ConfigError::ValidationError(
+                    "No registry configured, set registry_url in ~/.amaya/config.toml".to_string(),
+                )
+            })?;
+
+        let cache_path = AmarisPathHandler::ensure_cache_dir(project)
+            .await?
+            .join(INDEX_CACHE_FILE_NAME);
+        let cached = Self::read_cache(&cache_path)
+            .await
+            .filter(|cached| cached.url == url);
+
+        if AmarisNetwork::global().is_offline()
+            && let Some(cached) = cached
+        {
+            return Ok(cached.index);
+        }
+
+        match Self::fetch(
+            project,
+            &url,
+            cached.as_ref().and_then(|c| c.etag.as_deref()),
+        )
+        .await
+        {
+            Ok(Some((index, etag))) => {
+                let cached = CachedIndex {
+                    url,
+                    etag,
+                    fetched_at: Utc::now(),
+                    index,
+                };
+                tokio::fs::write(&cache_path, serde_json::to_string_pretty(&cached)?).await?;
+
+                Ok(cached.index)
+            }
+            Ok(None) => Ok(cached.map(|cached| cached.index).unwrap_or_default()),
+            Err(e) => {
+                let Some(cached) = cached else {
+                    return Err(e);
+                };
+
+                Reporter::global().warn(format!(
+                    "Warning: {}, using the cached index from {}",
+                    e,
+                    cached.fetched_at.format("%Y-%m-%d %H:%M UTC")
+                ));
+
+                Ok(cached.index)
+            }
+        }
+    }
+
+    /// Providers whose name or description contains `query`, ignoring case.
+    pub fn search<'a>(index: &'a RegistryIndex, query: &str) -> Vec<&'a IndexEntry> {
+        let query = query.to_lowercase();
+
+        index
+            .providers
+            .iter()
+            .filter(|entry| {
+                entry.name.to_lowercase().contains(&query)
+                    || entry.description.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    /// Downloads the bundle of `name`, verifies its checksum and unpacks it.
+    /// A previously downloaded bundle is used when it is still current.
+    pub async fn add(project: &ProjectContext, name: &str) -> Result<IndexEntry, ConfigError> {
+        let index = Self::load(project).await?;
+        let entry = index
+            .providers
+            .iter()
+            .find(|entry| entry.name == name)
+            .cloned()
+            .ok_or_else(|| {
+                ConfigError::ValidationError(format!("'{}' was not found in the registry", name))
+            })?;
+
+        let bundle_dir = AmarisPathHandler::ensure_cache_dir(project)
+            .await?
+            .join(BUNDLE_CACHE_DIR_NAME);
+        tokio::fs::create_dir_all(&bundle_dir).await?;
+
+        let bundle_path: PathBuf = bundle_dir.join(format!(
+            "{}-{}.{}",
+            entry.name, entry.version, BUNDLE_EXTENSION
+        ));
+
+        let cached = tokio::fs::read(&bundle_path)
+            .await
+            .ok()
+            .filter(|content| sha256_integrity(content) == entry.checksum);
+
+        if cached.is_none() {
+            let content = Self::download(project, &entry.url).await?;
+
+            let actual = sha256_integrity(&content);
+            if actual != entry.checksum {
+                return Err(ConfigError::ValidationError(format!(
+                    "{}: checksum mismatch, expected {} but found {}",
+                    entry.url, entry.checksum, actual
+                )));
+            }
+
+            tokio::fs::write(&bundle_path, &content).await?;
+        }
+
+        AmarisBundle::unpack(project, &bundle_path).await?;
+
+        Ok(entry)
+    }
+
+    /// Deletes the downloaded bundles of `name`, returning how many there were.
+    pub async fn clear_cached_bundles(
+        project: &ProjectContext,
+        name: &str,
+    ) -> Result<usize, ConfigError> {
+        let bundle_dir = AmarisPathHandler::ensure_cache_dir(project)
+            .await?
+            .join(BUNDLE_CACHE_DIR_NAME);
+
+        if !bundle_dir.exists() {
+            return Ok(0);
+        }
+
+        let prefix = format!("{}-", name);
+        let mut removed = 0;
+        let mut entries = tokio::fs::read_dir(&bundle_dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            // `<name>-<version>`, so `biome` does not match `biome-react-1.0.0`
+            let is_bundle = file_name
+                .strip_prefix(&prefix)
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+
+            if is_bundle {
+                tokio::fs::remove_file(entry.path()).await?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    async fn read_cache(path: &PathBuf) -> Option<CachedIndex> {
+        let content = tokio::fs::read_to_string(path).await.ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// `None` when the server reports the cached copy is still current.
+    async fn fetch(
+        project: &ProjectContext,
+        url: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<(RegistryIndex, Option<String>)>, ConfigError> {
+        let operation = "fetching the registry index";
+        let network = AmarisNetwork::global();
+
+        let mut request = network.client(project, operation).await?.get(url);
+        if let Some(etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+
+        let response = network.send(project, operation, request).await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(ConfigError::NetworkError(format!(
+                "{} returned {}",
+                url,
+                response.status()
+            )));
+        }
+
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ConfigError::NetworkError(format!("{}: {}", url, describe_error(&e))))?;
+
+        let index = serde_json::from_slice(&body)
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", url, e)))?;
+
+        Ok(Some((index, etag)))
+    }
+
+    async fn download(project: &ProjectContext, url: &str) -> Result<Vec<u8>, ConfigError> {
+        let operation = "downloading a provider bundle";
+        let network = AmarisNetwork::global();
+
+        let request = network.client(project, operation).await?.get(url);
+        let response = network.send(project, operation, request).await?;
+
+        if !response.status().is_success() {
+            return Err(ConfigError::NetworkError(format!(
+                "{} returned {}",
+                url,
+                response.status()
+            )));
+        }
+
+        Ok(response
+            .bytes()
+            .await
+            .map_err(|e| ConfigError::NetworkError(format!("{}: {}", url, describe_error(&e))))?
+            .to_vec())
+    }
+}
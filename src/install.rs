@@ -0,0 +1,567 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use futures::future::join_all;
+use inquire::{Confirm, Select};
+use tokio::sync::Semaphore;
+
+use crate::{
+    args::{
+        InstallArgs, check_installable, commit_changes, confirm_deprecated, confirm_dirty_worktree,
+        install_options, install_provider, provider_save_path, select_variant,
+    },
+    config::AmayaConfig,
+    error::ConfigError,
+    preview::AmarisPreview,
+    provider::{AmarisProvider, AmarisRegistry, DynamicProvider, InstallOptions, ProjectContext},
+    reporter::Reporter,
+    state::AmarisProjectState,
+    stream::AmarisStream,
+    utils::{AmarisConfigurationHandler, AmarisVisualStudioCodeHandler},
+};
+
+/// `amaya install`: a provider by name, a group, every provider with `--all`,
+/// a provider file with `--from-file`, or a preview project with `--preview`.
+pub struct AmarisInstall;
+
+impl AmarisInstall {
+    pub async fn run(
+        project: &ProjectContext,
+        registry: &AmarisRegistry,
+        args: &InstallArgs,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let reporter = Reporter::global();
+        let InstallArgs {
+            config,
+            name,
+            all,
+            concurrency,
+            from_file,
+            save,
+            variant: requested_variant,
+            force,
+            allow_deprecated,
+            allow_dirty,
+            commit,
+            variables,
+            env,
+            editors,
+            workspace_file,
+            preview,
+            clean,
+            scope,
+            // Handled in main, before anything is printed
+            output: _,
+        } = args;
+
+        let phases = scope.phases()?;
+        let settings = AmayaConfig::load(project).await?;
+
+        if *clean {
+            let removed = AmarisPreview::clean().await?;
+            reporter.success(format!(
+                "Removed {} preview {}",
+                removed,
+                match removed {
+                    1 => "project",
+                    _ => "projects",
+                }
+            ));
+            return Ok(());
+        }
+
+        if *preview {
+            // The preview project is thrown away, its files need no backups
+            let options = InstallOptions {
+                phases,
+                backups: false,
+                ..install_options(&settings, timeout, variables, env, editors)
+            };
+
+            let Some(path) = from_file else {
+                let Some(name) = config.as_ref().or(name.as_ref()) else {
+                    anyhow::bail!("Name the configuration to preview");
+                };
+                return install_preview(
+                    registry,
+                    name,
+                    requested_variant.as_deref(),
+                    *allow_deprecated,
+                    &options,
+                )
+                .await;
+            };
+
+            let mut provider = DynamicProvider::load_from_file(project, path).await?;
+            provider.read_sources_from(&provider_file_dir(path)?);
+            let name = provider.name.clone();
+            let mut file_registry = AmarisRegistry::new();
+            file_registry.register(provider.into());
+
+            return install_preview(
+                &file_registry,
+                &name,
+                requested_variant.as_deref(),
+                *allow_deprecated,
+                &options,
+            )
+            .await;
+        }
+        let workspace_file = match workspace_file {
+            Some(path) => Some(std::path::absolute(path)?),
+            None => AmarisVisualStudioCodeHandler::find_workspace_file(project),
+        };
+
+        if let Some(path) = from_file {
+            let mut provider = DynamicProvider::load_from_file(project, path).await?;
+            provider.read_sources_from(&provider_file_dir(path)?);
+            let name = provider.name.clone();
+            let source_copies = match save {
+                true => provider_source_copies(project, &provider)?,
+                false => Vec::new(),
+            };
+
+            let mut file_registry = AmarisRegistry::new();
+            file_registry.register(provider.into());
+            let provider = file_registry.get_provider(&name).unwrap();
+
+            if confirm_deprecated(&file_registry, &name, *allow_deprecated)
+                .await?
+                .is_none()
+            {
+                reporter.warn("Aborted.");
+                return Ok(());
+            }
+
+            let variant = select_variant(project, provider, requested_variant.as_deref()).await?;
+            let variant_provider = variant
+                .as_deref()
+                .map(|variant| provider.with_variant(variant))
+                .transpose()?;
+            let provider = variant_provider.as_deref().unwrap_or(provider);
+
+            let save_path = match save {
+                true => Some(provider_save_path(project, path).await?),
+                false => None,
+            };
+
+            check_installable(project, provider, *force).await?;
+
+            if !*allow_dirty
+                && !confirm_dirty_worktree(project, &[(provider, workspace_file.as_deref())])
+                    .await?
+            {
+                reporter.warn("Aborted.");
+                return Ok(());
+            }
+
+            let options = InstallOptions {
+                workspace_file: workspace_file.clone(),
+                phases: phases.clone(),
+                ..install_options(&settings, timeout, variables, env, editors)
+            };
+
+            install_provider(project, provider, variant.as_deref(), &options).await?;
+
+            if let Some(save_path) = save_path {
+                for (source, target) in &source_copies {
+                    if let Some(parent) = target.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    tokio::fs::copy(source, target)
+                        .await
+                        .map_err(|e| ConfigError::FileWriteError(e.to_string()))?;
+                }
+                tokio::fs::copy(path, &save_path)
+                    .await
+                    .map_err(|e| ConfigError::FileWriteError(e.to_string()))?;
+                reporter.success(format!("Saved provider to {}", save_path.display()));
+            }
+
+            return commit_changes(
+                project,
+                *commit,
+                "apply",
+                provider.name(),
+                provider.metadata().version.as_deref(),
+            )
+            .await;
+        }
+
+        let flags = GroupFlags {
+            force: *force,
+            allow_deprecated: *allow_deprecated,
+            allow_dirty: *allow_dirty,
+            commit: *commit,
+            concurrency: concurrency.get(),
+        };
+
+        if *all {
+            let state = AmarisProjectState::load(project).await?;
+            let members: Vec<String> = registry
+                .available_configs_sorted()
+                .into_iter()
+                .map(|(name, _)| name.to_string())
+                .filter(|name| !state.is_installed(name))
+                .filter(|name| {
+                    *allow_deprecated
+                        || registry
+                            .get_provider(name)
+                            .is_some_and(|provider| provider.metadata().deprecation.is_none())
+                })
+                .collect();
+
+            if members.is_empty() {
+                reporter.info("Every configuration is installed already.");
+                return Ok(());
+            }
+
+            let options = InstallOptions {
+                workspace_file,
+                phases,
+                ..install_options(&settings, timeout, variables, env, editors)
+            };
+
+            return install_group(project, registry, "all", &members, &flags, &options).await;
+        }
+
+        let config_name = match config.as_ref().or(name.as_ref()) {
+            Some(name) => name.clone(),
+            None => {
+                let configs: Vec<(&str, &str)> = registry.available_configs();
+                let options: Vec<_> = configs.iter().map(|(_, desc)| *desc).collect();
+                let selection =
+                    AmarisStream::select(Select::new("Select configuration to install:", options))?;
+                configs
+                    .iter()
+                    .find(|(_, desc)| *desc == selection)
+                    .map(|(name, _)| name.to_string())
+                    .unwrap()
+            }
+        };
+
+        let options = InstallOptions {
+            workspace_file,
+            phases,
+            ..install_options(&settings, timeout, variables, env, editors)
+        };
+
+        if registry.get_provider(&config_name).is_none()
+            && let Some(members) = settings.resolve_group(&config_name)?
+        {
+            return install_group(project, registry, &config_name, &members, &flags, &options)
+                .await;
+        }
+
+        if registry.get_provider(&config_name).is_some() {
+            let Some(config_name) =
+                confirm_deprecated(registry, &config_name, *allow_deprecated).await?
+            else {
+                reporter.warn("Aborted.");
+                return Ok(());
+            };
+            let Some(provider) = registry.get_provider(&config_name) else {
+                anyhow::bail!("Unknown configuration '{}'", config_name);
+            };
+
+            let state = AmarisProjectState::load(project).await?;
+            let missing: Vec<String> = registry
+                .resolve_install_order(std::slice::from_ref(&config_name))?
+                .into_iter()
+                .filter(|name| name != &config_name && !state.is_installed(name))
+                .collect();
+
+            if !missing.is_empty() {
+                return Err(ConfigError::MissingPrerequisite(format!(
+                    "{} depends on {}, install {} first",
+                    config_name,
+                    missing.join(", "),
+                    match missing.len() {
+                        1 => "it",
+                        _ => "them",
+                    }
+                ))
+                .into());
+            }
+
+            let variant = select_variant(project, provider, requested_variant.as_deref()).await?;
+            let variant_provider = variant
+                .as_deref()
+                .map(|variant| provider.with_variant(variant))
+                .transpose()?;
+            let provider = variant_provider.as_deref().unwrap_or(provider);
+
+            check_installable(project, provider, *force).await?;
+
+            if !*allow_dirty
+                && !confirm_dirty_worktree(
+                    project,
+                    &[(provider, options.workspace_file.as_deref())],
+                )
+                .await?
+            {
+                reporter.warn("Aborted.");
+                return Ok(());
+            }
+
+            install_provider(project, provider, variant.as_deref(), &options).await?;
+
+            commit_changes(
+                project,
+                *commit,
+                "apply",
+                provider.name(),
+                provider.metadata().version.as_deref(),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Installs `name` and the providers it depends on into a new preview
+/// project, then opens a shell there.
+async fn install_preview(
+    registry: &AmarisRegistry,
+    name: &str,
+    requested_variant: Option<&str>,
+    allow_deprecated: bool,
+    options: &InstallOptions,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+    let Some(name) = confirm_deprecated(registry, name, allow_deprecated).await? else {
+        reporter.warn("Aborted.");
+        return Ok(());
+    };
+    let order = registry.resolve_install_order(std::slice::from_ref(&name))?;
+
+    let dir = AmarisPreview::create(&name).await?;
+    reporter.info(format!("Created preview project {}", dir.display()));
+    let project = &ProjectContext::load(dir.clone()).await?;
+
+    for member in &order {
+        let Some(provider) = registry.get_provider(member) else {
+            anyhow::bail!("Unknown configuration '{}'", member);
+        };
+
+        let requested = requested_variant.filter(|_| *member == name);
+        let variant = select_variant(project, provider, requested).await?;
+        let variant_provider = variant
+            .as_deref()
+            .map(|variant| provider.with_variant(variant))
+            .transpose()?;
+        let provider = variant_provider.as_deref().unwrap_or(provider);
+
+        check_installable(project, provider, false).await?;
+        install_provider(project, provider, variant.as_deref(), options).await?;
+    }
+
+    AmarisPreview::open_shell(&dir).await?;
+    Ok(())
+}
+
+/// Directory a provider file's `source_from` paths are relative to.
+fn provider_file_dir(path: &Path) -> anyhow::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+
+    Ok(path.parent().map(Path::to_path_buf).unwrap_or_default())
+}
+
+/// Sources `--save` copies next to a provider file into the provider's config
+/// directory, refusing to replace existing ones.
+fn provider_source_copies(
+    project: &ProjectContext,
+    provider: &DynamicProvider,
+) -> anyhow::Result<Vec<(PathBuf, PathBuf)>> {
+    let entries = provider.configuration.iter().chain(
+        provider
+            .variants
+            .iter()
+            .flat_map(|variant| variant.configuration.iter()),
+    );
+
+    let mut copies: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for config in
+        entries.filter(|config| config.source_dir.is_some() && config.inline_content.is_none())
+    {
+        let source = AmarisConfigurationHandler::resolve_source(project, &provider.name, config)?;
+        let target = AmarisConfigurationHandler::saved_source(project, &provider.name, config)?;
+        if copies.iter().any(|(_, existing)| *existing == target) {
+            continue;
+        }
+        if target.exists() {
+            return Err(ConfigError::AlreadyExists(target.display().to_string()).into());
+        }
+
+        copies.push((source, target));
+    }
+
+    Ok(copies)
+}
+
+/// Flags of `amaya install` applying to every provider of a group.
+struct GroupFlags {
+    force: bool,
+    allow_deprecated: bool,
+    allow_dirty: bool,
+    commit: bool,
+    /// Providers of the same level checked at once.
+    concurrency: usize,
+}
+
+/// Installs every provider of a group after a single confirmation. Every
+/// provider is checked before anything is written; a failure partway keeps the
+/// providers installed so far, as a failed single install does.
+async fn install_group(
+    project: &ProjectContext,
+    registry: &AmarisRegistry,
+    group: &str,
+    members: &[String],
+    flags: &GroupFlags,
+    options: &InstallOptions,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::global();
+
+    for member in members {
+        if registry.get_provider(member).is_none() {
+            anyhow::bail!(
+                "Group '{}' references unknown configuration '{}'",
+                group,
+                member
+            );
+        }
+    }
+
+    let deprecated: Vec<&String> = members
+        .iter()
+        .filter(|member| {
+            registry
+                .get_provider(member)
+                .is_some_and(|provider| provider.metadata().deprecation.is_some())
+        })
+        .collect();
+    if !flags.allow_deprecated && !deprecated.is_empty() {
+        anyhow::bail!(
+            "Group '{}' includes deprecated configurations: {}, pass --allow-deprecated to install them",
+            group,
+            deprecated
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    // Dependencies outside of the group are only pulled in when not installed yet
+    let state = AmarisProjectState::load(project).await?;
+    let order: Vec<String> = registry
+        .resolve_install_order(members)?
+        .into_iter()
+        .filter(|name| members.contains(name) || !state.is_installed(name))
+        .collect();
+    let levels = registry.install_levels(&order);
+    let order: Vec<String> = levels.concat();
+
+    let mut candidates: Vec<(&dyn AmarisProvider, Option<String>)> = vec![];
+    for name in &order {
+        let provider = registry.get_provider(name).unwrap();
+        candidates.push((provider, select_variant(project, provider, None).await?));
+    }
+
+    let variant_providers = candidates
+        .iter()
+        .map(|(provider, variant)| {
+            variant
+                .as_deref()
+                .map(|variant| provider.with_variant(variant))
+                .transpose()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let providers: Vec<&dyn AmarisProvider> = candidates
+        .iter()
+        .zip(&variant_providers)
+        .map(|((provider, _), variant_provider)| variant_provider.as_deref().unwrap_or(*provider))
+        .collect();
+
+    check_installable_concurrently(project, &providers, &levels, flags).await?;
+
+    reporter.info(format!("Group {} will install:", reporter.provider(group)));
+    for provider in &providers {
+        reporter.info(format!(
+            "- {}: {}",
+            reporter.provider(provider.name()),
+            provider.description()
+        ));
+    }
+
+    if !AmarisStream::confirm(
+        Confirm::new(&format!("Install {} configurations?", providers.len())).with_default(true),
+    )? {
+        reporter.warn("Aborted.");
+        return Ok(());
+    }
+
+    let planned: Vec<_> = providers
+        .iter()
+        .map(|provider| (*provider, options.workspace_file.as_deref()))
+        .collect();
+    if !flags.allow_dirty && !confirm_dirty_worktree(project, &planned).await? {
+        reporter.warn("Aborted.");
+        return Ok(());
+    }
+
+    let mut installed: Vec<&str> = vec![];
+    for (provider, (_, variant)) in providers.iter().zip(&candidates) {
+        if let Err(e) = install_provider(project, *provider, variant.as_deref(), options).await {
+            if !installed.is_empty() {
+                reporter.warn(format!(
+                    "Installed before the failure: {}",
+                    installed.join(", ")
+                ));
+            }
+            return Err(e.context(format!("Failed to install {}", provider.name())));
+        }
+        installed.push(provider.name());
+    }
+
+    reporter.success(format!(
+        "Installed {} configurations from {}: {}",
+        installed.len(),
+        reporter.provider(group),
+        installed.join(", ")
+    ));
+
+    commit_changes(project, flags.commit, "apply", group, None).await
+}
+
+/// Checks the providers of each level at once, at most `concurrency` at a
+/// time, stopping at the first level with a failure. The installs themselves
+/// still run one after the other, as they all write package.json, the
+/// lockfile and often the same editor settings.
+async fn check_installable_concurrently(
+    project: &ProjectContext,
+    providers: &[&dyn AmarisProvider],
+    levels: &[Vec<String>],
+    flags: &GroupFlags,
+) -> anyhow::Result<()> {
+    let semaphore = Semaphore::new(flags.concurrency);
+
+    for level in levels {
+        let checks = providers
+            .iter()
+            .filter(|provider| level.iter().any(|name| name == provider.name()))
+            .map(|provider| async {
+                let _permit = semaphore.acquire().await?;
+                check_installable(project, *provider, flags.force).await
+            });
+
+        for result in join_all(checks).await {
+            result?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,440 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::ConfigError,
+    provider::ProjectContext,
+    state::AmarisProjectState,
+    utils::{AmarisVisualStudioCodeHandler, AmarisZedHandler, Editor, WORKSPACE_FILE_EXTENSION},
+};
+
+/// A setting amaya merged into a shared settings file for a provider, with
+/// the value it replaced. Providers installed later stack on top, so removing
+/// one hands its `previous` value to the provider above it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SettingContribution {
+    pub file: String,
+    /// Object keys leading to the setting. VS Code keys contain dots, so the
+    /// keys are kept apart instead of joined.
+    pub path: Vec<String>,
+    pub value: Value,
+    /// What the file had before the provider set it, `None` when it was unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<Value>,
+}
+
+impl SettingContribution {
+    fn same_setting(&self, other: &SettingContribution) -> bool {
+        self.file == other.file && self.path == other.path
+    }
+}
+
+/// Adds `new` to the contributions of a provider. A setting it already had
+/// keeps its original `previous`, which would otherwise be its own value.
+pub fn merge_contributions(
+    contributions: &mut Vec<SettingContribution>,
+    new: Vec<SettingContribution>,
+) {
+    for contribution in new {
+        match contributions
+            .iter_mut()
+            .find(|existing| existing.same_setting(&contribution))
+        {
+            Some(existing) => existing.value = contribution.value,
+            None => contributions.push(contribution),
+        }
+    }
+}
+
+/// Collects the settings merged into shared files during the current install,
+/// in the run of the project.
+pub struct AmarisContributions;
+
+impl AmarisContributions {
+    pub fn start(project: &ProjectContext) {
+        project.run.contributions.lock().unwrap().clear();
+    }
+
+    /// Records every setting of `source` about to be merged into `current`,
+    /// the settings object of `file`, relative to the project root.
+    pub fn record(project: &ProjectContext, file: &Path, current: &Value, source: &Value) {
+        let mut leaves = vec![];
+        collect_leaves(source, &mut vec![], &mut leaves);
+
+        let recorded = leaves
+            .into_iter()
+            .map(|(path, value)| SettingContribution {
+                file: file.display().to_string(),
+                previous: get_path(current, &path).cloned(),
+                path,
+                value,
+            })
+            .collect();
+
+        // Merged twice in one install, the first merge saw the real previous value
+        merge_contributions(&mut project.run.contributions.lock().unwrap(), recorded);
+    }
+
+    pub fn take(project: &ProjectContext) -> Vec<SettingContribution> {
+        std::mem::take(&mut *project.run.contributions.lock().unwrap())
+    }
+}
+
+/// The value to put at a setting path, `None` removes it.
+type SettingChange = (Vec<String>, Option<Value>);
+
+/// The shared settings of a provider as they are before its removal, so what
+/// it set can be handed back afterwards.
+pub struct SettingLayers {
+    provider: String,
+    settings: Vec<(SettingContribution, Option<Value>)>,
+}
+
+impl SettingLayers {
+    pub async fn capture(
+        project: &ProjectContext,
+        state: &AmarisProjectState,
+        name: &str,
+    ) -> Result<Self, ConfigError> {
+        let mut settings = vec![];
+
+        if let Some(record) = state.get(name) {
+            for contribution in &record.contributions {
+                let current = read_settings(project, Path::new(&contribution.file)).await?;
+                let value =
+                    current.and_then(|current| get_path(&current, &contribution.path).cloned());
+
+                settings.push((contribution.clone(), value));
+            }
+        }
+
+        Ok(Self {
+            provider: name.to_string(),
+            settings,
+        })
+    }
+
+    /// Runs after the provider took its settings out. A setting another
+    /// provider set on top is put back as it was, and that provider inherits
+    /// the value it replaced. A setting nobody set on top gets its previous
+    /// value back, unless it was changed since.
+    pub async fn restore(
+        self,
+        project: &ProjectContext,
+        state: &mut AmarisProjectState,
+    ) -> Result<(), ConfigError> {
+        let Some(position) = state
+            .installed_providers
+            .iter()
+            .position(|record| record.name == self.provider)
+        else {
+            return Ok(());
+        };
+
+        let mut files: Vec<(PathBuf, Vec<SettingChange>)> = vec![];
+
+        for (contribution, before) in self.settings {
+            let above = state.installed_providers[position + 1..]
+                .iter_mut()
+                .flat_map(|record| record.contributions.iter_mut())
+                .find(|other| other.same_setting(&contribution));
+
+            let target = match above {
+                Some(above) => {
+                    above.previous = contribution.previous;
+                    before
+                }
+                None if before.as_ref() == Some(&contribution.value) => contribution.previous,
+                None => continue,
+            };
+
+            let file = PathBuf::from(&contribution.file);
+            match files.iter_mut().find(|(path, _)| path == &file) {
+                Some((_, changes)) => changes.push((contribution.path, target)),
+                None => files.push((file, vec![(contribution.path, target)])),
+            }
+        }
+
+        for (file, changes) in files {
+            let Some(mut settings) = read_settings(project, &file).await? else {
+                continue;
+            };
+            let original = settings.clone();
+
+            for (path, target) in &changes {
+                match target {
+                    Some(value) => set_path(&mut settings, path, value.clone()),
+                    None => remove_path(&mut settings, path),
+                }
+            }
+
+            if settings != original {
+                write_settings(project, &file, settings).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_workspace_file(file: &Path) -> bool {
+    file.extension()
+        .is_some_and(|extension| extension == WORKSPACE_FILE_EXTENSION)
+}
+
+/// The settings object of a shared file of `project`, `None` when the file
+/// is gone.
+async fn read_settings(
+    project: &ProjectContext,
+    file: &Path,
+) -> Result<Option<Value>, ConfigError> {
+    let path = project.path(file);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let settings = match file {
+        file if is_workspace_file(file) => {
+            AmarisVisualStudioCodeHandler::read_settings(project, Some(&path)).await?
+        }
+        file if file == Editor::Zed.settings_path() => AmarisZedHandler::read(project).await?,
+        _ => AmarisVisualStudioCodeHandler::read_settings(project, None).await?,
+    };
+
+    Ok(Some(settings))
+}
+
+async fn write_settings(
+    project: &ProjectContext,
+    file: &Path,
+    settings: Value,
+) -> Result<(), ConfigError> {
+    match file {
+        file if is_workspace_file(file) => {
+            AmarisVisualStudioCodeHandler::update_workspace(
+                project,
+                &project.path(file),
+                |current| *current = settings,
+            )
+            .await
+        }
+        file if file == Editor::Zed.settings_path() => {
+            AmarisZedHandler::write(project, &settings).await
+        }
+        _ => AmarisVisualStudioCodeHandler::write(project, &settings).await,
+    }
+}
+
+/// Flattens `value` into the paths of its non-object values.
+fn collect_leaves(value: &Value, path: &mut Vec<String>, leaves: &mut Vec<(Vec<String>, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                path.push(key.clone());
+                collect_leaves(value, path, leaves);
+                path.pop();
+            }
+        }
+        _ if !path.is_empty() => leaves.push((path.clone(), value.clone())),
+        _ => {}
+    }
+}
+
+fn get_path<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(key))
+}
+
+fn set_path(value: &mut Value, path: &[String], new: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+
+    let mut current = value;
+    for key in parents {
+        if !current.get(key).is_some_and(Value::is_object) {
+            current[key] = serde_json::json!({});
+        }
+        current = &mut current[key];
+    }
+
+    current[last] = new;
+}
+
+/// Removes the value at `path`, dropping objects that end up empty.
+fn remove_path(value: &mut Value, path: &[String]) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    let Some(map) = value.as_object_mut() else {
+        return;
+    };
+
+    if rest.is_empty() {
+        map.remove(first);
+        return;
+    }
+
+    if let Some(child) = map.get_mut(first) {
+        remove_path(child, rest);
+
+        if child.as_object().is_some_and(|child| child.is_empty()) {
+            map.remove(first);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::state::InstallRecord;
+
+    const SETTINGS: &str = ".vscode/settings.json";
+
+    fn key(path: &[&str]) -> Vec<String> {
+        path.iter().map(|key| key.to_string()).collect()
+    }
+
+    fn record(name: &str, contributions: &[(&[&str], Value, Option<Value>)]) -> InstallRecord {
+        InstallRecord {
+            name: name.to_string(),
+            contributions: contributions
+                .iter()
+                .map(|(path, value, previous)| SettingContribution {
+                    file: SETTINGS.to_string(),
+                    path: key(path),
+                    value: value.clone(),
+                    previous: previous.clone(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// A project where `a` and then `b` set the same settings on top of the
+    /// user's own tab size.
+    async fn stacked_project() -> (tempfile::TempDir, ProjectContext, AmarisProjectState) {
+        let dir = tempfile::tempdir().unwrap();
+        let project = ProjectContext {
+            root: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let formatter: &[&str] = &["[typescript]", "editor.defaultFormatter"];
+        let state = AmarisProjectState {
+            installed_providers: vec![
+                record(
+                    "a",
+                    &[
+                        (&["editor.tabSize"], json!(4), Some(json!(2))),
+                        (formatter, json!("a.formatter"), None),
+                    ],
+                ),
+                record(
+                    "b",
+                    &[
+                        (&["editor.tabSize"], json!(8), Some(json!(4))),
+                        (formatter, json!("b.formatter"), Some(json!("a.formatter"))),
+                    ],
+                ),
+            ],
+        };
+
+        AmarisVisualStudioCodeHandler::write(
+            &project,
+            &json!({
+                "editor.tabSize": 8,
+                "[typescript]": { "editor.defaultFormatter": "b.formatter" },
+                "files.eol": "\n",
+            }),
+        )
+        .await
+        .unwrap();
+
+        (dir, project, state)
+    }
+
+    /// Removes `name` the way `amaya remove` does, its own settings taken
+    /// out by the provider in between capturing and restoring the layers.
+    async fn remove(project: &ProjectContext, state: &mut AmarisProjectState, name: &str) {
+        let layers = SettingLayers::capture(project, state, name).await.unwrap();
+        let own = state.get(name).unwrap().contributions.clone();
+        AmarisVisualStudioCodeHandler::update(project, |settings| {
+            for contribution in &own {
+                remove_path(settings, &contribution.path);
+            }
+        })
+        .await
+        .unwrap();
+
+        layers.restore(project, state).await.unwrap();
+        state.record_remove(name);
+    }
+
+    async fn settings(project: &ProjectContext) -> Value {
+        AmarisVisualStudioCodeHandler::read_settings(project, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn removing_the_lower_provider_first_keeps_the_upper_value() {
+        let (_dir, project, mut state) = stacked_project().await;
+
+        remove(&project, &mut state, "a").await;
+        assert_eq!(
+            settings(&project).await,
+            json!({
+                "editor.tabSize": 8,
+                "[typescript]": { "editor.defaultFormatter": "b.formatter" },
+                "files.eol": "\n",
+            })
+        );
+        let b = &state.get("b").unwrap().contributions;
+        assert_eq!(b[0].previous, Some(json!(2)));
+        assert_eq!(b[1].previous, None);
+
+        remove(&project, &mut state, "b").await;
+        assert_eq!(
+            settings(&project).await,
+            json!({ "editor.tabSize": 2, "files.eol": "\n" })
+        );
+    }
+
+    #[tokio::test]
+    async fn removing_the_upper_provider_first_restores_the_lower_value() {
+        let (_dir, project, mut state) = stacked_project().await;
+
+        remove(&project, &mut state, "b").await;
+        assert_eq!(
+            settings(&project).await,
+            json!({
+                "editor.tabSize": 4,
+                "[typescript]": { "editor.defaultFormatter": "a.formatter" },
+                "files.eol": "\n",
+            })
+        );
+
+        remove(&project, &mut state, "a").await;
+        assert_eq!(
+            settings(&project).await,
+            json!({ "editor.tabSize": 2, "files.eol": "\n" })
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_a_setting_changed_since_the_install() {
+        let (_dir, project, mut state) = stacked_project().await;
+        AmarisVisualStudioCodeHandler::update(&project, |settings| {
+            settings["editor.tabSize"] = json!(3)
+        })
+        .await
+        .unwrap();
+
+        let layers = SettingLayers::capture(&project, &state, "b").await.unwrap();
+        layers.restore(&project, &mut state).await.unwrap();
+        assert_eq!(settings(&project).await["editor.tabSize"], json!(3));
+    }
+}
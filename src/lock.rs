@@ -0,0 +1,172 @@
+use std::{
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::ConfigError;
+
+/// Prefix of the lock files in the temporary directory.
+const LOCK_FILE_PREFIX: &str = "amaya-";
+/// How often a held lock is checked again while waiting for it.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Age after which a lock file that cannot be read is considered abandoned.
+const UNREADABLE_GRACE: Duration = Duration::from_secs(2);
+
+/// Contents of a lock file, describing the process holding it.
+#[derive(Debug, Serialize, Deserialize)]
+struct LockOwner {
+    pid: u32,
+    started_at: DateTime<Utc>,
+}
+
+/// Advisory lock held by commands that change a project, so two amaya
+/// processes do not write its files at the same time.
+///
+/// The lock is a file in the temporary directory named after a hash of the
+/// project path, which keeps it out of the project and its git status. It is
+/// deleted when the guard is dropped, and a lock left behind by a process
+/// that is no longer running is taken over.
+#[derive(Debug)]
+pub struct AmarisLock {
+    path: PathBuf,
+}
+
+impl AmarisLock {
+    /// Takes the lock of `project`. When another process holds it, this
+    /// fails right away, or after `wait` if the lock was not released by then.
+    pub async fn acquire(project: &Path, wait: Option<Duration>) -> Result<Self, ConfigError> {
+        let path = Self::path_for(project)?;
+        let deadline = wait.map(|wait| Instant::now() + wait);
+
+        loop {
+            let owner = match Self::try_create(&path)? {
+                None => return Ok(Self { path }),
+                Some(owner) => owner,
+            };
+
+            match owner {
+                Some(owner) if is_running(owner.pid) => {
+                    if deadline.is_none_or(|deadline| Instant::now() >= deadline) {
+                        return Err(held_error(&owner, wait));
+                    }
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+                // Unreadable locks are still being written by the process
+                // that just took them, unless that one died in between
+                None if !is_abandoned(&path) => tokio::time::sleep(POLL_INTERVAL).await,
+                _ => {
+                    tracing::debug!(path = %path.display(), "removing stale lock");
+                    remove_lock(&path)?;
+                }
+            }
+        }
+    }
+
+    fn path_for(project: &Path) -> Result<PathBuf, ConfigError> {
+        let project = project
+            .canonicalize()
+            .map_err(|e| ConfigError::PathError(format!("{}: {}", project.display(), e)))?;
+        let digest = Sha256::digest(project.to_string_lossy().as_bytes());
+        let hex: String = digest
+            .iter()
+            .take(8)
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Ok(std::env::temp_dir().join(format!("{}{}.lock", LOCK_FILE_PREFIX, hex)))
+    }
+
+    /// Creates the lock file, returning `None` when it was created and the
+    /// current owner otherwise, which is `None` too if it cannot be read.
+    fn try_create(path: &Path) -> Result<Option<Option<LockOwner>>, ConfigError> {
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path);
+
+        match file {
+            Ok(mut file) => {
+                let owner = LockOwner {
+                    pid: std::process::id(),
+                    started_at: Utc::now(),
+                };
+                file.write_all(serde_json::to_string(&owner)?.as_bytes())
+                    .map_err(|e| {
+                        ConfigError::FileWriteError(format!("{}: {}", path.display(), e))
+                    })?;
+                Ok(None)
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let owner = std::fs::read_to_string(path)
+                    .ok()
+                    .and_then(|content| serde_json::from_str(&content).ok());
+                Ok(Some(owner))
+            }
+            Err(e) => Err(ConfigError::FileWriteError(format!(
+                "{}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+}
+
+impl Drop for AmarisLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn remove_lock(path: &Path) -> Result<(), ConfigError> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(ConfigError::FileWriteError(format!(
+            "{}: {}",
+            path.display(),
+            e
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn is_abandoned(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= UNREADABLE_GRACE)
+}
+
+fn held_error(owner: &LockOwner, wait: Option<Duration>) -> ConfigError {
+    let started_at = owner
+        .started_at
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S");
+    let hint = match wait {
+        Some(wait) => format!("did not finish within {}s", wait.as_secs()),
+        None => "retry with --wait to wait for it".to_string(),
+    };
+
+    ConfigError::Locked(format!(
+        "another amaya process (pid {}, started at {}) is running in this project, {}",
+        owner.pid, started_at, hint
+    ))
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // Signal 0 only checks whether the process exists, EPERM means it does
+    // but belongs to another user
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    // Without a portable check the lock is never considered stale
+    true
+}
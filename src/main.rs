@@ -1,25 +1,125 @@
 pub mod args;
+pub mod bundle;
+pub mod cache;
+pub mod ci;
+pub mod completions;
+pub mod config;
+pub mod deps;
+pub mod doctor;
 pub mod error;
+pub mod export;
+pub mod index;
+pub mod install;
+pub mod layers;
+pub mod lock;
+pub mod merge;
+pub mod network;
+pub mod npm_providers;
+pub mod package_manager;
+pub mod preview;
 pub mod provider;
+pub mod providers;
+pub mod reporter;
+pub mod retry;
+pub mod run;
+pub mod runner;
+pub mod selftest;
+pub mod state;
+pub mod status;
+pub mod stream;
+pub mod summary;
+pub mod sync;
+pub mod templates;
+pub mod transaction;
+pub mod updates;
+pub mod upgrade;
 pub mod utils;
+pub mod validate;
+pub mod watch;
+
+use std::sync::Arc;
 
 use args::CLI;
+use cache::FilesystemCache;
 use clap::Parser;
-use provider::{AmarisRegistry, DynamicProvider};
+use config::AmayaConfig;
+use network::AmarisNetwork;
+use package_manager::AmarisPackageManager;
+use provider::{AmarisAliases, AmarisRegistry, DynamicProvider, ProjectContext};
+use reporter::{ColorMode, Reporter};
+use stream::{AmarisStream, StreamEvent};
+use tracing_subscriber::EnvFilter;
+use utils::{AmarisFileHandler, AmarisInstaller};
+
+/// Environment variable holding the log filter when `--log-level` is not given.
+const LOG_ENV_VAR: &str = "AMAYA_LOG";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let cli: CLI = CLI::parse();
+    let mut cli: CLI = CLI::parse();
+
+    Reporter::init(ColorMode::detect(cli.global.no_color));
+    if cli.command.streams_events() {
+        AmarisStream::enable();
+        Reporter::global().use_stderr();
+    }
+
+    let filter = cli.global.log_level.take().unwrap_or_else(|| {
+        EnvFilter::try_from_env(LOG_ENV_VAR).unwrap_or_else(|_| EnvFilter::new("warn"))
+    });
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(Reporter::global().colors_enabled())
+        .init();
+    let project = match ProjectContext::detect(cli.global.cwd.as_deref()).await {
+        Ok(project) => project,
+        Err(e) => fail(e.into()),
+    };
+
+    AmarisNetwork::init(cli.global.offline);
+    AmarisPackageManager::init(cli.global.package_manager.clone());
+    FilesystemCache::init(Arc::new(FilesystemCache::new()));
+
+    if let Err(e) = args::first_run(&project, &cli.command).await {
+        fail(e);
+    }
+
+    // A broken config.toml is reported by the commands that read it
+    let config = AmayaConfig::load(&project).await.ok();
+    if let Some(config) = &config {
+        AmarisFileHandler::set_max_file_size(config.max_file_size_mb);
+        AmarisInstaller::set_error_output_lines(config.error_output_lines);
+    }
 
     let mut registry: AmarisRegistry = AmarisRegistry::new();
 
-    let providers = DynamicProvider::load_all(None).await?;
-    for provider in providers {
+    for provider in providers::builtin_providers() {
         registry.register(provider);
     }
 
-    match cli.command.execute(&registry).await {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
+    let providers = DynamicProvider::load_all(&project, None).await?;
+    for provider in providers {
+        registry.register(provider.into());
     }
+    if let Some(config) = &config {
+        registry.apply_options(&config.options);
+    }
+    AmarisAliases::init(registry.aliases().clone());
+
+    if let Err(e) = cli.command.execute(&registry, &cli.global, &project).await {
+        fail(e);
+    }
+
+    Ok(())
+}
+
+/// Reports `error` and exits with 1, as an event too with `--output json-lines`.
+fn fail(error: anyhow::Error) -> ! {
+    let message = format!("{:#}", error);
+    AmarisStream::send(&StreamEvent::Error {
+        message: message.clone(),
+    });
+    Reporter::global().error(format!("Error: {}", message));
+    std::process::exit(1);
 }
@@ -0,0 +1,297 @@
+use std::path::{Path, PathBuf};
+
+use inquire::Select;
+use serde_json::{Map, Value};
+
+use crate::{
+    error::ConfigError,
+    provider::ConfigFormat,
+    reporter::Reporter,
+    utils::{AmarisPlatformHandler, Indent, sanitize_output},
+};
+
+/// How to settle a change made both locally and by the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Keep the local change.
+    Ours,
+    /// Take the provider's change.
+    Theirs,
+}
+
+/// Merges the provider's changes to a file (`base` → `theirs`) into the local
+/// copy (`ours`), parsing them as `format`. Conflicts are settled by
+/// `resolution`, or interactively when it is `None`.
+pub async fn merge_three_way(
+    path: &Path,
+    format: ConfigFormat,
+    base: Option<&str>,
+    ours: &str,
+    theirs: &str,
+    resolution: Option<ConflictResolution>,
+) -> Result<String, ConfigError> {
+    if ours == theirs || base == Some(theirs) {
+        return Ok(ours.to_string());
+    }
+
+    if base == Some(ours) {
+        return Ok(theirs.to_string());
+    }
+
+    // Raw files are still merged by value when they hold JSON
+    let format = match format {
+        ConfigFormat::Raw => ConfigFormat::Json,
+        format => format,
+    };
+    let parse = |content: &str| format.parse(content).ok();
+
+    let base_json = match base {
+        Some(base) => parse(base).map(Some),
+        None => Some(None),
+    };
+
+    if let (Some(base_json), Some(ours_json), Some(theirs_json)) =
+        (base_json, parse(ours), parse(theirs))
+    {
+        let mut resolve = |key: &str, ours: Option<&Value>, theirs: Option<&Value>| {
+            resolve_conflict(path, key, ours, theirs, resolution)
+        };
+
+        let merged = merge_json(
+            "",
+            base_json.as_ref(),
+            Some(&ours_json),
+            Some(&theirs_json),
+            &mut resolve,
+        )?
+        .unwrap_or(Value::Object(Map::new()));
+
+        return format
+            .serialize(&merged, Indent::detect(ours))
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)));
+    }
+
+    merge_text(path, base.unwrap_or_default(), ours, theirs, resolution).await
+}
+
+type Resolver<'a> =
+    dyn FnMut(&str, Option<&Value>, Option<&Value>) -> Result<Option<Value>, ConfigError> + 'a;
+
+fn merge_json(
+    key: &str,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    resolve: &mut Resolver,
+) -> Result<Option<Value>, ConfigError> {
+    if ours == theirs || base == theirs {
+        return Ok(ours.cloned());
+    }
+
+    if base == ours {
+        return Ok(theirs.cloned());
+    }
+
+    match (ours, theirs) {
+        (Some(Value::Object(ours)), Some(Value::Object(theirs))) => {
+            let base = base.and_then(Value::as_object);
+            let mut merged = Map::new();
+
+            let mut keys: Vec<&String> = ours.keys().collect();
+            keys.extend(theirs.keys().filter(|k| !ours.contains_key(*k)));
+
+            for child in keys {
+                let child_key = if key.is_empty() {
+                    child.clone()
+                } else {
+                    format!("{}.{}", key, child)
+                };
+
+                let value = merge_json(
+                    &child_key,
+                    base.and_then(|b| b.get(child)),
+                    ours.get(child),
+                    theirs.get(child),
+                    resolve,
+                )?;
+
+                if let Some(value) = value {
+                    merged.insert(child.clone(), value);
+                }
+            }
+
+            Ok(Some(Value::Object(merged)))
+        }
+        _ => resolve(key, ours, theirs),
+    }
+}
+
+fn resolve_conflict(
+    path: &Path,
+    key: &str,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    resolution: Option<ConflictResolution>,
+) -> Result<Option<Value>, ConfigError> {
+    let describe = |value: Option<&Value>| match value {
+        Some(value) => value.to_string(),
+        None => "<removed>".to_string(),
+    };
+
+    let resolution = match resolution {
+        Some(resolution) => resolution,
+        None => {
+            let mine = format!("Keep mine: {}", describe(ours));
+            let provider = format!("Take provider's: {}", describe(theirs));
+
+            let selection = Select::new(
+                &format!("Conflict in {} at '{}':", path.display(), key),
+                vec![mine.clone(), provider],
+            )
+            .prompt()
+            .map_err(|_| {
+                ConfigError::ConflictError(format!(
+                    "{} at '{}' was changed both locally and by the provider, rerun with --ours or --theirs",
+                    path.display(),
+                    key
+                ))
+            })?;
+
+            if selection == mine {
+                ConflictResolution::Ours
+            } else {
+                ConflictResolution::Theirs
+            }
+        }
+    };
+
+    Ok(match resolution {
+        ConflictResolution::Ours => ours.cloned(),
+        ConflictResolution::Theirs => theirs.cloned(),
+    })
+}
+
+/// Textual three-way merge through `git merge-file`. Without a resolution,
+/// conflicts are left in the file as conflict markers.
+async fn merge_text(
+    path: &Path,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    resolution: Option<ConflictResolution>,
+) -> Result<String, ConfigError> {
+    let dir = std::env::temp_dir().join(format!("amaya-merge-{}", std::process::id()));
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let write = |name: &str, content: &str| {
+        let file: PathBuf = dir.join(name);
+        let content = content.to_string();
+        async move {
+            tokio::fs::write(&file, content).await?;
+            Ok::<PathBuf, ConfigError>(file)
+        }
+    };
+
+    let ours_path = write("ours", ours).await?;
+    let base_path = write("base", base).await?;
+    let theirs_path = write("theirs", theirs).await?;
+
+    let mut command = AmarisPlatformHandler::command("git");
+    command.args([
+        "merge-file",
+        "-p",
+        "-L",
+        "local",
+        "-L",
+        "base",
+        "-L",
+        "provider",
+    ]);
+    match resolution {
+        Some(ConflictResolution::Ours) => {
+            command.arg("--ours");
+        }
+        Some(ConflictResolution::Theirs) => {
+            command.arg("--theirs");
+        }
+        None => {}
+    }
+
+    let output = command
+        .arg(&ours_path)
+        .arg(&base_path)
+        .arg(&theirs_path)
+        .output()
+        .await;
+
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+
+    let output = output.map_err(|e| ConfigError::GitError(e.to_string()))?;
+
+    // `git merge-file` exits with the number of conflicts, negative on errors
+    match output.status.code() {
+        Some(code) if code >= 0 => {
+            if code > 0 {
+                Reporter::global().warn(format!(
+                    "Warning: {} conflict(s) left in {}, resolve the conflict markers manually",
+                    code,
+                    path.display()
+                ));
+            }
+
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        _ => Err(ConfigError::GitError(
+            sanitize_output(&output.stderr).trim().to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn merges_by_the_explicit_format() {
+        let path = Path::new(".settings");
+        let base = "a: 1\nb: 1\n";
+        let ours = "a: 2\nb: 1\n";
+        let theirs = "a: 1\nb: 3\n";
+
+        let merged = merge_three_way(path, ConfigFormat::Yaml, Some(base), ours, theirs, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            ConfigFormat::Yaml.parse(&merged).unwrap(),
+            serde_json::json!({ "a": 2, "b": 3 })
+        );
+    }
+
+    #[tokio::test]
+    async fn settles_conflicts_by_the_resolution() {
+        let path = Path::new("config.toml");
+        let base = "a = 1\n";
+        let ours = "a = 2\n";
+        let theirs = "a = 3\n";
+
+        for (resolution, expected) in [
+            (ConflictResolution::Ours, 2),
+            (ConflictResolution::Theirs, 3),
+        ] {
+            let merged = merge_three_way(
+                path,
+                ConfigFormat::Toml,
+                Some(base),
+                ours,
+                theirs,
+                Some(resolution),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                ConfigFormat::Toml.parse(&merged).unwrap(),
+                serde_json::json!({ "a": expected })
+            );
+        }
+    }
+}
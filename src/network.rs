@@ -0,0 +1,331 @@
+use std::{path::Path, sync::OnceLock};
+
+use reqwest::{Certificate, Client, NoProxy, Proxy, RequestBuilder, Response};
+
+use crate::{
+    config::AmayaConfig,
+    error::ConfigError,
+    provider::ProjectContext,
+    retry::{Failure, RetryPolicy},
+};
+
+static NETWORK: OnceLock<AmarisNetwork> = OnceLock::new();
+
+/// Proxies taken from `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY`, the
+/// lowercase spellings are accepted as well.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProxySettings {
+    pub https: Option<String>,
+    pub http: Option<String>,
+    pub no_proxy: Option<String>,
+}
+
+impl ProxySettings {
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Reads the proxy variables through `lookup`, preferring the uppercase
+    /// name and ignoring empty values.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name: &str| {
+            [name.to_uppercase(), name.to_lowercase()]
+                .iter()
+                .filter_map(|key| lookup(key))
+                .map(|value| value.trim().to_string())
+                .find(|value| !value.is_empty())
+        };
+
+        Self {
+            https: read("https_proxy"),
+            http: read("http_proxy"),
+            no_proxy: read("no_proxy"),
+        }
+    }
+
+    fn proxies(&self) -> Result<Vec<Proxy>, ConfigError> {
+        let no_proxy = self.no_proxy.as_deref().and_then(NoProxy::from_string);
+        let mut proxies = vec![];
+
+        if let Some(url) = &self.https {
+            let proxy = Proxy::https(url.as_str()).map_err(|e| {
+                ConfigError::NetworkError(format!("invalid HTTPS_PROXY '{}': {}", url, e))
+            })?;
+            proxies.push(proxy.no_proxy(no_proxy.clone()));
+        }
+
+        if let Some(url) = &self.http {
+            let proxy = Proxy::http(url.as_str()).map_err(|e| {
+                ConfigError::NetworkError(format!("invalid HTTP_PROXY '{}': {}", url, e))
+            })?;
+            proxies.push(proxy.no_proxy(no_proxy));
+        }
+
+        Ok(proxies)
+    }
+}
+
+/// Builds the HTTP client used for every network operation so proxy, CA and
+/// `--offline` settings apply uniformly.
+pub struct AmarisNetwork {
+    offline: bool,
+}
+
+impl AmarisNetwork {
+    pub fn init(offline: bool) {
+        let _ = NETWORK.set(AmarisNetwork { offline });
+    }
+
+    pub fn global() -> &'static AmarisNetwork {
+        NETWORK.get_or_init(|| AmarisNetwork { offline: false })
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// A client for `operation`, which names what needs the network in the
+    /// error raised under `--offline`.
+    pub async fn client(
+        &self,
+        project: &ProjectContext,
+        operation: &str,
+    ) -> Result<Client, ConfigError> {
+        if self.offline {
+            return Err(ConfigError::NetworkError(format!(
+                "{} needs network access, but --offline is set",
+                operation
+            )));
+        }
+
+        let config = AmayaConfig::load(project).await?;
+        let mut builder = Client::builder().no_proxy();
+
+        for proxy in ProxySettings::from_env().proxies()? {
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &config.network.ca_bundle {
+            builder = builder.tls_certs_merge(Self::load_ca_bundle(path).await?);
+        }
+
+        builder
+            .build()
+            .map_err(|e| ConfigError::NetworkError(format!("could not create HTTP client: {}", e)))
+    }
+
+    /// Sends `request`, retrying connection failures, timeouts and 5xx
+    /// responses. Other error statuses are returned for the caller to handle.
+    pub async fn send(
+        &self,
+        project: &ProjectContext,
+        operation: &str,
+        request: RequestBuilder,
+    ) -> Result<Response, ConfigError> {
+        let (client, request) = request.build_split();
+        let request = request.map_err(|e| {
+            ConfigError::NetworkError(format!("{}: {}", operation, describe_error(&e)))
+        })?;
+        let url = request.url().to_string();
+
+        RetryPolicy::load(project)
+            .await?
+            .run(operation, || async {
+                let attempt = request.try_clone().ok_or_else(|| {
+                    Failure::Permanent(ConfigError::NetworkError(format!(
+                        "{}: request to {} cannot be repeated",
+                        operation, url
+                    )))
+                })?;
+
+                let response = client.execute(attempt).await.map_err(|e| {
+                    let error = ConfigError::NetworkError(format!(
+                        "could not reach {}: {}",
+                        url,
+                        describe_error(&e)
+                    ));
+
+                    match e.is_connect() || e.is_timeout() || e.is_request() {
+                        true => Failure::Transient(error),
+                        false => Failure::Permanent(error),
+                    }
+                })?;
+
+                if response.status().is_server_error() {
+                    return Err(Failure::Transient(ConfigError::NetworkError(format!(
+                        "{} returned {}",
+                        url,
+                        response.status()
+                    ))));
+                }
+
+                Ok(response)
+            })
+            .await
+    }
+
+    /// Reads the PEM certificates in `path`, failing when it holds none.
+    pub async fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>, ConfigError> {
+        let content = tokio::fs::read(path)
+            .await
+            .map_err(|e| ConfigError::FileReadError(format!("{}: {}", path.display(), e)))?;
+
+        let certificates = Certificate::from_pem_bundle(&content)
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))?;
+
+        if certificates.is_empty() {
+            return Err(ConfigError::ValidationError(format!(
+                "{}: no PEM certificates found",
+                path.display()
+            )));
+        }
+
+        Ok(certificates)
+    }
+}
+
+/// Formats a request error with its causes, which reqwest leaves out of
+/// `Display` but are what tells a proxy or certificate problem apart.
+pub fn describe_error(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    fn settings(variables: &[(&str, &str)]) -> ProxySettings {
+        let variables: HashMap<&str, &str> = variables.iter().copied().collect();
+        ProxySettings::from_lookup(|key| variables.get(key).map(|value| value.to_string()))
+    }
+
+    /// A proxy answering one request with `ok`, returning its URL and the
+    /// request line it received.
+    fn one_shot_proxy() -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request_line = String::new();
+            BufReader::new(&stream)
+                .read_line(&mut request_line)
+                .unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .unwrap();
+            request_line.trim().to_string()
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn reads_the_uppercase_variables() {
+        assert_eq!(
+            settings(&[
+                ("HTTPS_PROXY", "http://secure.proxy:8443"),
+                ("HTTP_PROXY", "http://plain.proxy:8080"),
+                ("NO_PROXY", "localhost,.internal"),
+            ]),
+            ProxySettings {
+                https: Some("http://secure.proxy:8443".to_string()),
+                http: Some("http://plain.proxy:8080".to_string()),
+                no_proxy: Some("localhost,.internal".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_lowercase_variables() {
+        let proxy = settings(&[
+            ("https_proxy", "http://lower.proxy:8443"),
+            ("HTTP_PROXY", "http://upper.proxy:8080"),
+            ("http_proxy", "http://lower.proxy:8080"),
+            ("no_proxy", "example.com"),
+        ]);
+
+        assert_eq!(proxy.https.as_deref(), Some("http://lower.proxy:8443"));
+        assert_eq!(proxy.http.as_deref(), Some("http://upper.proxy:8080"));
+        assert_eq!(proxy.no_proxy.as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn ignores_empty_values() {
+        let proxy = settings(&[
+            ("HTTPS_PROXY", "  "),
+            ("https_proxy", " http://lower.proxy:8443 "),
+            ("HTTP_PROXY", ""),
+            ("NO_PROXY", ""),
+        ]);
+
+        assert_eq!(proxy.https.as_deref(), Some("http://lower.proxy:8443"));
+        assert_eq!(proxy.http, None);
+        assert_eq!(proxy.no_proxy, None);
+        assert!(settings(&[]).proxies().unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_an_invalid_proxy_url() {
+        let error = settings(&[("HTTP_PROXY", "http://[::1")])
+            .proxies()
+            .unwrap_err();
+
+        assert!(
+            matches!(&error, ConfigError::NetworkError(message) if message.starts_with("invalid HTTP_PROXY 'http://[::1'")),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_requests_through_the_proxy_unless_no_proxy_matches() {
+        let (url, proxy) = one_shot_proxy();
+        let client = |settings: &ProxySettings| {
+            settings
+                .proxies()
+                .unwrap()
+                .into_iter()
+                .fold(Client::builder().no_proxy(), |builder, proxy| {
+                    builder.proxy(proxy)
+                })
+                .build()
+                .unwrap()
+        };
+
+        let bypassed = settings(&[("HTTP_PROXY", &url), ("NO_PROXY", "registry.invalid")]);
+        assert!(
+            client(&bypassed)
+                .get("http://registry.invalid/amaya")
+                .send()
+                .await
+                .is_err()
+        );
+
+        let proxied = settings(&[("HTTP_PROXY", &url), ("NO_PROXY", "other.invalid")]);
+        let response = client(&proxied)
+            .get("http://registry.invalid/amaya")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+        assert_eq!(
+            proxy.join().unwrap(),
+            "GET http://registry.invalid/amaya HTTP/1.1"
+        );
+    }
+}
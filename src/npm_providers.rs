@@ -0,0 +1,377 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha512};
+
+use crate::{
+    bundle::{AmarisBundle, MAX_BUNDLE_SIZE},
+    error::ConfigError,
+    network::{AmarisNetwork, describe_error},
+    package_manager::PackageRegistry,
+    provider::ProjectContext,
+    updates::AmarisUpdates,
+    utils::{AmarisFileHandler, AmarisPathHandler},
+};
+
+pub const NPM_SOURCE_PREFIX: &str = "npm:";
+const NPM_SOURCES_FILE_NAME: &str = "npm-providers.json";
+const TARBALL_CACHE_DIR_NAME: &str = "npm";
+/// Directory of a package holding the provider, laid out like a bundle.
+const PACKAGE_PROVIDER_DIR: &str = "amaya";
+
+/// A package given as `npm:<package>[@<version>]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NpmProviderSpec {
+    pub package: String,
+    /// A version or dist-tag, the `latest` dist-tag when not given.
+    pub version: Option<String>,
+}
+
+impl NpmProviderSpec {
+    /// `None` when `source` does not start with `npm:`.
+    pub fn parse(source: &str) -> Result<Option<Self>, ConfigError> {
+        let Some(spec) = source.strip_prefix(NPM_SOURCE_PREFIX) else {
+            return Ok(None);
+        };
+
+        // The `@` of a scope is not a version separator
+        let (package, version) = match spec[1.min(spec.len())..].find('@') {
+            Some(at) => (&spec[..at + 1], Some(&spec[at + 2..])),
+            None => (spec, None),
+        };
+
+        let valid_scope = match package.strip_prefix('@') {
+            Some(scoped) => scoped
+                .split_once('/')
+                .is_some_and(|(scope, name)| !scope.is_empty() && !name.is_empty()),
+            None => true,
+        };
+        if package.is_empty() || !valid_scope || version == Some("") {
+            return Err(ConfigError::ValidationError(format!(
+                "'{}' is not a package, expected npm:<package> or npm:<package>@<version>",
+                source
+            )));
+        }
+
+        Ok(Some(Self {
+            package: package.to_string(),
+            version: version.map(String::from),
+        }))
+    }
+}
+
+/// Where a provider installed from npm came from.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NpmProviderSource {
+    pub package: String,
+    pub version: String,
+    /// Added with an explicit version, so `amaya update-providers` leaves it
+    /// alone unless it is named.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+}
+
+/// A provider installed from npm along with the latest published version.
+#[derive(Debug, Clone)]
+pub struct ProviderUpdate {
+    pub name: String,
+    pub source: NpmProviderSource,
+    pub latest: String,
+}
+
+impl ProviderUpdate {
+    pub fn is_available(&self) -> bool {
+        match (
+            Version::parse(&self.source.version),
+            Version::parse(&self.latest),
+        ) {
+            (Ok(installed), Ok(latest)) => latest > installed,
+            _ => self.latest != self.source.version,
+        }
+    }
+}
+
+/// Providers published as npm packages. The package carries an `amaya/`
+/// directory holding `providers/<file>` and `configs/<name>/`, as in a
+/// bundle. Where each provider came from is kept in `~/.amaya/npm-providers.json`.
+pub struct AmarisNpmProviders;
+
+impl AmarisNpmProviders {
+    /// Downloads the package of `spec` and adds its provider, returning the
+    /// provider name and the version installed.
+    pub async fn add(
+        project: &ProjectContext,
+        spec: &NpmProviderSpec,
+    ) -> Result<(String, String), ConfigError> {
+        let (name, version) =
+            Self::install(project, &spec.package, spec.version.as_deref(), false).await?;
+
+        let mut sources = Self::load_sources(project).await?;
+        sources.insert(
+            name.clone(),
+            NpmProviderSource {
+                package: spec.package.clone(),
+                version: version.clone(),
+                pinned: spec.version.is_some(),
+            },
+        );
+        Self::save_sources(project, &sources).await?;
+
+        Ok((name, version))
+    }
+
+    /// The latest version of every provider installed from npm, or of those
+    /// in `names` only.
+    pub async fn check(
+        project: &ProjectContext,
+        names: &[String],
+    ) -> Result<Vec<ProviderUpdate>, ConfigError> {
+        let sources = Self::load_sources(project).await?;
+        if let Some(unknown) = names.iter().find(|name| !sources.contains_key(*name)) {
+            return Err(ConfigError::ValidationError(format!(
+                "'{}' was not added from npm",
+                unknown
+            )));
+        }
+
+        let npm_registry = PackageRegistry::resolve(project, None).await?;
+        let mut updates = vec![];
+        for (name, source) in sources {
+            if !names.is_empty() && !names.contains(&name) {
+                continue;
+            }
+
+            let metadata =
+                AmarisUpdates::fetch_metadata(project, npm_registry.as_ref(), &source.package)
+                    .await?;
+            let latest = Self::resolve_version(&metadata, &source.package, None)?;
+            updates.push(ProviderUpdate {
+                name,
+                source,
+                latest,
+            });
+        }
+
+        Ok(updates)
+    }
+
+    /// Replaces the provider of `update` with its latest version, keeping
+    /// whether it is pinned.
+    pub async fn update(
+        project: &ProjectContext,
+        update: &ProviderUpdate,
+    ) -> Result<(), ConfigError> {
+        let (name, version) =
+            Self::install(project, &update.source.package, Some(&update.latest), true).await?;
+        if name != update.name {
+            return Err(ConfigError::ValidationError(format!(
+                "{}@{} now provides '{}' instead of '{}', remove it and add it again",
+                update.source.package, version, name, update.name
+            )));
+        }
+
+        let mut sources = Self::load_sources(project).await?;
+        sources.insert(
+            name,
+            NpmProviderSource {
+                version,
+                ..update.source.clone()
+            },
+        );
+        Self::save_sources(project, &sources).await
+    }
+
+    /// Forgets where `name` came from, once its provider was removed or
+    /// renamed. Returns the package it was added from.
+    pub async fn forget(
+        project: &ProjectContext,
+        name: &str,
+    ) -> Result<Option<String>, ConfigError> {
+        let mut sources = Self::load_sources(project).await?;
+        let Some(source) = sources.remove(name) else {
+            return Ok(None);
+        };
+
+        Self::save_sources(project, &sources).await?;
+        Ok(Some(source.package))
+    }
+
+    pub async fn load_sources(
+        project: &ProjectContext,
+    ) -> Result<BTreeMap<String, NpmProviderSource>, ConfigError> {
+        let path = Self::sources_path(project)?;
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let content = AmarisFileHandler::load_file(&path).await?;
+        serde_json::from_str(&content)
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))
+    }
+
+    async fn save_sources(
+        project: &ProjectContext,
+        sources: &BTreeMap<String, NpmProviderSource>,
+    ) -> Result<(), ConfigError> {
+        let content = serde_json::to_string_pretty(sources)?;
+        AmarisFileHandler::write_user_file(Self::sources_path(project)?, &content).await
+    }
+
+    fn sources_path(project: &ProjectContext) -> Result<PathBuf, ConfigError> {
+        Ok(AmarisPathHandler::get_app_config_path(project)?.with_file_name(NPM_SOURCES_FILE_NAME))
+    }
+
+    /// Downloads `version` of `package`, verifies it against the integrity
+    /// the registry publishes and installs the provider in its `amaya/`
+    /// directory. Returns the provider name and the version installed.
+    async fn install(
+        project: &ProjectContext,
+        package: &str,
+        version: Option<&str>,
+        replace: bool,
+    ) -> Result<(String, String), ConfigError> {
+        let npm_registry = PackageRegistry::resolve(project, None).await?;
+        let metadata =
+            AmarisUpdates::fetch_metadata(project, npm_registry.as_ref(), package).await?;
+        let version = Self::resolve_version(&metadata, package, version)?;
+        let label = format!("{}@{}", package, version);
+
+        let dist = &metadata["versions"][&version]["dist"];
+        let tarball = dist["tarball"].as_str().ok_or_else(|| {
+            ConfigError::NetworkError(format!("{} has no tarball in the registry", label))
+        })?;
+        let integrity = dist["integrity"].as_str().unwrap_or_default();
+
+        let content = Self::download(project, npm_registry.as_ref(), tarball).await?;
+        Self::verify_integrity(&label, integrity, &content)?;
+
+        let tarball_dir = AmarisPathHandler::ensure_cache_dir(project)
+            .await?
+            .join(TARBALL_CACHE_DIR_NAME);
+        tokio::fs::create_dir_all(&tarball_dir).await?;
+        let tarball_path =
+            tarball_dir.join(format!("{}-{}.tgz", package.replace('/', "+"), version));
+        tokio::fs::write(&tarball_path, &content).await?;
+
+        let invalid =
+            |message: String| ConfigError::ValidationError(format!("{}: {}", label, message));
+        let entries = AmarisBundle::read_entries(&tarball_path).map_err(|e| match e {
+            ConfigError::ValidationError(message) => invalid(message),
+            e => e,
+        })?;
+
+        // npm tarballs hold the package in a single directory, usually `package/`
+        let prefix = format!("{}/", PACKAGE_PROVIDER_DIR);
+        let entries: BTreeMap<String, Vec<u8>> = entries
+            .into_iter()
+            .filter_map(|(file, content)| {
+                let (_, inside) = file.split_once('/')?;
+                let file = inside.strip_prefix(&prefix)?;
+                Some((file.to_string(), content))
+            })
+            .collect();
+        if entries.is_empty() {
+            return Err(invalid(format!("the package has no {} directory", prefix)));
+        }
+
+        let name = AmarisBundle::install(project, &entries, None, replace)
+            .await
+            .map_err(|e| match e {
+                ConfigError::ValidationError(message) => invalid(message),
+                e => e,
+            })?;
+
+        Ok((name, version))
+    }
+
+    /// `requested` when it is a published version, the version a dist-tag
+    /// of that name points to otherwise. `latest` when nothing is requested.
+    fn resolve_version(
+        metadata: &Value,
+        package: &str,
+        requested: Option<&str>,
+    ) -> Result<String, ConfigError> {
+        let requested = requested.unwrap_or("latest");
+        if metadata["versions"].get(requested).is_some() {
+            return Ok(requested.to_string());
+        }
+
+        metadata["dist-tags"][requested]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| {
+                ConfigError::ValidationError(format!(
+                    "{} has no version or dist-tag '{}'",
+                    package, requested
+                ))
+            })
+    }
+
+    /// Checks `content` against the `sha512-<base64>` digest of an npm `integrity` field.
+    fn verify_integrity(label: &str, integrity: &str, content: &[u8]) -> Result<(), ConfigError> {
+        let expected: Vec<&str> = integrity
+            .split_whitespace()
+            .filter_map(|hash| hash.strip_prefix("sha512-"))
+            .collect();
+        if expected.is_empty() {
+            return Err(ConfigError::ValidationError(format!(
+                "{} has no sha512 integrity in the registry",
+                label
+            )));
+        }
+
+        let actual = BASE64_STANDARD.encode(Sha512::digest(content));
+        if !expected.contains(&actual.as_str()) {
+            return Err(ConfigError::ValidationError(format!(
+                "{}: integrity mismatch, expected sha512-{} but found sha512-{}",
+                label, expected[0], actual
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Downloads `url`, sending the auth token only to the configured registry.
+    async fn download(
+        project: &ProjectContext,
+        npm_registry: Option<&PackageRegistry>,
+        url: &str,
+    ) -> Result<Vec<u8>, ConfigError> {
+        let operation = "downloading a provider package";
+        let network = AmarisNetwork::global();
+
+        let mut request = network.client(project, operation).await?.get(url);
+        if let Some(token) = AmarisUpdates::token(npm_registry)
+            && npm_registry.is_some_and(|registry| url.starts_with(&registry.url))
+        {
+            request = request.bearer_auth(token);
+        }
+
+        let response = network.send(project, operation, request).await?;
+        if !response.status().is_success() {
+            return Err(ConfigError::NetworkError(format!(
+                "{} returned {}",
+                url,
+                response.status()
+            )));
+        }
+
+        let content = response
+            .bytes()
+            .await
+            .map_err(|e| ConfigError::NetworkError(format!("{}: {}", url, describe_error(&e))))?;
+        if content.len() as u64 > MAX_BUNDLE_SIZE {
+            return Err(ConfigError::ValidationError(format!(
+                "{} is {} bytes, larger than the {} byte limit",
+                url,
+                content.len(),
+                MAX_BUNDLE_SIZE
+            )));
+        }
+
+        Ok(content.to_vec())
+    }
+}
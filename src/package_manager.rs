@@ -0,0 +1,754 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::IsTerminal,
+    sync::{Mutex, OnceLock},
+};
+
+use inquire::Confirm;
+use serde_json::Value;
+use which::which;
+
+use crate::{
+    config::AmayaConfig,
+    error::ConfigError,
+    provider::{PackageScope, ProjectContext},
+    reporter::Reporter,
+    stream::AmarisStream,
+    utils::AmarisPackageJsonHandler,
+};
+
+static PACKAGE_MANAGER: OnceLock<AmarisPackageManager> = OnceLock::new();
+
+/// Lockfiles and the package manager writing them, the first present wins.
+pub const LOCKFILE_MANAGERS: &[(&str, &str)] = &[
+    ("bun.lock", "bun"),
+    ("bun.lockb", "bun"),
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("package-lock.json", "npm"),
+];
+/// Parts of variable names whose values are hidden in logs and output.
+const SECRET_NAME_MARKERS: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "KEY",
+    "AUTH",
+    "CREDENTIAL",
+];
+
+/// Where the package manager of an install was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManagerSource {
+    /// `--package-manager` on the command line.
+    Flag,
+    /// The `packageManager` field of package.json, as used by corepack.
+    PackageJson,
+    Lockfile,
+    /// The `package_manager` of the provider itself.
+    Provider,
+}
+
+impl PackageManagerSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackageManagerSource::Flag => "--package-manager",
+            PackageManagerSource::PackageJson => "the packageManager field",
+            PackageManagerSource::Lockfile => "the lockfile",
+            PackageManagerSource::Provider => "the provider",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPackageManager {
+    pub name: String,
+    /// The version pinned by the `packageManager` field.
+    pub version: Option<String>,
+    pub source: PackageManagerSource,
+}
+
+/// Picks the package manager provider packages are installed with, the
+/// project's choice winning over the provider's.
+pub struct AmarisPackageManager {
+    flag: Option<String>,
+    /// Pinned managers missing from PATH that run through `corepack` instead.
+    corepack: Mutex<HashSet<String>>,
+}
+
+impl AmarisPackageManager {
+    pub fn init(flag: Option<String>) {
+        let _ = PACKAGE_MANAGER.set(AmarisPackageManager {
+            flag,
+            corepack: Mutex::new(HashSet::new()),
+        });
+    }
+
+    pub fn global() -> &'static AmarisPackageManager {
+        PACKAGE_MANAGER.get_or_init(|| AmarisPackageManager {
+            flag: None,
+            corepack: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Resolves in order: the CLI flag, the `packageManager` field of
+    /// package.json, the lockfile present and finally the provider's own
+    /// `package_manager`. The field's version is kept whenever it names the
+    /// manager picked, so corepack can run that exact version.
+    pub fn resolve_from(
+        flag: Option<&str>,
+        pinned: Option<(&str, Option<&str>)>,
+        lockfile: Option<&str>,
+        provider: &str,
+    ) -> ResolvedPackageManager {
+        let (name, source) = match (flag, pinned, lockfile) {
+            (Some(flag), _, _) => (flag, PackageManagerSource::Flag),
+            (None, Some((name, _)), _) => (name, PackageManagerSource::PackageJson),
+            (None, None, Some(lockfile)) => (lockfile, PackageManagerSource::Lockfile),
+            (None, None, None) => (provider, PackageManagerSource::Provider),
+        };
+
+        let version = pinned
+            .filter(|(pinned, _)| *pinned == name)
+            .and_then(|(_, version)| version)
+            .map(String::from);
+
+        ResolvedPackageManager {
+            name: name.to_string(),
+            version,
+            source,
+        }
+    }
+
+    /// The package manager to install `provider_manager`'s packages with in
+    /// `project`.
+    pub async fn resolve(
+        &self,
+        project: &ProjectContext,
+        provider_manager: &str,
+    ) -> Result<ResolvedPackageManager, ConfigError> {
+        let pinned = match AmarisPackageJsonHandler::get_default_path(project).exists() {
+            true => Self::pinned(&AmarisPackageJsonHandler::read(project).await?),
+            false => None,
+        };
+
+        Ok(Self::resolve_from(
+            self.flag.as_deref(),
+            pinned
+                .as_ref()
+                .map(|(name, version)| (name.as_str(), version.as_deref())),
+            Self::from_lockfile(project),
+            provider_manager,
+        ))
+    }
+
+    /// The manager and version of the `packageManager` field, such as
+    /// `pnpm@9.1.0+sha512.abc`, without the hash.
+    pub fn pinned(package_json: &Value) -> Option<(String, Option<String>)> {
+        let field = package_json.get("packageManager")?.as_str()?.trim();
+        let (name, version) = match field.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (field, None),
+        };
+        if name.is_empty() {
+            return None;
+        }
+
+        let version = version
+            .and_then(|version| version.split('+').next())
+            .filter(|version| !version.is_empty())
+            .map(String::from);
+
+        Some((name.to_string(), version))
+    }
+
+    pub fn from_lockfile(project: &ProjectContext) -> Option<&'static str> {
+        LOCKFILE_MANAGERS
+            .iter()
+            .find(|(lockfile, _)| project.path(lockfile).exists())
+            .map(|(_, manager)| *manager)
+    }
+
+    /// Warns when the project settled on another package manager than the one
+    /// `provider` was written for.
+    pub fn warn_on_mismatch(
+        provider: &str,
+        provider_manager: &str,
+        resolved: &ResolvedPackageManager,
+    ) {
+        let from_project = matches!(
+            resolved.source,
+            PackageManagerSource::PackageJson | PackageManagerSource::Lockfile
+        );
+
+        if from_project && resolved.name != provider_manager {
+            Reporter::global().warn(format!(
+                "Warning: provider '{}' uses {} but the project uses {} according to {}, installing with {}",
+                provider,
+                provider_manager,
+                resolved.name,
+                resolved.source.as_str(),
+                resolved.name
+            ));
+        }
+    }
+
+    /// Whether `resolved` can run, from PATH or through corepack. A manager
+    /// pinned by the `packageManager` field but missing from PATH is offered
+    /// to run through corepack when that is installed.
+    pub fn ensure_available(&self, resolved: &ResolvedPackageManager) -> Result<bool, ConfigError> {
+        if self.is_available(&resolved.name) {
+            return Ok(true);
+        }
+
+        if resolved.source != PackageManagerSource::PackageJson || which("corepack").is_err() {
+            return Ok(false);
+        }
+
+        if !std::io::stdin().is_terminal() && !AmarisStream::is_enabled() {
+            Reporter::global().warn(format!(
+                "Warning: {} is pinned in package.json but not on PATH, run `corepack enable` to install it",
+                resolved.name
+            ));
+            return Ok(false);
+        }
+
+        let pinned = match &resolved.version {
+            Some(version) => format!("{}@{}", resolved.name, version),
+            None => resolved.name.clone(),
+        };
+        let accepted = AmarisStream::confirm(
+            Confirm::new(&format!(
+                "{} is pinned in package.json but not on PATH, run it through corepack?",
+                pinned
+            ))
+            .with_default(true),
+        )
+        .map_err(|e| ConfigError::DependencyError(e.to_string()))?;
+
+        if accepted {
+            self.corepack.lock().unwrap().insert(resolved.name.clone());
+        }
+
+        Ok(accepted)
+    }
+
+    /// The arguments adding a package with `manager` in `scope`, pinned to
+    /// the exact version with `exact`. The package goes last.
+    pub fn add_args(manager: &str, scope: PackageScope, exact: bool) -> Vec<&'static str> {
+        let (mut args, exact_flag) = match (manager, scope) {
+            ("npm", PackageScope::Dev) => (vec!["install", "--save-dev"], "--save-exact"),
+            ("npm", PackageScope::Prod) => (vec!["install"], "--save-exact"),
+            ("npm", PackageScope::Global) => (vec!["install", "--global"], "--save-exact"),
+            ("pnpm", PackageScope::Dev) => (vec!["add", "--save-dev"], "--save-exact"),
+            ("pnpm", PackageScope::Prod) => (vec!["add"], "--save-exact"),
+            ("pnpm", PackageScope::Global) => (vec!["add", "--global"], "--save-exact"),
+            ("yarn", PackageScope::Dev) => (vec!["add", "--dev"], "--exact"),
+            ("yarn", PackageScope::Prod) => (vec!["add"], "--exact"),
+            ("yarn", PackageScope::Global) => (vec!["global", "add"], "--exact"),
+            (_, PackageScope::Dev) => (vec!["install", "--dev"], "--exact"),
+            (_, PackageScope::Prod) => (vec!["add"], "--exact"),
+            (_, PackageScope::Global) => (vec!["add", "--global"], "--exact"),
+        };
+        if exact {
+            args.push(exact_flag);
+        }
+
+        args
+    }
+
+    /// The arguments removing a package installed in `scope` with `manager`.
+    /// The package goes last.
+    pub fn remove_args(manager: &str, scope: PackageScope) -> Vec<&'static str> {
+        match (manager, scope) {
+            ("npm", PackageScope::Global) => vec!["uninstall", "--global"],
+            ("yarn", PackageScope::Global) => vec!["global", "remove"],
+            (_, PackageScope::Global) => vec!["remove", "--global"],
+            _ => vec!["remove"],
+        }
+    }
+
+    /// Whether `manager` runs, from PATH or through corepack.
+    pub fn is_available(&self, manager: &str) -> bool {
+        which(manager).is_ok() || self.uses_corepack(manager)
+    }
+
+    pub fn uses_corepack(&self, manager: &str) -> bool {
+        self.corepack.lock().unwrap().contains(manager)
+    }
+
+    /// The program and leading arguments that run `manager`, `corepack pnpm`
+    /// when it was accepted to run through corepack.
+    pub fn program(&self, manager: &str) -> (String, Vec<String>) {
+        match self.uses_corepack(manager) {
+            true => ("corepack".to_string(), vec![manager.to_string()]),
+            false => (manager.to_string(), vec![]),
+        }
+    }
+}
+
+/// The registry packages are installed from, along with the environment
+/// variable holding its auth token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageRegistry {
+    pub url: String,
+    pub token_env: Option<String>,
+}
+
+/// What a package manager invocation needs to install from a registry.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RegistryInvocation {
+    /// Arguments added after the package.
+    pub args: Vec<String>,
+    /// Environment variables set on the package manager.
+    pub env: Vec<(String, String)>,
+    /// Contents of a user config the package manager reads through
+    /// `npm_config_userconfig`. It refers to the token by its variable, so the
+    /// token itself is never written.
+    pub npmrc: Option<String>,
+}
+
+impl PackageRegistry {
+    /// The registry of a provider whose `registry` is `provider_registry`,
+    /// falling back to `npm.registry` of the global config. The token of
+    /// `npm.token_env` is only sent to the registry of the global config, a
+    /// provider pointing elsewhere never receives it.
+    pub async fn resolve(
+        project: &ProjectContext,
+        provider_registry: Option<&str>,
+    ) -> Result<Option<Self>, ConfigError> {
+        let npm = AmayaConfig::load(project).await?.npm;
+
+        Ok(Self::resolve_from(
+            provider_registry,
+            npm.registry.as_deref(),
+            npm.token_env.as_deref(),
+        ))
+    }
+
+    pub fn resolve_from(
+        provider_registry: Option<&str>,
+        global_registry: Option<&str>,
+        token_env: Option<&str>,
+    ) -> Option<Self> {
+        let url = provider_registry.or(global_registry)?.trim_end_matches('/');
+        let token_env = token_env
+            .filter(|_| global_registry.is_some_and(|global| global.trim_end_matches('/') == url));
+
+        Some(Self {
+            url: url.to_string(),
+            token_env: token_env.map(String::from),
+        })
+    }
+
+    /// How `manager` is pointed at the registry. bun takes the token from
+    /// `NPM_CONFIG_TOKEN`, the others from a generated user config scoping
+    /// it to the registry host.
+    pub fn invocation(&self, manager: &str) -> RegistryInvocation {
+        let mut invocation = RegistryInvocation {
+            args: vec!["--registry".to_string(), format!("{}/", self.url)],
+            ..Default::default()
+        };
+
+        let Some(token_env) = &self.token_env else {
+            return invocation;
+        };
+
+        match manager {
+            "bun" => {
+                if let Ok(token) = std::env::var(token_env) {
+                    invocation.env.push(("NPM_CONFIG_TOKEN".to_string(), token));
+                }
+            }
+            _ => {
+                invocation.npmrc = Some(format!(
+                    "registry={}/\n{}:_authToken=${{{}}}\n",
+                    self.url,
+                    self.auth_scope(),
+                    token_env
+                ));
+            }
+        }
+
+        invocation
+    }
+
+    /// The `//host/path/` prefix npm scopes registry credentials with.
+    fn auth_scope(&self) -> String {
+        let without_scheme = self
+            .url
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .unwrap_or(&self.url);
+
+        format!("//{}/", without_scheme)
+    }
+
+    /// Whether `stderr` of a failed install says the registry refused the credentials.
+    pub fn is_auth_failure(stderr: &str) -> bool {
+        [
+            "E401",
+            "E403",
+            "401 Unauthorized",
+            "403 Forbidden",
+            " - 401",
+            " - 403",
+        ]
+        .iter()
+        .any(|marker| stderr.contains(marker))
+    }
+}
+
+/// What the package manager invocations of an install run with besides
+/// their arguments.
+#[derive(Debug, Default, Clone)]
+pub struct InstallContext {
+    pub registry: Option<PackageRegistry>,
+    /// Variables set on every invocation, e.g. `BUN_VERSION`.
+    pub environment: HashMap<String, String>,
+}
+
+impl InstallContext {
+    /// The context of a provider with `provider_registry` and
+    /// `provider_environment`, the variables of `extra_env` given on the
+    /// command line taking precedence.
+    pub async fn resolve(
+        project: &ProjectContext,
+        provider_registry: Option<&str>,
+        provider_environment: &HashMap<String, String>,
+        extra_env: &HashMap<String, String>,
+    ) -> Result<Self, ConfigError> {
+        let mut environment = HashMap::new();
+        for (name, value) in provider_environment.iter().chain(extra_env) {
+            environment.insert(name.clone(), interpolate_env(name, value)?);
+        }
+
+        Ok(Self {
+            registry: PackageRegistry::resolve(project, provider_registry).await?,
+            environment,
+        })
+    }
+}
+
+/// Replaces the `${VAR}` references in the value of the variable `name` with
+/// the variables of amaya's own environment, failing on one that is not set.
+pub fn interpolate_env(name: &str, value: &str) -> Result<String, ConfigError> {
+    let mut interpolated = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        interpolated.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err(ConfigError::ValidationError(format!(
+                "environment variable {}: unterminated reference in '{}'",
+                name, value
+            )));
+        };
+
+        let reference = &rest[start + 2..start + end];
+        let resolved = std::env::var(reference).map_err(|_| {
+            ConfigError::ValidationError(format!(
+                "environment variable {}: ${{{}}} is not set",
+                name, reference
+            ))
+        })?;
+        interpolated.push_str(&resolved);
+        rest = &rest[start + end + 1..];
+    }
+
+    interpolated.push_str(rest);
+    Ok(interpolated)
+}
+
+/// `environment` sorted by name for display, with the values of variables
+/// whose name looks like a secret hidden.
+pub fn redact_env(environment: &HashMap<String, String>) -> BTreeMap<String, String> {
+    environment
+        .iter()
+        .map(|(name, value)| {
+            let upper = name.to_uppercase();
+            let value = match SECRET_NAME_MARKERS
+                .iter()
+                .any(|marker| upper.contains(marker))
+            {
+                true => "***".to_string(),
+                false => value.clone(),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN_ENV: &str = "AMAYA_TEST_REGISTRY_TOKEN";
+    const GLOBAL: &str = "https://npm.example.com/";
+
+    #[test]
+    fn flag_wins_over_package_json_over_lockfile_over_provider() {
+        use PackageManagerSource::*;
+
+        type Pinned = Option<(&'static str, Option<&'static str>)>;
+        type Expected = (&'static str, Option<&'static str>, PackageManagerSource);
+
+        let cases: &[(Option<&str>, Pinned, Option<&str>, Expected)] = &[
+            (
+                Some("npm"),
+                Some(("pnpm", Some("9.1.0"))),
+                Some("yarn"),
+                ("npm", None, Flag),
+            ),
+            (
+                Some("pnpm"),
+                Some(("pnpm", Some("9.1.0"))),
+                None,
+                ("pnpm", Some("9.1.0"), Flag),
+            ),
+            (
+                None,
+                Some(("pnpm", Some("9.1.0"))),
+                Some("yarn"),
+                ("pnpm", Some("9.1.0"), PackageJson),
+            ),
+            (
+                None,
+                Some(("yarn", None)),
+                None,
+                ("yarn", None, PackageJson),
+            ),
+            (None, None, Some("yarn"), ("yarn", None, Lockfile)),
+            (None, None, None, ("bun", None, Provider)),
+        ];
+
+        for (flag, pinned, lockfile, (name, version, source)) in cases {
+            assert_eq!(
+                AmarisPackageManager::resolve_from(*flag, *pinned, *lockfile, "bun"),
+                ResolvedPackageManager {
+                    name: name.to_string(),
+                    version: version.map(String::from),
+                    source: *source,
+                },
+                "flag {:?}, packageManager {:?}, lockfile {:?}",
+                flag,
+                pinned,
+                lockfile
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_package_manager_field() {
+        let pinned = |field: Value| {
+            AmarisPackageManager::pinned(&serde_json::json!({ "packageManager": field }))
+        };
+
+        assert_eq!(
+            pinned("pnpm@9.1.0+sha512.abc".into()),
+            Some(("pnpm".to_string(), Some("9.1.0".to_string())))
+        );
+        assert_eq!(pinned("yarn".into()), Some(("yarn".to_string(), None)));
+        assert_eq!(pinned("@9.1.0".into()), None);
+        assert_eq!(pinned(Value::Null), None);
+    }
+
+    #[tokio::test]
+    async fn resolves_from_the_files_of_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = ProjectContext {
+            root: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let manager = |flag: Option<&str>| AmarisPackageManager {
+            flag: flag.map(String::from),
+            corepack: Mutex::new(HashSet::new()),
+        };
+        let resolve = async |flag: Option<&str>| {
+            let resolved = manager(flag).resolve(&project, "bun").await.unwrap();
+            (resolved.name, resolved.source)
+        };
+
+        assert_eq!(
+            resolve(None).await,
+            ("bun".to_string(), PackageManagerSource::Provider)
+        );
+
+        std::fs::write(project.path("yarn.lock"), "").unwrap();
+        assert_eq!(
+            resolve(None).await,
+            ("yarn".to_string(), PackageManagerSource::Lockfile)
+        );
+
+        std::fs::write(
+            project.path("package.json"),
+            r#"{ "packageManager": "pnpm@9.1.0" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            resolve(None).await,
+            ("pnpm".to_string(), PackageManagerSource::PackageJson)
+        );
+        assert_eq!(
+            resolve(Some("npm")).await,
+            ("npm".to_string(), PackageManagerSource::Flag)
+        );
+    }
+
+    #[test]
+    fn token_goes_to_the_global_registry() {
+        let registry = PackageRegistry::resolve_from(None, Some(GLOBAL), Some(TOKEN_ENV)).unwrap();
+
+        assert_eq!(registry.url, "https://npm.example.com");
+        assert_eq!(registry.token_env.as_deref(), Some(TOKEN_ENV));
+    }
+
+    #[test]
+    fn token_goes_to_a_provider_registry_matching_the_global_one() {
+        let registry = PackageRegistry::resolve_from(
+            Some("https://npm.example.com"),
+            Some(GLOBAL),
+            Some(TOKEN_ENV),
+        )
+        .unwrap();
+
+        assert_eq!(registry.token_env.as_deref(), Some(TOKEN_ENV));
+    }
+
+    #[test]
+    fn token_is_not_sent_to_another_provider_registry() {
+        let registry = PackageRegistry::resolve_from(
+            Some("https://evil.example.org"),
+            Some(GLOBAL),
+            Some(TOKEN_ENV),
+        )
+        .unwrap();
+
+        assert_eq!(registry.url, "https://evil.example.org");
+        assert_eq!(registry.token_env, None);
+    }
+
+    #[test]
+    fn token_is_not_sent_without_a_global_registry() {
+        let registry =
+            PackageRegistry::resolve_from(Some("https://evil.example.org"), None, Some(TOKEN_ENV))
+                .unwrap();
+
+        assert_eq!(registry.token_env, None);
+        assert!(PackageRegistry::resolve_from(None, None, Some(TOKEN_ENV)).is_none());
+    }
+
+    #[test]
+    fn bun_receives_the_token_in_its_environment() {
+        // SAFETY: only this test reads or writes the variable
+        unsafe { std::env::set_var(TOKEN_ENV, "npm_secret") };
+        let registry = PackageRegistry::resolve_from(None, Some(GLOBAL), Some(TOKEN_ENV)).unwrap();
+        let invocation = registry.invocation("bun");
+
+        assert_eq!(
+            invocation.args,
+            vec!["--registry".to_string(), GLOBAL.to_string()]
+        );
+        assert_eq!(
+            invocation.env,
+            vec![("NPM_CONFIG_TOKEN".to_string(), "npm_secret".to_string())]
+        );
+        assert_eq!(invocation.npmrc, None);
+    }
+
+    #[test]
+    fn other_managers_reference_the_token_in_a_user_config() {
+        let registry = PackageRegistry::resolve_from(None, Some(GLOBAL), Some(TOKEN_ENV)).unwrap();
+
+        for manager in ["npm", "pnpm", "yarn"] {
+            let invocation = registry.invocation(manager);
+
+            assert_eq!(
+                invocation.args,
+                vec!["--registry".to_string(), GLOBAL.to_string()]
+            );
+            assert!(invocation.env.is_empty(), "{}", manager);
+            assert_eq!(
+                invocation.npmrc.as_deref(),
+                Some(
+                    "registry=https://npm.example.com/\n//npm.example.com/:_authToken=${AMAYA_TEST_REGISTRY_TOKEN}\n"
+                ),
+                "{}",
+                manager
+            );
+        }
+    }
+
+    #[test]
+    fn registry_without_token_only_adds_the_flag() {
+        let registry = PackageRegistry::resolve_from(
+            Some("https://evil.example.org/"),
+            Some(GLOBAL),
+            Some(TOKEN_ENV),
+        )
+        .unwrap();
+
+        for manager in ["bun", "npm", "pnpm", "yarn"] {
+            assert_eq!(
+                registry.invocation(manager),
+                RegistryInvocation {
+                    args: vec![
+                        "--registry".to_string(),
+                        "https://evil.example.org/".to_string()
+                    ],
+                    ..Default::default()
+                },
+                "{}",
+                manager
+            );
+        }
+    }
+
+    #[test]
+    fn add_and_remove_arguments_per_manager() {
+        let cases = [
+            (
+                "bun",
+                PackageScope::Dev,
+                vec!["install", "--dev", "--exact"],
+            ),
+            (
+                "npm",
+                PackageScope::Dev,
+                vec!["install", "--save-dev", "--save-exact"],
+            ),
+            ("pnpm", PackageScope::Prod, vec!["add", "--save-exact"]),
+            (
+                "yarn",
+                PackageScope::Global,
+                vec!["global", "add", "--exact"],
+            ),
+        ];
+        for (manager, scope, expected) in cases {
+            assert_eq!(
+                AmarisPackageManager::add_args(manager, scope, true),
+                expected,
+                "{}",
+                manager
+            );
+        }
+
+        assert_eq!(
+            AmarisPackageManager::add_args("npm", PackageScope::Dev, false),
+            vec!["install", "--save-dev"]
+        );
+        assert_eq!(
+            AmarisPackageManager::remove_args("npm", PackageScope::Global),
+            vec!["uninstall", "--global"]
+        );
+        assert_eq!(
+            AmarisPackageManager::remove_args("yarn", PackageScope::Global),
+            vec!["global", "remove"]
+        );
+        assert_eq!(
+            AmarisPackageManager::remove_args("pnpm", PackageScope::Dev),
+            vec!["remove"]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+
+use crate::{
+    error::ConfigError,
+    reporter::Reporter,
+    utils::{AmarisGitHandler, AmarisPlatformHandler},
+};
+
+/// Prefix of the directories preview projects are created in.
+const PREVIEW_DIR_PREFIX: &str = "amaya-preview-";
+/// Directories of a fixture project that are not copied.
+const SKIPPED_FIXTURE_DIRS: [&str; 2] = [".git", "node_modules"];
+
+/// Throwaway projects in the temporary directory to try a provider in.
+pub struct AmarisPreview;
+
+impl AmarisPreview {
+    /// Creates an empty project for `name` with a package.json and a git
+    /// repository, returning its directory.
+    pub async fn create(name: &str) -> Result<PathBuf, ConfigError> {
+        let dir = std::env::temp_dir().join(format!(
+            "{}{}-{}",
+            PREVIEW_DIR_PREFIX,
+            name,
+            Utc::now().timestamp_millis()
+        ));
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| ConfigError::FileWriteError(format!("{}: {}", dir.display(), e)))?;
+
+        let package_json = serde_json::json!({ "name": "preview", "type": "module" });
+        tokio::fs::write(
+            dir.join("package.json"),
+            serde_json::to_string_pretty(&package_json)?,
+        )
+        .await
+        .map_err(|e| ConfigError::FileWriteError(format!("{}: {}", dir.display(), e)))?;
+
+        AmarisGitHandler::init(&dir).await?;
+
+        Ok(dir)
+    }
+
+    /// Copies the project at `fixture` into `dir`, leaving out its git
+    /// repository and installed packages.
+    pub fn copy_fixture(fixture: &Path, dir: &Path) -> Result<(), ConfigError> {
+        if !fixture.is_dir() {
+            return Err(ConfigError::PathError(format!(
+                "{} is not a directory",
+                fixture.display()
+            )));
+        }
+
+        for entry in std::fs::read_dir(fixture)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let target = dir.join(&name);
+
+            if entry.file_type()?.is_dir() {
+                if SKIPPED_FIXTURE_DIRS.iter().any(|skipped| name == *skipped) {
+                    continue;
+                }
+                std::fs::create_dir_all(&target)?;
+                Self::copy_fixture(&entry.path(), &target)?;
+            } else {
+                std::fs::copy(entry.path(), &target)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Opens `$SHELL` in `dir` when running in a terminal, otherwise prints
+    /// where the preview project is.
+    pub async fn open_shell(dir: &Path) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+        let shell = std::env::var("SHELL")
+            .ok()
+            .filter(|shell| !shell.trim().is_empty());
+
+        match shell {
+            Some(shell) if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() => {
+                reporter.info(format!(
+                    "Opening {} in {}, exit it to return",
+                    shell,
+                    dir.display()
+                ));
+                AmarisPlatformHandler::command(&shell)
+                    .current_dir(dir)
+                    .status()
+                    .await
+                    .map_err(|e| ConfigError::PathError(format!("{}: {}", shell, e)))?;
+            }
+            _ => reporter.info(format!("Preview project: {}", dir.display())),
+        }
+
+        reporter.info("Remove preview projects with `amaya install --preview --clean`");
+        Ok(())
+    }
+
+    /// Deletes every preview project, returning how many there were.
+    pub async fn clean() -> Result<usize, ConfigError> {
+        let mut removed = 0;
+        let mut entries = tokio::fs::read_dir(std::env::temp_dir()).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let is_preview = entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(PREVIEW_DIR_PREFIX);
+
+            if is_preview && entry.file_type().await?.is_dir() {
+                tokio::fs::remove_dir_all(entry.path()).await?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+}
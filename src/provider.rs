@@ -1,162 +1,2800 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    fs::{File, create_dir_all},
+    io::AsyncReadExt,
+};
+
+use crate::{
+    config::{DEFAULT_TIMEOUT_SECS, ProjectOverrides},
+    error::ConfigError,
+    merge::ConflictResolution,
+    package_manager::{
+        AmarisPackageManager, InstallContext, PackageManagerSource, ResolvedPackageManager,
+    },
+    reporter::Reporter,
+    run::RunContext,
+    state::{AmarisBaseStore, AmarisProjectState},
+    summary::{AmarisEvents, InstallEvent},
+    templates::PACKAGE_MANAGERS,
+    utils::{
+        AmarisConfigurationHandler, AmarisConflictHandler, AmarisEditorHandler, AmarisFileHandler,
+        AmarisInstaller, AmarisNodeVersionHandler, AmarisPackageJsonHandler, AmarisPathHandler,
+        AmarisPlatformHandler, BUN_ENGINE, ConfigChange, ConfigDiff, Editor, GITIGNORE_FILE_NAME,
+        Indent, NODE_ENGINE, NVMRC_FILE_NAME, PACKAGE_JSON_FILE_NAME, ScriptChange, VsCodeFile,
+        format_json, merge_json_values, strip_json_comments,
+    },
+};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStrategy {
+    /// Replace the target file with the rendered source.
+    #[default]
+    Overwrite,
+    /// Deep-merge the rendered source into an existing JSON, YAML or TOML target.
+    Merge,
+}
+
+impl MergeStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MergeStrategy::Overwrite => "overwrite",
+            MergeStrategy::Merge => "merge",
+        }
+    }
+}
+
+/// How a configuration file is serialized. Sources are converted when the
+/// format of their target differs, and structured targets are merged and
+/// compared by value.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+    /// Text copied as it is, never parsed.
+    Raw,
+}
+
+impl ConfigFormat {
+    /// The format of a file going by its extension, `Raw` for unstructured ones.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("json" | "jsonc") => ConfigFormat::Json,
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Raw,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Raw => "raw",
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Result<Value, String> {
+        match self {
+            ConfigFormat::Json => {
+                serde_json::from_str(&strip_json_comments(content)).map_err(|e| e.to_string())
+            }
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Raw => Err("raw files cannot be parsed".to_string()),
+        }
+    }
+
+    /// Serializes `value`, JSON with `indent`.
+    pub fn serialize(&self, value: &Value, indent: Indent) -> Result<String, String> {
+        match self {
+            ConfigFormat::Json => Ok(format_json(value, indent)),
+            ConfigFormat::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::to_string_pretty(value).map_err(|e| e.to_string()),
+            ConfigFormat::Raw => Err("raw files cannot be serialized".to_string()),
+        }
+    }
+
+    /// Whether `content` has comments, which parsing and serializing it again drops.
+    pub fn has_comments(&self, content: &str) -> bool {
+        match self {
+            ConfigFormat::Json => strip_json_comments(content) != content,
+            ConfigFormat::Yaml | ConfigFormat::Toml => content
+                .lines()
+                .any(|line| line.trim_start().starts_with('#')),
+            ConfigFormat::Raw => false,
+        }
+    }
+}
+
+/// How Node.js loads the `.js` files of a project, which decides the syntax
+/// and extension of generated JavaScript configs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleSystem {
+    /// ES modules, `import` and `export default`.
+    Esm,
+    /// CommonJS, `require` and `module.exports`.
+    Cjs,
+}
+
+impl ModuleSystem {
+    /// The module system of the project: the `type` field of package.json,
+    /// or without one, CommonJS unless the project root already has `.cjs`
+    /// files and no `.mjs` ones, which only an ESM project needs.
+    pub fn detect(project: &ProjectContext) -> Self {
+        let package_json =
+            std::fs::read_to_string(AmarisPackageJsonHandler::get_default_path(project))
+                .ok()
+                .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+        match package_json
+            .as_ref()
+            .and_then(|package_json| package_json.get("type"))
+            .and_then(Value::as_str)
+        {
+            Some("module") => return ModuleSystem::Esm,
+            Some("commonjs") => return ModuleSystem::Cjs,
+            _ => {}
+        }
+
+        let extensions: Vec<String> = std::fs::read_dir(&project.root)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| Some(entry.path().extension()?.to_string_lossy().to_string()))
+            .collect();
+        let has = |extension: &str| extensions.iter().any(|e| e == extension);
+
+        match has("cjs") && !has("mjs") {
+            true => ModuleSystem::Esm,
+            false => ModuleSystem::Cjs,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModuleSystem::Esm => "esm",
+            ModuleSystem::Cjs => "cjs",
+        }
+    }
+
+    /// The extension of a file written with the syntax of `syntax` in a
+    /// project using `self`: `js` when they agree, `mjs` or `cjs` otherwise.
+    pub fn extension(&self, syntax: ModuleSystem) -> &'static str {
+        match (self, syntax) {
+            (ModuleSystem::Esm, ModuleSystem::Esm) | (ModuleSystem::Cjs, ModuleSystem::Cjs) => "js",
+            (_, ModuleSystem::Esm) => "mjs",
+            (_, ModuleSystem::Cjs) => "cjs",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ConfigEntry {
+    pub file_location: String,
+    pub file_name: String,
+    #[serde(default)]
+    pub source_from: String,
+    /// Base64 encoded source embedded in an export, used instead of `source_from`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline_content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge: Option<MergeStrategy>,
+    /// Format of the target, the source is converted to it when it differs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ConfigFormat>,
+    /// Expected `sha256:<hex>` digest of the source, checked before it is written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+    /// Platforms the entry applies to, `None` means all of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Vec<String>>,
+    /// Module system of the projects the entry applies to, `None` means
+    /// both, so an ESM config can go to `.js` or `.mjs` depending on it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module: Option<ModuleSystem>,
+    /// Provider whose config directory holds the source, set for entries inherited through `extends`.
+    #[serde(skip)]
+    pub source_provider: Option<String>,
+    /// Directory holding the source instead of a config directory, set for
+    /// providers installed from a file with `amaya install --from-file`.
+    #[serde(skip)]
+    pub source_dir: Option<PathBuf>,
+}
+
+impl ConfigEntry {
+    /// Whether the entry is written in `project`, for its platform and
+    /// module system.
+    pub fn applies(&self, project: &ProjectContext) -> bool {
+        AmarisPlatformHandler::matches(&self.platform)
+            && self
+                .module
+                .is_none_or(|module| module == ModuleSystem::detect(project))
+    }
+
+    /// The format the target is written in, `format` or the one of its extension.
+    pub fn target_format(&self) -> ConfigFormat {
+        self.format
+            .unwrap_or_else(|| ConfigFormat::from_path(&self.file_location))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScriptEntry {
+    pub name: String,
+    pub script: String,
+    /// Only add the script when this holds, see [`evaluate_condition`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    /// Remove the script along with the provider. Scripts the project
+    /// already had, like `build`, can be left in place with `false`.
+    #[serde(default = "true_default", skip_serializing_if = "is_true")]
+    pub remove_on_uninstall: bool,
+    /// Content the script is restored to on removal instead of deleting it.
+    /// When not given, the script package.json had before the install is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_content: Option<String>,
+}
+
+fn true_default() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+/// Where a package is installed, a dev dependency unless given.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageScope {
+    #[default]
+    Dev,
+    Prod,
+    /// Installed for the user instead of the project, for CLI tools.
+    Global,
+}
+
+impl PackageScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackageScope::Dev => "dev",
+            PackageScope::Prod => "prod",
+            PackageScope::Global => "global",
+        }
+    }
+}
+
+/// A package of a provider, written as its name for a dev dependency or as
+/// `{ "name": ..., "scope": "prod" }`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "PackageEntryRepr", into = "PackageEntryRepr")]
+pub struct PackageEntry {
+    pub name: String,
+    pub scope: PackageScope,
+}
+
+impl PackageEntry {
+    pub fn is_global(&self) -> bool {
+        self.scope == PackageScope::Global
+    }
+}
+
+impl From<&str> for PackageEntry {
+    fn from(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            scope: PackageScope::Dev,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+enum PackageEntryRepr {
+    Name(String),
+    Scoped {
+        name: String,
+        #[serde(default)]
+        scope: PackageScope,
+    },
+}
+
+impl From<PackageEntryRepr> for PackageEntry {
+    fn from(repr: PackageEntryRepr) -> Self {
+        match repr {
+            PackageEntryRepr::Name(name) => Self {
+                name,
+                scope: PackageScope::Dev,
+            },
+            PackageEntryRepr::Scoped { name, scope } => Self { name, scope },
+        }
+    }
+}
+
+impl From<PackageEntry> for PackageEntryRepr {
+    fn from(entry: PackageEntry) -> Self {
+        match entry.scope {
+            PackageScope::Dev => PackageEntryRepr::Name(entry.name),
+            scope => PackageEntryRepr::Scoped {
+                name: entry.name,
+                scope,
+            },
+        }
+    }
+}
+
+pub const SCRIPT_CONDITION_HAS_DEP: &str = "has_dep:";
+pub const SCRIPT_CONDITION_FILE_EXISTS: &str = "file_exists:";
+pub const SCRIPT_CONDITION_ENV: &str = "env:";
+
+fn split_condition(condition: &str) -> Option<(&'static str, &str)> {
+    [
+        SCRIPT_CONDITION_HAS_DEP,
+        SCRIPT_CONDITION_FILE_EXISTS,
+        SCRIPT_CONDITION_ENV,
+    ]
+    .into_iter()
+    .find_map(|kind| {
+        condition
+            .strip_prefix(kind)
+            .filter(|argument| !argument.trim().is_empty())
+            .map(|argument| (kind, argument.trim()))
+    })
+}
+
+/// Evaluates a script condition: `has_dep:<package>` holds when package.json
+/// lists the package, `file_exists:<path>` when the path exists in the project
+/// and `env:<VAR>` when the variable is set. Anything else is false.
+pub async fn evaluate_condition(project: &ProjectContext, condition: &str) -> bool {
+    match split_condition(condition) {
+        Some((SCRIPT_CONDITION_HAS_DEP, package)) => {
+            AmarisPackageJsonHandler::check_if_any_dependency_exists(project, &[package])
+                .await
+                .unwrap_or(false)
+        }
+        Some((SCRIPT_CONDITION_FILE_EXISTS, path)) => project.path(path).exists(),
+        Some((SCRIPT_CONDITION_ENV, variable)) => std::env::var_os(variable).is_some(),
+        _ => false,
+    }
+}
+
+/// A flavour of a provider, applied on top of the shared definition like a
+/// provider that `extends` it.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ProviderVariant {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<PackageEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub configuration: Vec<ConfigEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<ScriptEntry>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+    /// Dependencies whose presence in package.json selects this variant. The
+    /// first matching variant wins, so list more specific ones first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detect: Vec<String>,
+}
+
+/// A problem with a provider definition that parsing alone does not catch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// The field at fault, such as `package_manager`.
+    pub field: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct DynamicProvider {
+    pub name: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Semantic version of the provider definition, e.g. `1.2.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// SPDX license identifier, e.g. `MIT`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    pub package_manager: String,
+    pub packages: Vec<PackageEntry>,
+    pub configuration: Vec<ConfigEntry>,
+    pub scripts: Vec<ScriptEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicting_files: Vec<String>,
+    /// Patterns added to `.gitignore` in a section named after the provider,
+    /// such as `coverage/`, removed along with it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gitignore: Vec<String>,
+    /// Default values for `{{variable}}` placeholders in configuration sources.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+    /// Environment variables set on every package manager invocation, e.g. `BUN_VERSION`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub environment: HashMap<String, String>,
+    /// Registry the packages are installed from instead of `npm.registry` of the global config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// Packages installed with `--exact`, keyed by package. `*` pins the latest version.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub exact_versions: HashMap<String, String>,
+    /// Platforms the provider supports, `None` means all of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Vec<String>>,
+    /// Providers that must be installed before this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Workspace settings merged into the configuration of each editor, keyed by editor.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub editor_settings: BTreeMap<Editor, Value>,
+    /// VS Code settings, as dot paths, that only this provider configures,
+    /// e.g. `editor.codeActionsOnSave.source.fixAll.eslint`. Removing the
+    /// provider removes them even when they were changed since.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owned_settings: Vec<String>,
+    /// Whether the provider only works inside a git repository, e.g. for git hooks.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub requires_git: bool,
+    /// Minimum Node.js version, pinned in `.nvmrc` when the installed one is older.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_version: Option<String>,
+    /// Minimum Node.js version the tooling runs on, installing fails on an older one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_node_version: Option<String>,
+    /// Providers this one builds upon, merged in order before its own definition.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+    /// Former names of the provider, still resolved while projects move over.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Whether the provider should no longer be installed, installing it asks first.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Provider to install instead of a deprecated one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+    /// Why the provider is deprecated, shown with the deprecation warning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation_message: Option<String>,
+    /// Flavours selectable with `--variant` or detected from the project.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<ProviderVariant>,
+    /// Variant merged into this provider by [`DynamicProvider::with_variant`].
+    #[serde(skip)]
+    pub variant: Option<String>,
+    /// Ancestors merged into this provider by [`DynamicProvider::resolve_extends`].
+    #[serde(skip)]
+    pub lineage: Vec<String>,
+    /// File the provider was loaded from.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+static PROVIDER_ALIASES: OnceLock<HashMap<String, String>> = OnceLock::new();
+static PROVIDER_SCHEMA: OnceLock<Result<jsonschema::Validator, String>> = OnceLock::new();
+
+/// JSON schema of provider files, the one published for editors.
+const PROVIDER_SCHEMA_SOURCE: &str = include_str!("../schema.json");
+
+/// Template variable holding the TypeScript version of the project, when known.
+pub const TYPESCRIPT_VERSION_VARIABLE: &str = "typescript_version";
+/// Template variable holding the module system of the project, `esm` or `cjs`.
+pub const MODULE_SYNTAX_VARIABLE: &str = "module_syntax";
+
+pub const CONDITION_PACKAGE_JSON: &str = "package.json";
+pub const CONDITION_PACKAGE_MANAGER: &str = "package_manager";
+pub const CONDITION_GIT: &str = "git";
+
+/// How to fix a failed prerequisite check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrerequisiteSuggestion {
+    /// The check the suggestion applies to, e.g. [`CONDITION_PACKAGE_MANAGER`].
+    pub condition: String,
+    pub message: String,
+    pub fix_command: Option<String>,
+}
+
+impl PrerequisiteSuggestion {
+    /// Suggestions for the checks every provider installing with `package_manager` runs.
+    pub fn for_package_manager(package_manager: &str) -> Vec<Self> {
+        let (message, fix_command) = match package_manager {
+            "bun" => (
+                "Install Bun".to_string(),
+                Some("curl -fsSL https://bun.sh/install | bash"),
+            ),
+            "pnpm" => ("Install pnpm".to_string(), Some("npm install -g pnpm")),
+            "yarn" => ("Install Yarn".to_string(), Some("npm install -g yarn")),
+            "npm" => (
+                "Install Node.js from https://nodejs.org, it ships with npm".to_string(),
+                None,
+            ),
+            other => (
+                format!("Install {} and make sure it is on your PATH", other),
+                None,
+            ),
+        };
+
+        let init = match package_manager {
+            "npm" | "yarn" => format!("{} init -y", package_manager),
+            _ => format!("{} init", package_manager),
+        };
+
+        vec![
+            Self {
+                condition: CONDITION_PACKAGE_JSON.to_string(),
+                message: "Run amaya from the root of a JavaScript project or create one"
+                    .to_string(),
+                fix_command: Some(init),
+            },
+            Self {
+                condition: CONDITION_PACKAGE_MANAGER.to_string(),
+                message,
+                fix_command: fix_command.map(String::from),
+            },
+        ]
+    }
+
+    /// Suggestions for the package manager check once the project's package
+    /// manager is known, pointing at corepack when package.json pins it.
+    pub fn for_resolved_manager(resolved: &ResolvedPackageManager) -> Vec<Self> {
+        match resolved.source {
+            PackageManagerSource::PackageJson => vec![Self {
+                condition: CONDITION_PACKAGE_MANAGER.to_string(),
+                message: format!(
+                    "Enable corepack to get the {} version pinned in package.json",
+                    resolved.name
+                ),
+                fix_command: Some("corepack enable".to_string()),
+            }],
+            _ => Self::for_package_manager(&resolved.name),
+        }
+    }
+
+    /// Builds the error for a failed check, followed by the matching suggestion if any.
+    pub fn apply(suggestions: &[Self], condition: &str, message: String) -> ConfigError {
+        match suggestions.iter().find(|s| s.condition == condition) {
+            Some(suggestion) => {
+                ConfigError::MissingPrerequisite(format!("{}\n  hint: {}", message, suggestion))
+            }
+            None => ConfigError::MissingPrerequisite(message),
+        }
+    }
+}
+
+impl std::fmt::Display for PrerequisiteSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.fix_command {
+            Some(command) => write!(f, "{}: {}", self.message, command),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A step of an install, skipped with `--skip-*` or applied alone with `--only-*`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallPhase {
+    Packages,
+    /// Configuration files and editor settings.
+    Configs,
+    Scripts,
+}
+
+impl InstallPhase {
+    pub const ALL: [InstallPhase; 3] = [
+        InstallPhase::Packages,
+        InstallPhase::Configs,
+        InstallPhase::Scripts,
+    ];
+
+    pub fn all() -> Vec<InstallPhase> {
+        Self::ALL.to_vec()
+    }
+}
+
+/// Per-invocation settings passed from the CLI to a provider.
+#[derive(Debug)]
+pub struct InstallOptions {
+    /// Template variables given with `--var`, these take precedence over everything else.
+    pub variables: HashMap<String, String>,
+    /// Upper bound for each package manager invocation.
+    pub timeout: Duration,
+    /// How to settle upgrade conflicts, `None` prompts for each one.
+    pub conflict_resolution: Option<ConflictResolution>,
+    /// Editors given with `--editors`, `None` uses the global config or detection.
+    pub editors: Option<Vec<Editor>>,
+    /// Multi-root workspace file receiving the VS Code settings instead of
+    /// `.vscode/settings.json`.
+    pub workspace_file: Option<PathBuf>,
+    /// Phases to apply, on upgrade and removal those recorded at install.
+    pub phases: Vec<InstallPhase>,
+    /// Back up existing project files before overwriting them.
+    pub backups: bool,
+    /// Verify the configuration files once installed.
+    pub verify: bool,
+    /// Scopes recorded at install for packages not installed as dev
+    /// dependencies, removal uses these over the provider's.
+    pub package_scopes: BTreeMap<String, PackageScope>,
+    /// Environment variables given with `--env`, set on package manager
+    /// invocations over those of the provider.
+    pub extra_env: HashMap<String, String>,
+    /// Scripts package.json had before the install, keyed by name. Removal
+    /// restores these instead of deleting the scripts.
+    pub original_scripts: BTreeMap<String, String>,
+    /// Configuration files recorded at install. Removal takes the entries
+    /// limited to a module system from these, the project may have
+    /// switched since.
+    pub installed_files: Vec<String>,
+}
+
+impl InstallOptions {
+    pub fn applies(&self, phase: InstallPhase) -> bool {
+        self.phases.contains(&phase)
+    }
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        Self {
+            variables: HashMap::new(),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            conflict_resolution: None,
+            editors: None,
+            workspace_file: None,
+            phases: InstallPhase::all(),
+            backups: false,
+            verify: false,
+            package_scopes: BTreeMap::new(),
+            extra_env: HashMap::new(),
+            original_scripts: BTreeMap::new(),
+            installed_files: vec![],
+        }
+    }
+}
+
+impl DynamicProvider {
+    /// Loads every valid provider in `dir` with its `extends` resolved. A file
+    /// that does not parse or validate is skipped with a warning.
+    pub async fn load_all(
+        project: &ProjectContext,
+        dir: Option<&PathBuf>,
+    ) -> Result<Vec<Self>, ConfigError> {
+        let mut providers = vec![];
+
+        for provider in Self::read_each(project, dir).await? {
+            match provider.and_then(|provider| provider.check().map(|_| provider)) {
+                Ok(provider) => {
+                    tracing::info!(
+                        provider = %provider.name,
+                        source = ?provider.source,
+                        "loaded provider"
+                    );
+                    providers.push(provider);
+                }
+                Err(e) => Reporter::global().warn(format!("Warning: skipping provider, {}", e)),
+            }
+        }
+
+        Self::resolve_extends(providers)
+    }
+
+    /// Reads every provider file in `dir` without resolving `extends`.
+    pub async fn read_all(
+        project: &ProjectContext,
+        dir: Option<&PathBuf>,
+    ) -> Result<Vec<Self>, ConfigError> {
+        Self::read_each(project, dir).await?.into_iter().collect()
+    }
+
+    /// Parses every provider file in `dir`, one result per file so a broken
+    /// file does not hide the others.
+    pub async fn read_each(
+        project: &ProjectContext,
+        dir: Option<&PathBuf>,
+    ) -> Result<Vec<Result<Self, ConfigError>>, ConfigError> {
+        let mut providers = vec![];
+        for path in Self::files(project, dir).await? {
+            providers.push(Self::read_file(&path).await);
+        }
+
+        Ok(providers)
+    }
+
+    /// Lists the provider files in `dir`, sorted by path.
+    pub async fn files(
+        project: &ProjectContext,
+        dir: Option<&PathBuf>,
+    ) -> Result<Vec<PathBuf>, ConfigError> {
+        let dir = match dir {
+            Some(d) => d.clone(),
+            None => AmarisPathHandler::ensure_provider_dir(project).await?,
+        };
+
+        let mut files = vec![];
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.is_file() && Self::is_provider_file(&path) {
+                files.push(path);
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    /// Loads a single provider file from outside the provider directory,
+    /// resolving its `extends` against the installed providers.
+    pub async fn load_from_file(
+        project: &ProjectContext,
+        path: &Path,
+    ) -> Result<Self, ConfigError> {
+        if !Self::is_provider_file(path) {
+            return Err(ConfigError::ValidationError(format!(
+                "{}: expected a .json, .toml, .yaml or .yml provider file",
+                path.display()
+            )));
+        }
+
+        let provider = Self::read_file(path).await?;
+        provider.check()?;
+
+        let name = provider.name.clone();
+        let mut providers = Self::read_all(project, None).await?;
+        providers.retain(|existing| existing.name != name);
+        providers.push(provider);
+
+        Self::resolve_extends(providers)?
+            .into_iter()
+            .find(|provider| provider.name == name)
+            .ok_or_else(|| ConfigError::ValidationError(format!("{}: not resolved", name)))
+    }
+
+    /// Reads the sources of the provider's own configuration entries, those
+    /// of its variants included, from `dir` instead of its config directory.
+    /// Entries inherited through `extends` keep their provider's sources.
+    pub fn read_sources_from(&mut self, dir: &Path) {
+        let entries = self.configuration.iter_mut().chain(
+            self.variants
+                .iter_mut()
+                .flat_map(|variant| variant.configuration.iter_mut()),
+        );
+
+        for config in entries.filter(|config| config.source_provider.is_none()) {
+            config.source_dir = Some(dir.to_path_buf());
+        }
+    }
+
+    /// Writes the provider back to the file it was loaded from, in the same format.
+    pub async fn save(&self) -> Result<(), ConfigError> {
+        let Some(path) = &self.source else {
+            return Err(ConfigError::PathError(format!(
+                "{} was not loaded from a file",
+                self.name
+            )));
+        };
+
+        let content = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::to_string_pretty(self)
+                .map_err(|e| ConfigError::FileWriteError(e.to_string()))?,
+            Some("yaml" | "yml") => serde_yaml::to_string(self)
+                .map_err(|e| ConfigError::FileWriteError(e.to_string()))?,
+            _ => serde_json::to_string_pretty(self)?,
+        };
+
+        tokio::fs::write(path, content)
+            .await
+            .map_err(|e| ConfigError::FileWriteError(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn is_provider_file(path: &Path) -> bool {
+        matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("json" | "toml" | "yaml" | "yml")
+        )
+    }
+
+    async fn read_file(path: &Path) -> Result<Self, ConfigError> {
+        let content = AmarisFileHandler::read_text(path).await?;
+
+        let mut provider = Self::from_content(&content, path).map_err(|e| match e {
+            ConfigError::ValidationError(message) => {
+                ConfigError::ValidationError(format!("{}: {}", path.display(), message))
+            }
+            other => other,
+        })?;
+
+        provider.source = Some(path.to_path_buf());
+        Ok(provider)
+    }
+
+    /// Parses a provider in the format implied by the extension of `path`.
+    pub fn from_content(content: &str, path: &Path) -> Result<Self, ConfigError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(content),
+            Some("yaml" | "yml") => Self::from_yaml(content),
+            _ => serde_json::from_str(content)
+                .map_err(|e| ConfigError::ValidationError(e.to_string())),
+        }
+    }
+
+    /// Checks the content of a provider file against the provider schema,
+    /// returning every violation with the location it was found at.
+    pub fn schema_issues(content: &str, path: &Path) -> Result<Vec<ValidationIssue>, ConfigError> {
+        let instance: Value = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => {
+                toml::from_str(content).map_err(|e| ConfigError::ValidationError(e.to_string()))?
+            }
+            Some("yaml" | "yml") => serde_yaml::from_str(content)
+                .map_err(|e| ConfigError::ValidationError(e.to_string()))?,
+            _ => serde_json::from_str(content)
+                .map_err(|e| ConfigError::ValidationError(e.to_string()))?,
+        };
+
+        let validator = PROVIDER_SCHEMA
+            .get_or_init(|| {
+                let schema: Value =
+                    serde_json::from_str(PROVIDER_SCHEMA_SOURCE).map_err(|e| e.to_string())?;
+                jsonschema::validator_for(&schema).map_err(|e| e.to_string())
+            })
+            .as_ref()
+            .map_err(|e| ConfigError::ValidationError(format!("provider schema: {}", e)))?;
+
+        Ok(validator
+            .iter_errors(&instance)
+            .map(|error| {
+                let location = error.instance_path().to_string();
+                let field = match location.is_empty() {
+                    true => "/".to_string(),
+                    false => location,
+                };
+
+                ValidationIssue {
+                    message: format!("{}: {}", field, error.masked()),
+                    field,
+                }
+            })
+            .collect())
+    }
+
+    /// Checks the fields serde cannot, so a broken provider fails before
+    /// anything is installed. Every problem is returned, not only the first.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        let mut issue = |field: &str, message: String| {
+            issues.push(ValidationIssue {
+                field: field.to_string(),
+                message,
+            })
+        };
+
+        if self.name.trim().is_empty() {
+            issue("name", "name must not be empty".to_string());
+        } else if !is_provider_name(&self.name) {
+            issue(
+                "name",
+                format!(
+                    "name '{}' may only contain lowercase letters, digits, _ and -",
+                    self.name
+                ),
+            );
+        }
+
+        for alias in &self.aliases {
+            if alias == &self.name || !is_provider_name(alias) {
+                issue(
+                    "aliases",
+                    format!(
+                        "alias '{}' must be a valid provider name other than its own",
+                        alias
+                    ),
+                );
+            }
+        }
+
+        if let Some(replacement) = &self.replaced_by
+            && (!self.deprecated || replacement == &self.name || !is_provider_name(replacement))
+        {
+            issue(
+                "replaced_by",
+                format!(
+                    "replaced_by '{}' needs deprecated set and must name another provider",
+                    replacement
+                ),
+            );
+        }
+
+        if !PACKAGE_MANAGERS.contains(&self.package_manager.as_str()) {
+            issue(
+                "package_manager",
+                format!(
+                    "package_manager '{}' is not one of: {}",
+                    self.package_manager,
+                    PACKAGE_MANAGERS.join(", ")
+                ),
+            );
+        }
+
+        if let Some(registry) = &self.registry
+            && !registry.starts_with("https://")
+            && !registry.starts_with("http://")
+        {
+            issue(
+                "registry",
+                format!("registry '{}' must be an http or https URL", registry),
+            );
+        }
+
+        for config in self.configuration.iter().filter(|config| {
+            config.file_location.is_empty()
+                || (config.source_from.is_empty() && config.inline_content.is_none())
+        }) {
+            issue(
+                "configuration",
+                format!(
+                    "configuration entry '{}' needs a file_location and either source_from or inline_content",
+                    config.file_name
+                ),
+            );
+        }
+
+        let configuration_lists = std::iter::once(&self.configuration)
+            .chain(self.variants.iter().map(|variant| &variant.configuration));
+        for configurations in configuration_lists {
+            let mut locations = HashSet::new();
+
+            for config in configurations {
+                if !config.file_location.is_empty()
+                    && !locations.insert(AmarisPathHandler::normalize(&config.file_location))
+                {
+                    issue(
+                        "configuration",
+                        format!(
+                            "file_location '{}' is written by more than one configuration entry",
+                            config.file_location
+                        ),
+                    );
+                }
+
+                let fields = [
+                    ("file_location", &config.file_location),
+                    ("source_from", &config.source_from),
+                ];
+
+                // Symbolic links are checked when the entry is used
+                for (field, location) in fields {
+                    if let Err(reason) = AmarisPathHandler::check_relative(location) {
+                        issue(
+                            field,
+                            format!(
+                                "{} of configuration entry '{}' {}: {}",
+                                field, config.file_name, reason, location
+                            ),
+                        );
+                    }
+                }
+
+                if config.source_from.contains("://") || config.source_from.starts_with('~') {
+                    issue(
+                        "source_from",
+                        format!(
+                            "source_from of configuration entry '{}' must be a path relative to the provider's config directory: {}",
+                            config.file_name, config.source_from
+                        ),
+                    );
+                }
+
+                if config.merge == Some(MergeStrategy::Merge)
+                    && config.format == Some(ConfigFormat::Raw)
+                {
+                    issue(
+                        "merge",
+                        format!(
+                            "configuration entry '{}' has format raw, which cannot be merged",
+                            config.file_name
+                        ),
+                    );
+                }
+            }
+        }
+
+        if let Some(version) = &self.version
+            && !is_semver(version)
+        {
+            issue(
+                "version",
+                format!(
+                    "version '{}' is not a semantic version (MAJOR.MINOR.PATCH)",
+                    version
+                ),
+            );
+        }
+
+        if let Some(homepage) = &self.homepage
+            && !homepage.starts_with("https://")
+            && !homepage.starts_with("http://")
+        {
+            issue(
+                "homepage",
+                format!("homepage '{}' must be an http(s) URL", homepage),
+            );
+        }
+
+        for (field, version) in [
+            ("node_version", &self.node_version),
+            ("min_node_version", &self.min_node_version),
+        ] {
+            if let Some(version) = version
+                && !AmarisNodeVersionHandler::is_valid_version(version)
+            {
+                issue(
+                    field,
+                    format!(
+                        "{} '{}' is not a Node.js version, expected something like 20 or v20.11.0",
+                        field, version
+                    ),
+                );
+            }
+        }
+
+        for (name, value) in &self.environment {
+            if value
+                .split("${")
+                .skip(1)
+                .any(|reference| !reference.contains('}'))
+            {
+                issue(
+                    "environment",
+                    format!(
+                        "environment variable {} has an unterminated ${{...}} reference",
+                        name
+                    ),
+                );
+            }
+        }
+
+        let script_lists = std::iter::once(&self.scripts)
+            .chain(self.variants.iter().map(|variant| &variant.scripts));
+        for scripts in script_lists {
+            let mut names = HashSet::new();
+
+            for script in scripts {
+                if !names.insert(&script.name) {
+                    issue(
+                        "scripts",
+                        format!("script '{}' is defined more than once", script.name),
+                    );
+                }
+
+                if let Some(condition) = &script.condition
+                    && split_condition(condition).is_none()
+                {
+                    issue(
+                        "scripts",
+                        format!(
+                            "script '{}' has an unknown condition '{}', expected has_dep:<package>, file_exists:<path> or env:<VAR>",
+                            script.name, condition
+                        ),
+                    );
+                }
+            }
+        }
+
+        for (package, version) in self.exact_versions.iter().filter(|(_, version)| {
+            version.trim().is_empty() || version.contains(char::is_whitespace)
+        }) {
+            issue(
+                "exact_versions",
+                format!(
+                    "exact_versions entry '{}' needs a version or *, got '{}'",
+                    package, version
+                ),
+            );
+        }
+
+        for pattern in self
+            .gitignore
+            .iter()
+            .filter(|pattern| pattern.trim().is_empty() || pattern.contains('\n'))
+        {
+            issue(
+                "gitignore",
+                format!("gitignore entry '{}' must be a single pattern", pattern),
+            );
+        }
+
+        let platforms = std::iter::once(&self.platform)
+            .chain(self.configuration.iter().map(|config| &config.platform));
+        for platform in platforms {
+            if let Err(ConfigError::ValidationError(message)) =
+                AmarisPlatformHandler::validate(platform)
+            {
+                issue("platform", message);
+            }
+        }
+
+        issues
+    }
+
+    /// Fails with every issue of [`DynamicProvider::validate`], attributed to
+    /// the provider file.
+    pub fn check(&self) -> Result<(), ConfigError> {
+        let issues = self.validate();
+        if issues.is_empty() {
+            return Ok(());
+        }
+
+        let issues: Vec<String> = issues.iter().map(ValidationIssue::to_string).collect();
+        Err(ConfigError::ValidationError(format!(
+            "{}: {}",
+            self.file_label(),
+            issues.join("; ")
+        )))
+    }
+
+    /// The file the provider was loaded from, or its name when it has none.
+    pub fn file_label(&self) -> String {
+        self.source
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    /// Merges every provider's `extends` chain into it. Parents are applied in
+    /// order, then the provider's own definition on top.
+    pub fn resolve_extends(providers: Vec<Self>) -> Result<Vec<Self>, ConfigError> {
+        // Names go in last, so they win over an alias of another provider
+        let by_name: HashMap<&str, &DynamicProvider> = providers
+            .iter()
+            .flat_map(|p| p.aliases.iter().map(move |alias| (alias.as_str(), p)))
+            .chain(providers.iter().map(|p| (p.name.as_str(), p)))
+            .collect();
+        let mut resolved: HashMap<String, DynamicProvider> = HashMap::new();
+
+        for provider in &providers {
+            Self::resolve_provider(provider, &by_name, &mut resolved, &mut vec![])?;
+        }
+
+        Ok(providers
+            .iter()
+            .filter_map(|provider| resolved.remove(&provider.name))
+            .collect())
+    }
+
+    fn resolve_provider(
+        provider: &DynamicProvider,
+        by_name: &HashMap<&str, &DynamicProvider>,
+        resolved: &mut HashMap<String, DynamicProvider>,
+        stack: &mut Vec<String>,
+    ) -> Result<(), ConfigError> {
+        if resolved.contains_key(&provider.name) {
+            return Ok(());
+        }
+
+        if let Some(start) = stack.iter().position(|name| name == &provider.name) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(provider.name.clone());
+            return Err(ConfigError::ValidationError(format!(
+                "Circular extends: {}",
+                cycle.join(" → ")
+            )));
+        }
+
+        stack.push(provider.name.clone());
+
+        let mut base: Option<DynamicProvider> = None;
+        let mut lineage: Vec<String> = vec![];
+
+        for parent_name in &provider.extends {
+            let Some(parent) = by_name.get(parent_name.as_str()) else {
+                let file = provider
+                    .source
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|| provider.name.clone());
+                return Err(ConfigError::ValidationError(format!(
+                    "{}: extends unknown provider '{}'",
+                    file, parent_name
+                )));
+            };
+
+            Self::resolve_provider(parent, by_name, resolved, stack)?;
+
+            let parent_name = &parent.name;
+            let mut parent = resolved[parent_name].clone();
+            for config in &mut parent.configuration {
+                config
+                    .source_provider
+                    .get_or_insert_with(|| parent_name.clone());
+            }
+
+            lineage.extend(parent.lineage.iter().cloned());
+            lineage.push(parent_name.clone());
+
+            base = Some(match base {
+                Some(base) => base.overlay(&parent),
+                None => parent,
+            });
+        }
+
+        let mut result = match base {
+            Some(base) => base.overlay(provider),
+            None => provider.clone(),
+        };
+
+        let mut seen = vec![];
+        lineage.retain(|name| {
+            let first = !seen.contains(name);
+            seen.push(name.clone());
+            first
+        });
+        result.lineage = lineage;
+
+        stack.pop();
+        resolved.insert(provider.name.clone(), result);
+
+        Ok(())
+    }
+
+    /// Applies `child` on top of this provider: packages are concatenated,
+    /// configuration entries are replaced by `file_location` and scripts by name.
+    fn overlay(mut self, child: &DynamicProvider) -> DynamicProvider {
+        for package in &child.packages {
+            match self
+                .packages
+                .iter_mut()
+                .find(|existing| existing.name == package.name)
+            {
+                Some(existing) => existing.scope = package.scope,
+                None => self.packages.push(package.clone()),
+            }
+        }
+
+        for config in &child.configuration {
+            self.configuration
+                .retain(|existing| existing.file_location != config.file_location);
+            self.configuration.push(config.clone());
+        }
+
+        for script in &child.scripts {
+            self.scripts.retain(|existing| existing.name != script.name);
+            self.scripts.push(script.clone());
+        }
+
+        for file in &child.conflicting_files {
+            if !self.conflicting_files.contains(file) {
+                self.conflicting_files.push(file.clone());
+            }
+        }
+
+        for pattern in &child.gitignore {
+            if !self.gitignore.contains(pattern) {
+                self.gitignore.push(pattern.clone());
+            }
+        }
+
+        for key in &child.owned_settings {
+            if !self.owned_settings.contains(key) {
+                self.owned_settings.push(key.clone());
+            }
+        }
+
+        self.variables.extend(child.variables.clone());
+        self.exact_versions.extend(child.exact_versions.clone());
+        self.environment.extend(child.environment.clone());
+        self.requires_git |= child.requires_git;
+
+        for (editor, settings) in &child.editor_settings {
+            merge_json_values(
+                self.editor_settings
+                    .entry(*editor)
+                    .or_insert_with(|| serde_json::json!({})),
+                settings,
+            );
+        }
+
+        for dependency in &child.depends_on {
+            if !self.depends_on.contains(dependency) {
+                self.depends_on.push(dependency.clone());
+            }
+        }
+
+        for variant in &child.variants {
+            self.variants
+                .retain(|existing| existing.name != variant.name);
+            self.variants.push(variant.clone());
+        }
+
+        DynamicProvider {
+            name: child.name.clone(),
+            description: child.description.clone(),
+            author: child.author.clone(),
+            version: child.version.clone(),
+            homepage: child.homepage.clone(),
+            license: child.license.clone().or(self.license),
+            package_manager: child.package_manager.clone(),
+            registry: child.registry.clone().or(self.registry),
+            platform: child.platform.clone().or(self.platform),
+            node_version: child.node_version.clone().or(self.node_version),
+            min_node_version: child.min_node_version.clone().or(self.min_node_version),
+            extends: child.extends.clone(),
+            aliases: child.aliases.clone(),
+            deprecated: child.deprecated,
+            replaced_by: child.replaced_by.clone(),
+            deprecation_message: child.deprecation_message.clone(),
+            source: child.source.clone(),
+            ..self
+        }
+    }
+
+    /// Applies a variant on top of the shared definition, merged the same way as `extends`.
+    pub fn with_variant(&self, name: &str) -> Result<Self, ConfigError> {
+        let Some(variant) = self.variants.iter().find(|variant| variant.name == name) else {
+            let available: Vec<&str> = self.variants.iter().map(|v| v.name.as_str()).collect();
+            return Err(ConfigError::ValidationError(match available.is_empty() {
+                true => format!("{} has no variants", self.name),
+                false => format!(
+                    "{} has no variant '{}', available: {}",
+                    self.name,
+                    name,
+                    available.join(", ")
+                ),
+            }));
+        };
+
+        let child = DynamicProvider {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            package_manager: self.package_manager.clone(),
+            packages: variant.packages.clone(),
+            configuration: variant.configuration.clone(),
+            scripts: variant.scripts.clone(),
+            variables: variant.variables.clone(),
+            extends: self.extends.clone(),
+            source: self.source.clone(),
+            ..Default::default()
+        };
+
+        let mut provider = self.clone().overlay(&child);
+        provider.variant = Some(variant.name.clone());
+
+        Ok(provider)
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, ConfigError> {
+        toml::from_str(content).map_err(|e| ConfigError::ValidationError(e.to_string()))
+    }
+
+    pub fn from_yaml(content: &str) -> Result<Self, ConfigError> {
+        serde_yaml::from_str(content).map_err(|e| ConfigError::ValidationError(e.to_string()))
+    }
+
+    pub fn metadata(&self) -> ProviderMetadata {
+        ProviderMetadata {
+            author: self.author.clone(),
+            version: self.version.clone(),
+            homepage: self.homepage.clone(),
+            license: self.license.clone(),
+            deprecation: self.deprecated.then(|| Deprecation {
+                replaced_by: self.replaced_by.clone(),
+                message: self.deprecation_message.clone(),
+            }),
+        }
+    }
+}
+
+/// Authorship and version information shown by `amaya info`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProviderMetadata {
+    pub author: Option<String>,
+    pub version: Option<String>,
+    pub homepage: Option<String>,
+    pub license: Option<String>,
+    pub deprecation: Option<Deprecation>,
+}
+
+/// Why and in favour of what a provider is deprecated.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Deprecation {
+    pub replaced_by: Option<String>,
+    pub message: Option<String>,
+}
+
+impl Deprecation {
+    /// One line for warnings, such as `use biome-strict instead: lint rules moved`.
+    pub fn describe(&self) -> String {
+        let replacement = self
+            .replaced_by
+            .as_ref()
+            .map(|replacement| format!("use {} instead", replacement));
+
+        match (replacement, &self.message) {
+            (Some(replacement), Some(message)) => format!("{}: {}", replacement, message),
+            (Some(replacement), None) => replacement,
+            (None, Some(message)) => message.clone(),
+            (None, None) => "no replacement given".to_string(),
+        }
+    }
+}
+
+/// Whether `name` is usable as a provider name: lowercase letters, digits,
+/// `_` and `-`, as it ends up in file and directory names.
+fn is_provider_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// Whether `version` is `MAJOR.MINOR.PATCH` with an optional pre-release or
+/// build suffix.
+fn is_semver(version: &str) -> bool {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+
+    parts.len() == 3
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part.chars().all(|c| c.is_ascii_digit())
+                && (part.len() == 1 || !part.starts_with('0'))
+        })
+        && !version.ends_with(['-', '+'])
+}
+
+/// An option given to [`AmarisProvider::set_option`] as the string its
+/// variables hold. Lists are joined with commas.
+pub fn option_value(provider: &str, key: &str, value: &Value) -> Result<String, ConfigError> {
+    let scalar = |value: &Value| match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+        _ => None,
+    };
+
+    let value = match value {
+        Value::Array(items) => items
+            .iter()
+            .map(scalar)
+            .collect::<Option<Vec<_>>>()
+            .map(|items| items.join(",")),
+        value => scalar(value),
+    };
+
+    value.ok_or_else(|| {
+        ConfigError::ValidationError(format!(
+            "option '{}' of {} must be a string, number, boolean or list of them",
+            key, provider
+        ))
+    })
+}
+
+/// The parts of the current project provider detection looks at, read once
+/// and shared by every provider.
+#[derive(Debug, Default, Clone)]
+pub struct ProjectContext {
+    pub root: PathBuf,
+    /// Packages in `dependencies` and `devDependencies`.
+    pub dependencies: HashSet<String>,
+    /// Package manager pinned in package.json or implied by a lockfile.
+    pub package_manager: Option<String>,
+    /// What this run has written and collected so far, shared by the clones.
+    pub run: Arc<RunContext>,
+}
+
+impl ProjectContext {
+    /// Reads the project at `root`.
+    pub async fn load(root: PathBuf) -> Result<Self, ConfigError> {
+        let mut project = Self {
+            root,
+            ..Self::default()
+        };
+        let package_json = AmarisPackageJsonHandler::read(&project).await?;
+
+        project.dependencies = ["dependencies", "devDependencies"]
+            .iter()
+            .filter_map(|field| package_json.get(field).and_then(Value::as_object))
+            .flat_map(|deps| deps.keys().cloned())
+            .collect();
+        project.package_manager = AmarisPackageManager::pinned(&package_json)
+            .map(|(manager, _)| manager)
+            .or_else(|| AmarisPackageManager::from_lockfile(&project).map(str::to_string));
+
+        Ok(project)
+    }
+
+    /// The project of `cwd`, or of the current directory when it is `None`,
+    /// see [`Self::find_root`].
+    pub async fn detect(cwd: Option<&Path>) -> Result<Self, ConfigError> {
+        let start = match cwd {
+            Some(dir) => dir
+                .canonicalize()
+                .map_err(|e| ConfigError::PathError(format!("{}: {}", dir.display(), e)))?,
+            None => std::env::current_dir()?,
+        };
+
+        Self::load(Self::find_root(&start)).await
+    }
+
+    /// Reads the project again, after an install changed its package.json.
+    /// The reloaded project carries on with the same run.
+    pub async fn reload(&self) -> Result<Self, ConfigError> {
+        Ok(Self {
+            run: self.run.clone(),
+            ..Self::load(self.root.clone()).await?
+        })
+    }
+
+    /// The project `start` belongs to: the closest directory with a
+    /// package.json, looking no further up than the root of its git
+    /// repository. `start` itself when there is none.
+    pub fn find_root(start: &Path) -> PathBuf {
+        for dir in start.ancestors() {
+            if dir.join(PACKAGE_JSON_FILE_NAME).is_file() {
+                return dir.to_path_buf();
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        start.to_path_buf()
+    }
+
+    pub fn has_dependency(&self, name: &str) -> bool {
+        self.dependencies.contains(name)
+    }
+
+    /// `relative` resolved against the project root.
+    pub fn path(&self, relative: impl AsRef<Path>) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    /// `path` relative to the project root, as it is shown and recorded.
+    /// Paths outside the project are returned as they are.
+    pub fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root).unwrap_or(path).to_path_buf()
+    }
+
+    pub fn has_file(&self, relative: impl AsRef<Path>) -> bool {
+        self.path(relative).exists()
+    }
+}
+
+/// The aliases of the registered providers, for reading project files that
+/// still use an old name.
+pub struct AmarisAliases;
+
+impl AmarisAliases {
+    pub fn init(aliases: HashMap<String, String>) {
+        let _ = PROVIDER_ALIASES.set(aliases);
+    }
+
+    /// The current name of a provider that was renamed from `name`.
+    pub fn resolve(name: &str) -> Option<&'static str> {
+        PROVIDER_ALIASES.get()?.get(name).map(String::as_str)
+    }
+
+    /// The names `name` was renamed from.
+    pub fn previous_names(name: &str) -> Vec<&'static str> {
+        PROVIDER_ALIASES
+            .get()
+            .map(|aliases| {
+                aliases
+                    .iter()
+                    .filter(|(_, current)| *current == name)
+                    .map(|(alias, _)| alias.as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// How sure detection is that a provider is applied to the project.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DetectionConfidence {
+    /// The provider cannot tell.
+    #[default]
+    Unknown,
+    /// None of its packages or files are present.
+    Absent,
+    /// Some of its packages or files are present.
+    Partial,
+    /// All of its packages and at least one of its files are present.
+    Full,
+}
+
+impl DetectionConfidence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DetectionConfidence::Unknown => "unknown",
+            DetectionConfidence::Absent => "absent",
+            DetectionConfidence::Partial => "partial",
+            DetectionConfidence::Full => "full",
+        }
+    }
+}
+
+/// What [`AmarisProvider::diff`] found installing a provider would change.
+#[derive(Debug, Default, Clone)]
+pub struct ProviderDiff {
+    pub files: Vec<ConfigDiff>,
+    pub scripts: Vec<ScriptChange>,
+}
+
+impl ProviderDiff {
+    /// Whether installing would leave every file and script as it is.
+    pub fn is_identical(&self) -> bool {
+        self.scripts.is_empty()
+            && self
+                .files
+                .iter()
+                .all(|file| file.change == ConfigChange::Identical)
+    }
+}
+
+/// What [`AmarisProvider::detect`] found of a provider in the project.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DetectionResult {
+    pub packages: Vec<String>,
+    pub files: Vec<String>,
+    pub confidence: DetectionConfidence,
+}
+
+impl DetectionResult {
+    /// Matches `packages` and `files` against the project.
+    pub fn from_project(project: &ProjectContext, packages: &[String], files: &[String]) -> Self {
+        let found_packages: Vec<String> = packages
+            .iter()
+            .filter(|package| project.has_dependency(package))
+            .cloned()
+            .collect();
+        let found_files: Vec<String> = files
+            .iter()
+            .filter(|file| project.has_file(file))
+            .cloned()
+            .collect();
+
+        let confidence = match (found_packages.len(), found_files.len()) {
+            (0, 0) => DetectionConfidence::Absent,
+            (found, matched) if found == packages.len() && (matched > 0 || files.is_empty()) => {
+                DetectionConfidence::Full
+            }
+            _ => DetectionConfidence::Partial,
+        };
+
+        Self {
+            packages: found_packages,
+            files: found_files,
+            confidence,
+        }
+    }
+
+    /// Whether anything of the provider was found.
+    pub fn is_present(&self) -> bool {
+        matches!(
+            self.confidence,
+            DetectionConfidence::Partial | DetectionConfidence::Full
+        )
+    }
+}
+
+#[async_trait]
+pub trait AmarisProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    async fn check_prerequisites(&self, project: &ProjectContext) -> Result<(), ConfigError>;
+    async fn check_conflicts(&self, project: &ProjectContext) -> Result<(), ConfigError>;
+    /// Problems with the configuration files installed in `project`.
+    async fn verify(
+        &self,
+        project: &ProjectContext,
+        _options: &InstallOptions,
+    ) -> Result<Vec<String>, ConfigError> {
+        Ok(self
+            .managed_files(project)
+            .iter()
+            .filter(|file| !project.path(file).exists())
+            .map(|file| format!("{} is missing", file.display()))
+            .collect())
+    }
+    /// How installing would change the configuration files and scripts of
+    /// `project`, without changing anything.
+    async fn diff(
+        &self,
+        _project: &ProjectContext,
+        _options: &InstallOptions,
+    ) -> Result<ProviderDiff, ConfigError> {
+        Err(ConfigError::ValidationError(format!(
+            "{} generates its files while installing, they cannot be compared beforehand",
+            self.name()
+        )))
+    }
+    /// Hints appended to failed prerequisite checks and shown by `amaya doctor`.
+    fn prerequisite_suggestions(&self) -> Vec<PrerequisiteSuggestion> {
+        vec![]
+    }
+    fn packages(&self) -> Vec<String> {
+        vec![]
+    }
+    /// The packages with the scope each is installed in, dev dependencies by default.
+    fn package_entries(&self) -> Vec<PackageEntry> {
+        self.packages()
+            .iter()
+            .map(|package| PackageEntry::from(package.as_str()))
+            .collect()
+    }
+    fn config_files(&self) -> Vec<ConfigEntry> {
+        vec![]
+    }
+    fn scripts(&self) -> Vec<ScriptEntry> {
+        vec![]
+    }
+    /// Providers merged into this one through `extends`, outermost ancestor first.
+    fn lineage(&self) -> Vec<String> {
+        vec![]
+    }
+    /// Former names the provider is still found under.
+    fn aliases(&self) -> Vec<String> {
+        vec![]
+    }
+    /// Names of the providers that must be installed before this one.
+    fn dependencies(&self) -> Vec<String> {
+        vec![]
+    }
+    /// Default values of the `{{variable}}` placeholders in the configuration sources.
+    fn variables(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+    /// Variables set on the package manager commands, before `${VAR}` references are resolved.
+    fn environment(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+    fn metadata(&self) -> ProviderMetadata {
+        ProviderMetadata::default()
+    }
+    /// Sets an option of the provider, such as `indent_width`, from the
+    /// `[options.<provider>]` table of config.toml. Ignored unless overridden.
+    fn set_option(&mut self, _key: &str, _value: Value) -> Result<(), ConfigError> {
+        Ok(())
+    }
+    /// Whether the provider is compiled into amaya rather than read from a provider file.
+    fn is_builtin(&self) -> bool {
+        false
+    }
+    /// Which of the provider's packages and files are in the project, a cheap
+    /// check that does not need amaya.json.
+    async fn detect(&self, _project: &ProjectContext) -> Result<DetectionResult, ConfigError> {
+        Ok(DetectionResult::default())
+    }
+    fn variants(&self) -> Vec<ProviderVariant> {
+        vec![]
+    }
+    /// The first variant whose `detect` dependencies are present in package.json.
+    async fn detect_variant(
+        &self,
+        _project: &ProjectContext,
+    ) -> Result<Option<String>, ConfigError> {
+        Ok(None)
+    }
+    fn with_variant(&self, variant: &str) -> Result<Box<dyn AmarisProvider>, ConfigError> {
+        Err(ConfigError::ValidationError(format!(
+            "{} has no variant '{}'",
+            self.name(),
+            variant
+        )))
+    }
+    /// The configuration files the provider writes, relative to the project root.
+    fn managed_files(&self, project: &ProjectContext) -> Vec<PathBuf>;
+    /// Every file installing may write, relative to the project root. VS Code
+    /// settings go to `workspace_file` instead of `.vscode/settings.json` when set.
+    fn planned_writes(
+        &self,
+        project: &ProjectContext,
+        workspace_file: Option<&Path>,
+    ) -> Vec<PathBuf>;
+    async fn install(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError>;
+    async fn upgrade(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError>;
+    async fn remove(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError>;
+}
+
+struct DynamicProviderImpl {
+    name: String,
+    description: String,
+    provider: DynamicProvider,
+}
+
+impl From<DynamicProvider> for DynamicProviderImpl {
+    fn from(provider: DynamicProvider) -> Self {
+        Self {
+            name: provider.name.clone(),
+            description: provider.description.clone(),
+            provider,
+        }
+    }
+}
+
+impl From<DynamicProvider> for Box<dyn AmarisProvider> {
+    fn from(provider: DynamicProvider) -> Self {
+        Box::new(DynamicProviderImpl::from(provider))
+    }
+}
+
+impl DynamicProviderImpl {
+    /// The packages not in package.json yet. Global packages cannot be looked
+    /// up there, so they are always installed again.
+    async fn missing_packages(
+        &self,
+        project: &ProjectContext,
+    ) -> Result<Vec<PackageEntry>, ConfigError> {
+        let detection = self.detect(&project.reload().await?).await?;
+
+        Ok(self
+            .provider
+            .packages
+            .iter()
+            .filter(|package| package.is_global() || !detection.packages.contains(&package.name))
+            .cloned()
+            .collect())
+    }
+
+    /// The packages installed into the project, those package.json lists.
+    fn project_packages(&self) -> Vec<String> {
+        self.provider
+            .packages
+            .iter()
+            .filter(|package| !package.is_global())
+            .map(|package| package.name.clone())
+            .collect()
+    }
+
+    /// The scripts whose condition holds, or that have none.
+    async fn applicable_scripts(&self, project: &ProjectContext) -> Vec<ScriptEntry> {
+        let mut scripts = vec![];
+
+        for script in &self.provider.scripts {
+            match &script.condition {
+                Some(condition) if !evaluate_condition(project, condition).await => {
+                    Reporter::global().info(format!(
+                        "Skipping script {}, {} does not hold",
+                        script.name, condition
+                    ));
+                }
+                _ => scripts.push(script.clone()),
+            }
+        }
+
+        scripts
+    }
+
+    /// The package manager to run, the project's one when it pins or locks one.
+    async fn package_manager(
+        &self,
+        project: &ProjectContext,
+    ) -> Result<ResolvedPackageManager, ConfigError> {
+        AmarisPackageManager::global()
+            .resolve(project, &self.provider.package_manager)
+            .await
+    }
+
+    async fn install_context(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<InstallContext, ConfigError> {
+        InstallContext::resolve(
+            project,
+            self.provider.registry.as_deref(),
+            &self.provider.environment,
+            &options.extra_env,
+        )
+        .await
+    }
+
+    /// Installs `packages` in their scope, pinning those listed in `exact_versions`.
+    async fn install_packages(
+        &self,
+        project: &ProjectContext,
+        packages: &[PackageEntry],
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let manager = self.package_manager(project).await?.name;
+        let context = self.install_context(project, options).await?;
+
+        for scope in [PackageScope::Dev, PackageScope::Prod, PackageScope::Global] {
+            let (exact, latest): (Vec<String>, Vec<String>) = packages
+                .iter()
+                .filter(|package| package.scope == scope)
+                .map(|package| package.name.clone())
+                .partition(|package| self.provider.exact_versions.contains_key(package));
+
+            for package in &exact {
+                AmarisInstaller::install_exact(
+                    project,
+                    &manager,
+                    scope,
+                    &context,
+                    package,
+                    &self.provider.exact_versions[package],
+                    options.timeout,
+                )
+                .await?;
+            }
+
+            AmarisInstaller::install(project, &manager, scope, &context, &latest, options.timeout)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the packages of the provider from the scope they were
+    /// installed in, taken from the recorded scopes of `options` first.
+    async fn remove_packages(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let manager = self.package_manager(project).await?.name;
+        let context = self.install_context(project, options).await?;
+        let scopes = &options.package_scopes;
+
+        for scope in [PackageScope::Dev, PackageScope::Prod, PackageScope::Global] {
+            let packages: Vec<String> = self
+                .provider
+                .packages
+                .iter()
+                .filter(|package| *scopes.get(&package.name).unwrap_or(&package.scope) == scope)
+                .map(|package| package.name.clone())
+                .collect();
+
+            AmarisInstaller::remove(
+                project,
+                &manager,
+                scope,
+                &context,
+                &packages,
+                options.timeout,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the `gitignore` patterns in a section named after the
+    /// provider, dropping the section once the provider has none.
+    async fn write_gitignore(&self, project: &ProjectContext) -> Result<(), ConfigError> {
+        if self.provider.gitignore.is_empty() {
+            return AmarisConfigurationHandler::remove_gitignore_entries(project, &self.name).await;
+        }
+
+        let entries: Vec<&str> = self.provider.gitignore.iter().map(String::as_str).collect();
+        AmarisConfigurationHandler::write_gitignore_entries(project, &self.name, &entries).await
+    }
+
+    async fn apply_editor_settings(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        if self.provider.editor_settings.is_empty() {
+            return Ok(());
+        }
+
+        for editor in AmarisEditorHandler::resolve(project, &options.editors).await? {
+            if let Some(settings) = self.provider.editor_settings.get(&editor) {
+                Reporter::global().info(format!("Writing {} settings...", editor));
+                AmarisEditorHandler::apply_settings(
+                    project,
+                    editor,
+                    settings,
+                    options.workspace_file.as_deref(),
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds everything that would make the install fail halfway, so it can
+    /// be reported at once before anything is changed.
+    async fn preflight(
+        &self,
+        project: &ProjectContext,
+        configurations: &Vec<ConfigEntry>,
+        variables: &HashMap<String, String>,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let mut problems = vec![];
+
+        if options.applies(InstallPhase::Packages) && !self.provider.packages.is_empty() {
+            let manager = self.package_manager(project).await?.name;
+            if !AmarisPackageManager::global().is_available(&manager) {
+                problems.push(format!("package manager '{}' not found", manager));
+            }
+        }
+
+        if options.applies(InstallPhase::Configs) {
+            problems.extend(
+                AmarisConfigurationHandler::preflight_configs(
+                    project,
+                    &self.name,
+                    configurations,
+                    variables,
+                    options.workspace_file.as_deref(),
+                )
+                .await,
+            );
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        Err(ConfigError::ValidationError(format!(
+            "{} cannot be installed, nothing was changed:\n  {}",
+            self.name,
+            problems.join("\n  ")
+        )))
+    }
+
+    /// Applies project overrides and `--var` flags on top of the provider definition.
+    async fn resolve_configurations(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(Vec<ConfigEntry>, HashMap<String, String>), ConfigError> {
+        let overrides = ProjectOverrides::load(project)
+            .await?
+            .get(&self.name)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut variables = self.provider.variables.clone();
+        if let Some(version) = AmarisPackageJsonHandler::detect_typescript_version(project).await? {
+            variables.insert(TYPESCRIPT_VERSION_VARIABLE.to_string(), version.to_string());
+        }
+        variables.insert(
+            MODULE_SYNTAX_VARIABLE.to_string(),
+            ModuleSystem::detect(project).as_str().to_string(),
+        );
+        variables.extend(overrides.variables.clone());
+        variables.extend(options.variables.clone());
+
+        let configurations: Vec<ConfigEntry> = self
+            .provider
+            .configuration
+            .iter()
+            .map(|config| {
+                let mut config = config.clone();
+                if let Some(strategy) = overrides.merge.get(&config.file_location) {
+                    config.merge = Some(*strategy);
+                }
+                config
+            })
+            .collect();
+
+        Ok((configurations, variables))
+    }
+}
+
+#[async_trait]
+impl AmarisProvider for DynamicProviderImpl {
+    fn name(&self) -> &'static str {
+        Box::leak(self.name.clone().into_boxed_str())
+    }
+
+    fn description(&self) -> &'static str {
+        Box::leak(self.description.clone().into_boxed_str())
+    }
+
+    async fn check_prerequisites(&self, project: &ProjectContext) -> Result<(), ConfigError> {
+        AmarisPlatformHandler::validate(&self.provider.platform)?;
+        for config in &self.provider.configuration {
+            AmarisPlatformHandler::validate(&config.platform)?;
+        }
+
+        if let Some(platforms) = &self.provider.platform
+            && !AmarisPlatformHandler::matches(&self.provider.platform)
+        {
+            let names: Vec<&str> = platforms
+                .iter()
+                .map(|p| AmarisPlatformHandler::display_name(p))
+                .collect();
+
+            return Err(ConfigError::MissingPrerequisite(format!(
+                "This provider requires {}",
+                names.join(" or ")
+            )));
+        }
+
+        let suggestions = self.prerequisite_suggestions();
+
+        if !AmarisPackageJsonHandler::get_default_path(project).exists() {
+            return Err(PrerequisiteSuggestion::apply(
+                &suggestions,
+                CONDITION_PACKAGE_JSON,
+                "package.json not found!".to_string(),
+            ));
+        }
+
+        if self.provider.requires_git && !AmarisPathHandler::is_git_repo(project) {
+            return Err(PrerequisiteSuggestion::apply(
+                &suggestions,
+                CONDITION_GIT,
+                "This provider requires a git repository".to_string(),
+            ));
+        }
+
+        if let Some(version) = &self.provider.min_node_version {
+            AmarisNodeVersionHandler::require_minimum(version).await?;
+        }
+
+        let manager = self.package_manager(project).await?;
+        AmarisPackageManager::warn_on_mismatch(
+            &self.name,
+            &self.provider.package_manager,
+            &manager,
+        );
+
+        if !AmarisPackageManager::global().ensure_available(&manager)? {
+            let missing = self.missing_packages(project).await?;
+
+            let message = match missing.is_empty() {
+                true => format!("Package manager '{}' not found", manager.name),
+                false => format!(
+                    "Package manager '{}' not found, it is required to install: {}",
+                    manager.name,
+                    missing
+                        .iter()
+                        .map(|package| package.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+
+            return Err(PrerequisiteSuggestion::apply(
+                &PrerequisiteSuggestion::for_resolved_manager(&manager),
+                CONDITION_PACKAGE_MANAGER,
+                message,
+            ));
+        }
+
+        // The runtimes the project declares in `engines`, a runtime that is
+        // not installed is not the one the project runs on
+        if let Some(engines) = AmarisPackageJsonHandler::get_engines(project).await? {
+            if engines.contains_key(NODE_ENGINE)
+                && let Ok(version) = AmarisNodeVersionHandler::get_node_version().await
+            {
+                AmarisPackageJsonHandler::check_engine_compatibility(&engines, &version)?;
+            }
+            if manager.name == BUN_ENGINE
+                && engines.contains_key(BUN_ENGINE)
+                && let Some(version) = AmarisInstaller::get_bun_version().await
+            {
+                AmarisPackageJsonHandler::check_engine(&engines, BUN_ENGINE, &version)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn prerequisite_suggestions(&self) -> Vec<PrerequisiteSuggestion> {
+        let mut suggestions =
+            PrerequisiteSuggestion::for_package_manager(&self.provider.package_manager);
 
-use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use tokio::{
-    fs::{File, create_dir_all},
-    io::AsyncReadExt,
-};
-use which::which;
+        if self.provider.requires_git {
+            suggestions.push(PrerequisiteSuggestion {
+                condition: CONDITION_GIT.to_string(),
+                message: "Create a git repository for the project".to_string(),
+                fix_command: Some("git init".to_string()),
+            });
+        }
 
-use crate::{
-    error::ConfigError,
-    utils::{
-        AmarisConfigurationHandler, AmarisInstaller, AmarisPackageJsonHandler, AmarisPathHandler,
-    },
-};
+        suggestions
+    }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ConfigEntry {
-    pub file_location: String,
-    pub file_name: String,
-    pub source_from: String,
-}
+    async fn check_conflicts(&self, project: &ProjectContext) -> Result<(), ConfigError> {
+        let mut candidates = self.provider.conflicting_files.clone();
+        candidates.extend(AmarisConflictHandler::infer_conflicting_files(
+            &self.project_packages(),
+        ));
+        candidates.retain(|file| {
+            !self
+                .provider
+                .configuration
+                .iter()
+                .any(|config| &config.file_location == file)
+        });
+        candidates.sort();
+        candidates.dedup();
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ScriptEntry {
-    pub name: String,
-    pub script: String,
-}
+        let conflicts = AmarisConflictHandler::find_conflicts(project, &candidates);
+        if conflicts.is_empty() {
+            return Ok(());
+        }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct DynamicProvider {
-    pub name: String,
-    pub description: String,
-    pub package_manager: String,
-    pub packages: Vec<String>,
-    pub configuration: Vec<ConfigEntry>,
-    pub scripts: Vec<ScriptEntry>,
-}
+        let files: Vec<String> = conflicts
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
 
-impl DynamicProvider {
-    pub async fn load_all(dir: Option<&PathBuf>) -> Result<Vec<Self>, ConfigError> {
-        let dir = match dir {
-            Some(d) => d.clone(),
-            None => AmarisPathHandler::ensure_provider_dir().await?,
+        let mut tools: Vec<&str> = files
+            .iter()
+            .filter_map(|file| AmarisConflictHandler::owning_tool(file))
+            .collect();
+        tools.sort();
+        tools.dedup();
+
+        let suggestion = if tools.is_empty() {
+            "remove or migrate them first".to_string()
+        } else {
+            format!(
+                "remove or migrate the existing {} configuration first",
+                tools.join("/")
+            )
         };
 
-        let mut providers = vec![];
-        let mut entries = tokio::fs::read_dir(&dir).await?;
+        Err(ConfigError::ConflictError(format!(
+            "{} found, {} installs a competing tool; {}",
+            files.join(", "),
+            self.name,
+            suggestion
+        )))
+    }
 
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
-                let provider = tokio::fs::read(path).await?;
-                let provider: DynamicProvider = serde_json::from_slice(&provider)?;
-                providers.push(provider);
+    async fn verify(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<Vec<String>, ConfigError> {
+        let (configurations, variables) = self.resolve_configurations(project, options).await?;
+
+        AmarisConfigurationHandler::verify_configs(
+            project,
+            &self.name,
+            &configurations,
+            &variables,
+            options.workspace_file.as_deref(),
+        )
+        .await
+    }
+
+    async fn diff(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<ProviderDiff, ConfigError> {
+        let (configurations, variables) = self.resolve_configurations(project, options).await?;
+
+        Ok(ProviderDiff {
+            files: AmarisConfigurationHandler::diff_configs(
+                project,
+                &self.name,
+                &configurations,
+                &variables,
+                options.workspace_file.as_deref(),
+            )
+            .await?,
+            scripts: AmarisPackageJsonHandler::diff_scripts(
+                project,
+                &self.applicable_scripts(project).await,
+            )
+            .await?,
+        })
+    }
+
+    fn packages(&self) -> Vec<String> {
+        self.provider
+            .packages
+            .iter()
+            .map(|package| package.name.clone())
+            .collect()
+    }
+
+    fn package_entries(&self) -> Vec<PackageEntry> {
+        self.provider.packages.clone()
+    }
+
+    fn config_files(&self) -> Vec<ConfigEntry> {
+        self.provider.configuration.clone()
+    }
+
+    fn scripts(&self) -> Vec<ScriptEntry> {
+        self.provider.scripts.clone()
+    }
+
+    fn lineage(&self) -> Vec<String> {
+        self.provider.lineage.clone()
+    }
+
+    fn aliases(&self) -> Vec<String> {
+        self.provider.aliases.clone()
+    }
+
+    fn dependencies(&self) -> Vec<String> {
+        self.provider.depends_on.clone()
+    }
+
+    fn variables(&self) -> HashMap<String, String> {
+        self.provider.variables.clone()
+    }
+
+    fn environment(&self) -> HashMap<String, String> {
+        self.provider.environment.clone()
+    }
+
+    fn metadata(&self) -> ProviderMetadata {
+        self.provider.metadata()
+    }
+
+    /// Options replace the default of the variable of the same name.
+    fn set_option(&mut self, key: &str, value: Value) -> Result<(), ConfigError> {
+        if !self.provider.variables.contains_key(key) {
+            return Err(ConfigError::ValidationError(format!(
+                "{} has no variable '{}' to set",
+                self.name, key
+            )));
+        }
+
+        let value = option_value(&self.name, key, &value)?;
+        self.provider.variables.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn detect(&self, project: &ProjectContext) -> Result<DetectionResult, ConfigError> {
+        let files: Vec<String> = self
+            .provider
+            .configuration
+            .iter()
+            .filter(|config| config.applies(project))
+            // Shared settings files exist in projects regardless of the provider
+            .filter(|config| VsCodeFile::from_path(Path::new(&config.file_location)).is_none())
+            .map(|config| config.file_location.clone())
+            .collect();
+
+        Ok(DetectionResult::from_project(
+            project,
+            &self.project_packages(),
+            &files,
+        ))
+    }
+
+    fn variants(&self) -> Vec<ProviderVariant> {
+        self.provider.variants.clone()
+    }
+
+    async fn detect_variant(
+        &self,
+        project: &ProjectContext,
+    ) -> Result<Option<String>, ConfigError> {
+        for variant in &self.provider.variants {
+            let detect: Vec<&str> = variant.detect.iter().map(String::as_str).collect();
+
+            if !detect.is_empty()
+                && AmarisPackageJsonHandler::check_if_any_dependency_exists(project, &detect)
+                    .await?
+            {
+                return Ok(Some(variant.name.clone()));
             }
         }
 
-        Ok(providers)
+        Ok(None)
     }
-}
 
-#[async_trait]
-pub trait AmarisProvider: Send + Sync {
-    fn name(&self) -> &'static str;
-    fn description(&self) -> &'static str;
-    async fn check_prerequisites(&self) -> Result<(), ConfigError>;
-    async fn install(&self) -> Result<(), ConfigError>;
-    async fn remove(&self) -> Result<(), ConfigError>;
-}
+    fn with_variant(&self, variant: &str) -> Result<Box<dyn AmarisProvider>, ConfigError> {
+        let provider = self.provider.with_variant(variant)?;
 
-struct DynamicProviderImpl {
-    name: String,
-    description: String,
-    provider: DynamicProvider,
-}
+        Ok(provider.into())
+    }
 
-#[async_trait]
-impl AmarisProvider for DynamicProviderImpl {
-    fn name(&self) -> &'static str {
-        Box::leak(self.name.clone().into_boxed_str())
+    fn managed_files(&self, project: &ProjectContext) -> Vec<PathBuf> {
+        self.provider
+            .configuration
+            .iter()
+            .filter(|config| config.applies(project))
+            .map(|config| AmarisPathHandler::normalize(&config.file_location))
+            .collect()
     }
 
-    fn description(&self) -> &'static str {
-        Box::leak(self.description.clone().into_boxed_str())
+    fn planned_writes(
+        &self,
+        project: &ProjectContext,
+        workspace_file: Option<&Path>,
+    ) -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(PACKAGE_JSON_FILE_NAME)];
+        if self.provider.node_version.is_some() {
+            paths.push(PathBuf::from(NVMRC_FILE_NAME));
+        }
+        if !self.provider.gitignore.is_empty() {
+            paths.push(PathBuf::from(GITIGNORE_FILE_NAME));
+        }
+        paths.extend(self.provider.editor_settings.keys().map(|editor| {
+            match (editor, workspace_file) {
+                (Editor::VsCode, Some(path)) => project.relative(path),
+                _ => editor.settings_path(),
+            }
+        }));
+        paths.extend(self.managed_files(project));
+        paths
     }
 
-    async fn check_prerequisites(&self) -> Result<(), ConfigError> {
-        which(&self.provider.package_manager).map_err(|_| {
-            ConfigError::MissingPrerequisite("Package manager not found".to_string())
-        })?;
+    async fn install(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+        let (configurations, variables) = self.resolve_configurations(project, options).await?;
+        tracing::debug!(
+            provider = %self.name,
+            configurations = configurations.len(),
+            phases = ?options.phases,
+            "resolved configurations"
+        );
+        self.preflight(project, &configurations, &variables, options)
+            .await?;
+        tracing::debug!(provider = %self.name, "preflight passed");
 
-        if !AmarisPackageJsonHandler::get_default_path().exists() {
-            return Err(ConfigError::MissingPrerequisite(
-                "package.json not found!".to_string(),
-            ));
+        if let Some(version) = &self.provider.node_version {
+            AmarisNodeVersionHandler::ensure_minimum(project, version).await?;
+        }
+
+        if options.applies(InstallPhase::Packages) {
+            reporter.info("Installing packages...");
+            AmarisEvents::emit(project, InstallEvent::Phase("packages".to_string()));
+            let missing = self.missing_packages(project).await?;
+            for package in &self.provider.packages {
+                if !missing.contains(package) {
+                    reporter.warn(format!("Skipping {}, already installed", package.name));
+                }
+            }
+            tracing::debug!(
+                provider = %self.name,
+                packages = ?missing.iter().map(|package| &package.name).collect::<Vec<_>>(),
+                "installing packages"
+            );
+            self.install_packages(project, &missing, options).await?;
+        }
+
+        if options.applies(InstallPhase::Configs) {
+            reporter.info("Writing configurations...");
+            AmarisEvents::emit(project, InstallEvent::Phase("configurations".to_string()));
+            tracing::debug!(
+                provider = %self.name,
+                files = ?configurations.iter().map(|config| &config.file_location).collect::<Vec<_>>(),
+                "writing configurations"
+            );
+            AmarisConfigurationHandler::write_configs(
+                project,
+                self.name.clone(),
+                &configurations,
+                &variables,
+                options.workspace_file.as_deref(),
+                options.backups,
+            )
+            .await?;
+            self.apply_editor_settings(project, options).await?;
+            self.write_gitignore(project).await?;
+        }
+
+        if options.applies(InstallPhase::Scripts) {
+            reporter.info("Writing scripts...");
+            AmarisEvents::emit(project, InstallEvent::Phase("scripts".to_string()));
+            let scripts = self.applicable_scripts(project).await;
+            tracing::debug!(
+                provider = %self.name,
+                scripts = ?scripts.iter().map(|script| &script.name).collect::<Vec<_>>(),
+                "writing scripts"
+            );
+            AmarisPackageJsonHandler::write_scripts(project, &scripts).await?;
         }
 
+        reporter.success("Done!");
+
         Ok(())
     }
 
-    async fn install(&self) -> Result<(), ConfigError> {
-        let configurations = &self.provider.configuration;
+    async fn upgrade(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+        let (configurations, variables) = self.resolve_configurations(project, options).await?;
 
-        println!("Installing packages...");
-        AmarisInstaller::install(&self.provider.package_manager, &self.provider.packages).await?;
+        if options.applies(InstallPhase::Packages) {
+            reporter.info("Installing missing packages...");
+            let missing = self.missing_packages(project).await?;
+            self.install_packages(project, &missing, options).await?;
+        }
 
-        println!("Writing configurations...");
-        AmarisConfigurationHandler::write_configs(self.name.clone(), configurations).await?;
+        if options.applies(InstallPhase::Configs) {
+            reporter.info("Upgrading configurations...");
+            AmarisConfigurationHandler::upgrade_configs(
+                project,
+                self.name.clone(),
+                &configurations,
+                &variables,
+                options.conflict_resolution,
+                options.backups,
+            )
+            .await?;
+            self.apply_editor_settings(project, options).await?;
+            self.write_gitignore(project).await?;
+        }
 
-        println!("Writing scripts...");
-        AmarisPackageJsonHandler::write_scripts(&self.provider.scripts).await?;
+        if options.applies(InstallPhase::Scripts) {
+            reporter.info("Writing scripts...");
+            AmarisPackageJsonHandler::write_scripts(
+                project,
+                &self.applicable_scripts(project).await,
+            )
+            .await?;
+        }
 
-        println!("Done!");
+        reporter.success("Done!");
 
         Ok(())
     }
 
-    async fn remove(&self) -> Result<(), ConfigError> {
-        let configurations = &self.provider.configuration;
+    async fn remove(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+        let configurations: Vec<ConfigEntry> = self
+            .provider
+            .configuration
+            .iter()
+            .filter(|config| match config.module {
+                None => true,
+                Some(_) if options.installed_files.is_empty() => config.applies(project),
+                Some(_) => options.installed_files.contains(
+                    &AmarisPathHandler::normalize(&config.file_location)
+                        .to_string_lossy()
+                        .to_string(),
+                ),
+            })
+            .cloned()
+            .collect();
+
+        if options.applies(InstallPhase::Packages) {
+            reporter.info("Removing packages...");
+            self.remove_packages(project, options).await?;
+        }
 
-        println!("Removing packages...");
-        AmarisInstaller::remove(&self.provider.package_manager, &self.provider.packages).await?;
+        if options.applies(InstallPhase::Configs) {
+            reporter.info("Removing configurations...");
+            AmarisConfigurationHandler::remove_configs(
+                project,
+                &self.name,
+                &configurations,
+                options.workspace_file.as_deref(),
+            )
+            .await?;
+            AmarisConfigurationHandler::remove_gitignore_entries(project, &self.name).await?;
+            for (editor, settings) in &self.provider.editor_settings {
+                AmarisEditorHandler::remove_settings(
+                    project,
+                    *editor,
+                    settings,
+                    options.workspace_file.as_deref(),
+                )
+                .await?;
+            }
+            let owned: Vec<&str> = self
+                .provider
+                .owned_settings
+                .iter()
+                .map(String::as_str)
+                .collect();
+            crate::utils::AmarisVisualStudioCodeHandler::update_vscode_settings_remove_keys(
+                project,
+                options.workspace_file.as_deref(),
+                &owned,
+            )
+            .await?;
+        }
 
-        println!("Removing configurations...");
-        AmarisConfigurationHandler::remove_configs(configurations).await?;
+        if options.applies(InstallPhase::Scripts) {
+            reporter.info("Removing scripts...");
+            let scripts: Vec<ScriptEntry> = self
+                .provider
+                .scripts
+                .iter()
+                .filter(|script| script.remove_on_uninstall)
+                .map(|script| ScriptEntry {
+                    original_content: script
+                        .original_content
+                        .clone()
+                        .or_else(|| options.original_scripts.get(&script.name).cloned()),
+                    ..script.clone()
+                })
+                .collect();
+            AmarisPackageJsonHandler::remove_scripts(project, &scripts).await?;
+            AmarisPackageJsonHandler::sync_scripts(project, &self.name, &scripts).await?;
+        }
 
-        println!("Removing scripts...");
-        AmarisPackageJsonHandler::remove_scripts(&self.provider.scripts).await?;
+        AmarisBaseStore::clear(project, &self.name).await?;
 
-        println!("Done!");
+        reporter.success("Done!");
 
         Ok(())
     }
 }
 
+/// Depth-first search state of a provider in [`AmarisRegistry::resolve_install_order`].
+enum VisitMark {
+    /// On the current path, reaching it again means a cycle.
+    InProgress,
+    Done,
+}
+
+/// A provider of the registry and whether the current project has it installed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderStatus {
+    pub name: String,
+    pub description: String,
+    pub installed: bool,
+    pub installed_at: Option<DateTime<Utc>>,
+    /// Version of the provider definition.
+    pub version: Option<String>,
+    /// Version of the definition the installed configuration was generated from.
+    pub installed_version: Option<String>,
+}
+
+/// What `amaya info --json` prints about a provider.
+#[derive(Serialize, Debug, Clone)]
+pub struct ProviderInfo {
+    pub name: String,
+    pub description: String,
+    pub packages: Vec<String>,
+    pub config_files: Vec<ConfigFileInfo>,
+    pub scripts: Vec<ScriptEntry>,
+    /// Whether the current project has the provider installed.
+    pub installed: bool,
+    /// Version of the provider definition.
+    pub version: Option<String>,
+}
+
+/// A configuration file of a provider, `source` is relative to its
+/// configuration directory and `destination` to the project.
+#[derive(Serialize, Debug, Clone)]
+pub struct ConfigFileInfo {
+    pub source: String,
+    pub destination: String,
+}
+
+impl ProviderInfo {
+    pub fn new(provider: &dyn AmarisProvider, state: &AmarisProjectState) -> Self {
+        Self {
+            name: provider.name().to_string(),
+            description: provider.description().to_string(),
+            packages: provider.packages(),
+            config_files: provider
+                .config_files()
+                .into_iter()
+                .map(|config| ConfigFileInfo {
+                    source: config.source_from,
+                    destination: config.file_location,
+                })
+                .collect(),
+            scripts: provider.scripts(),
+            installed: state.is_installed(provider.name()),
+            version: provider.metadata().version,
+        }
+    }
+}
+
+#[derive(Default)]
 pub struct AmarisRegistry {
     providers: HashMap<String, Box<dyn AmarisProvider>>,
+    /// Current provider name keyed by alias.
+    aliases: HashMap<String, String>,
 }
 
 impl AmarisRegistry {
     pub fn new() -> Self {
         Self {
             providers: HashMap::new(),
+            aliases: HashMap::new(),
         }
     }
 
-    pub fn register(&mut self, provider: DynamicProvider) {
-        let dynamic_provider = DynamicProviderImpl {
-            name: provider.name.clone(),
-            description: provider.description.clone(),
-            provider,
-        };
-        self.providers.insert(
-            dynamic_provider.name.clone(),
-            Box::new(dynamic_provider) as Box<dyn AmarisProvider>,
-        );
+    /// Adds `provider`. A user-defined provider replaces a built-in one of
+    /// the same name, whichever is registered first.
+    pub fn register(&mut self, provider: Box<dyn AmarisProvider>) {
+        if let Some(existing) = self.providers.get(provider.name()) {
+            match (existing.is_builtin(), provider.is_builtin()) {
+                (false, true) => return,
+                (true, false) => Reporter::global().warn(format!(
+                    "Warning: provider '{}' replaces the built-in provider of the same name",
+                    provider.name()
+                )),
+                _ => {}
+            }
+        }
+
+        if let Some(owner) = self.aliases.remove(provider.name()) {
+            Reporter::global().warn(format!(
+                "Warning: provider '{}' takes the name of an alias of '{}', the alias is ignored",
+                provider.name(),
+                owner
+            ));
+        }
+
+        for alias in provider.aliases() {
+            let owner = match self.aliases.get(&alias) {
+                Some(owner) if owner != provider.name() => Some(owner.clone()),
+                _ => self.providers.contains_key(&alias).then(|| alias.clone()),
+            };
+
+            match owner {
+                Some(owner) => Reporter::global().warn(format!(
+                    "Warning: alias '{}' of provider '{}' already refers to '{}', it is ignored",
+                    alias,
+                    provider.name(),
+                    owner
+                )),
+                None => {
+                    self.aliases.insert(alias, provider.name().to_string());
+                }
+            }
+        }
+
+        self.providers.insert(provider.name().to_string(), provider);
+    }
+
+    /// Current provider name keyed by alias, see [`DynamicProvider::aliases`].
+    pub fn aliases(&self) -> &HashMap<String, String> {
+        &self.aliases
+    }
+
+    /// Whether `name` is the name or an alias of a provider.
+    pub fn contains(&self, name: &str) -> bool {
+        self.providers.contains_key(name) || self.aliases.contains_key(name)
     }
 
     pub fn available_configs(&self) -> Vec<(&str, &str)> {
@@ -166,20 +2804,187 @@ impl AmarisRegistry {
             .collect()
     }
 
-    pub fn get_provider(&self, name: &str) -> Option<&Box<dyn AmarisProvider>> {
-        self.providers.get(name)
+    pub fn available_configs_sorted(&self) -> Vec<(&str, &str)> {
+        let mut configs = self.available_configs();
+        configs.sort_by_key(|(name, _)| *name);
+        configs
+    }
+
+    /// Every provider sorted by name, with its installation in `state`.
+    pub fn list_with_status(&self, state: &AmarisProjectState) -> Vec<ProviderStatus> {
+        let mut statuses: Vec<ProviderStatus> = self
+            .providers
+            .values()
+            .map(|provider| {
+                let record = state.get(provider.name());
+                ProviderStatus {
+                    name: provider.name().to_string(),
+                    description: provider.description().to_string(),
+                    installed: record.is_some(),
+                    installed_at: record.map(|record| record.installed_at),
+                    version: provider.metadata().version,
+                    installed_version: record.and_then(|record| record.version.clone()),
+                }
+            })
+            .collect();
+
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    /// The provider called `name`, or renamed from it, noting the new name.
+    pub fn get_provider(&self, name: &str) -> Option<&dyn AmarisProvider> {
+        if let Some(provider) = self.providers.get(name) {
+            return Some(provider.as_ref());
+        }
+
+        let current = self.aliases.get(name)?;
+        Reporter::global().warn(format!(
+            "Warning: provider '{}' was renamed to '{}', the old name will stop working once the alias is removed",
+            name, current
+        ));
+
+        self.providers.get(current).map(|p| p.as_ref())
+    }
+
+    /// The provider called `name`, or renamed from it, for changing its options.
+    pub fn get_provider_mut(&mut self, name: &str) -> Result<&mut dyn AmarisProvider, ConfigError> {
+        let name = match self.providers.contains_key(name) {
+            true => name.to_string(),
+            false => {
+                let current = self.aliases.get(name).ok_or_else(|| {
+                    ConfigError::ValidationError(format!("Unknown provider '{}'", name))
+                })?;
+                Reporter::global().warn(format!(
+                    "Warning: provider '{}' was renamed to '{}', the old name will stop working once the alias is removed",
+                    name, current
+                ));
+                current.clone()
+            }
+        };
+
+        self.providers
+            .get_mut(&name)
+            .map(|provider| provider.as_mut() as &mut dyn AmarisProvider)
+            .ok_or_else(|| ConfigError::ValidationError(format!("Unknown provider '{}'", name)))
+    }
+
+    /// Sets the options of config.toml on the providers they name, warning
+    /// about those that do not apply.
+    pub fn apply_options(&mut self, options: &HashMap<String, BTreeMap<String, Value>>) {
+        let reporter = Reporter::global();
+
+        for (name, provider_options) in options {
+            let provider = match self.get_provider_mut(name) {
+                Ok(provider) => provider,
+                Err(e) => {
+                    reporter.warn(format!("Warning: skipping options of '{}', {}", name, e));
+                    continue;
+                }
+            };
+
+            for (key, value) in provider_options {
+                if let Err(e) = provider.set_option(key, value.clone()) {
+                    reporter.warn(format!("Warning: skipping option, {}", e));
+                }
+            }
+        }
+    }
+
+    /// Orders `names` and everything they depend on so that dependencies come
+    /// before their dependents, keeping the given order otherwise.
+    pub fn resolve_install_order(&self, names: &[String]) -> Result<Vec<String>, ConfigError> {
+        let mut marks: HashMap<String, VisitMark> = HashMap::new();
+        let mut stack: Vec<String> = vec![];
+        let mut order: Vec<String> = vec![];
+
+        for name in names {
+            self.visit(name, &mut marks, &mut stack, &mut order)?;
+        }
+
+        Ok(order)
+    }
+
+    /// Splits `order`, as returned by [`Self::resolve_install_order`], into
+    /// levels whose providers only depend on providers of earlier levels.
+    /// Dependencies outside of `order` are taken as installed.
+    pub fn install_levels(&self, order: &[String]) -> Vec<Vec<String>> {
+        let mut depths: HashMap<&str, usize> = HashMap::new();
+        let mut levels: Vec<Vec<String>> = vec![];
+
+        for name in order {
+            let dependencies = self
+                .get_provider(name)
+                .map(|provider| provider.dependencies())
+                .unwrap_or_default();
+            let depth = dependencies
+                .iter()
+                .filter_map(|dependency| depths.get(dependency.as_str()))
+                .map(|depth| depth + 1)
+                .max()
+                .unwrap_or(0);
+
+            depths.insert(name, depth);
+            if levels.len() <= depth {
+                levels.resize(depth + 1, vec![]);
+            }
+            levels[depth].push(name.clone());
+        }
+
+        levels
+    }
+
+    fn visit(
+        &self,
+        name: &str,
+        marks: &mut HashMap<String, VisitMark>,
+        stack: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), ConfigError> {
+        match marks.get(name) {
+            Some(VisitMark::Done) => return Ok(()),
+            Some(VisitMark::InProgress) => {
+                let start = stack.iter().position(|entry| entry == name).unwrap_or(0);
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(name.to_string());
+                return Err(ConfigError::CircularDependency(cycle.join(" → ")));
+            }
+            None => {}
+        }
+
+        let Some(provider) = self.get_provider(name) else {
+            return Err(ConfigError::ValidationError(match stack.last() {
+                Some(dependent) => {
+                    format!("{} depends on unknown configuration '{}'", dependent, name)
+                }
+                None => format!("Unknown configuration '{}'", name),
+            }));
+        };
+
+        marks.insert(name.to_string(), VisitMark::InProgress);
+        stack.push(name.to_string());
+
+        for dependency in provider.dependencies() {
+            self.visit(&dependency, marks, stack, order)?;
+        }
+
+        stack.pop();
+        marks.insert(name.to_string(), VisitMark::Done);
+        order.push(name.to_string());
+
+        Ok(())
     }
 }
 
 pub struct AmarisVisualStudioCodeHandler;
 
 impl AmarisVisualStudioCodeHandler {
-    pub fn get_default_path() -> PathBuf {
-        PathBuf::from(".vscode/settings.json")
+    pub fn get_default_path(project: &ProjectContext) -> PathBuf {
+        project.path(".vscode/settings.json")
     }
 
-    pub async fn read() -> Result<Value, ConfigError> {
-        let settings_path = AmarisVisualStudioCodeHandler::get_default_path();
+    pub async fn read(project: &ProjectContext) -> Result<Value, ConfigError> {
+        let settings_path = AmarisVisualStudioCodeHandler::get_default_path(project);
 
         if !settings_path.exists() {
             create_dir_all(settings_path.parent().unwrap())
@@ -204,3 +3009,249 @@ impl AmarisVisualStudioCodeHandler {
         serde_json::from_str(&contents).map_err(|e| ConfigError::ValidationError(e.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A registry of providers depending on each other, `(name, depends_on)`.
+    fn registry(providers: &[(&str, &[&str])]) -> AmarisRegistry {
+        let mut registry = AmarisRegistry::new();
+        for (name, depends_on) in providers {
+            registry.register(
+                DynamicProvider {
+                    name: name.to_string(),
+                    package_manager: "bun".to_string(),
+                    depends_on: depends_on.iter().map(|name| name.to_string()).collect(),
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+        registry
+    }
+
+    fn install_order(
+        registry: &AmarisRegistry,
+        names: &[&str],
+    ) -> Result<Vec<String>, ConfigError> {
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        registry.resolve_install_order(&names)
+    }
+
+    fn assert_cycle(result: Result<Vec<String>, ConfigError>, expected: &str) {
+        match result {
+            Err(ConfigError::CircularDependency(cycle)) => assert_eq!(cycle, expected),
+            result => panic!("expected a circular dependency, got {:?}", result),
+        }
+    }
+
+    const PROVIDER_JSON: &str = r#"{
+  "name": "lint",
+  "description": "Linting with oxlint",
+  "version": "1.2.0",
+  "package_manager": "bun",
+  "packages": [
+    { "name": "oxlint", "scope": "dev" },
+    { "name": "lint-cli", "scope": "global" }
+  ],
+  "configuration": [
+    {
+      "file_name": "oxlintrc",
+      "file_location": ".oxlintrc.json",
+      "source_from": "oxlintrc.json",
+      "merge": "merge",
+      "platform": ["linux", "macos"]
+    }
+  ],
+  "scripts": [{ "name": "lint", "script": "oxlint ." }],
+  "variables": { "max_warnings": "0" },
+  "depends_on": ["format"],
+  "editor_settings": {
+    "vscode": { "oxc.enable": true, "editor.tabSize": 2 }
+  },
+  "requires_git": true
+}"#;
+
+    const PROVIDER_TOML: &str = r#"
+name = "lint"
+description = "Linting with oxlint"
+version = "1.2.0"
+package_manager = "bun"
+depends_on = ["format"]
+requires_git = true
+
+[[packages]]
+name = "oxlint"
+scope = "dev"
+
+[[packages]]
+name = "lint-cli"
+scope = "global"
+
+[[configuration]]
+file_name = "oxlintrc"
+file_location = ".oxlintrc.json"
+source_from = "oxlintrc.json"
+merge = "merge"
+platform = ["linux", "macos"]
+
+[[scripts]]
+name = "lint"
+script = "oxlint ."
+
+[variables]
+max_warnings = "0"
+
+[editor_settings.vscode]
+"oxc.enable" = true
+"editor.tabSize" = 2
+"#;
+
+    const PROVIDER_YAML: &str = r#"
+name: lint
+description: Linting with oxlint
+version: 1.2.0
+package_manager: bun
+packages:
+  - name: oxlint
+    scope: dev
+  - name: lint-cli
+    scope: global
+configuration:
+  - file_name: oxlintrc
+    file_location: .oxlintrc.json
+    source_from: oxlintrc.json
+    merge: merge
+    platform: [linux, macos]
+scripts:
+  - name: lint
+    script: oxlint .
+variables:
+  max_warnings: "0"
+depends_on: [format]
+editor_settings:
+  vscode:
+    oxc.enable: true
+    editor.tabSize: 2
+requires_git: true
+"#;
+
+    #[test]
+    fn reads_the_same_provider_from_every_format() {
+        let json = DynamicProvider::from_content(PROVIDER_JSON, Path::new("lint.json")).unwrap();
+        let toml = DynamicProvider::from_content(PROVIDER_TOML, Path::new("lint.toml")).unwrap();
+        let yaml = DynamicProvider::from_content(PROVIDER_YAML, Path::new("lint.yaml")).unwrap();
+        let yml = DynamicProvider::from_content(PROVIDER_YAML, Path::new("lint.yml")).unwrap();
+
+        assert_eq!(json.packages[1].scope, PackageScope::Global);
+        assert_eq!(json.configuration[0].merge, Some(MergeStrategy::Merge));
+        assert_eq!(toml, json);
+        assert_eq!(yaml, json);
+        assert_eq!(yml, json);
+    }
+
+    #[test]
+    fn reports_the_syntax_errors_of_each_format() {
+        for (content, path) in [
+            ("{ \"name\": ", "lint.json"),
+            ("name = ", "lint.toml"),
+            ("name: [", "lint.yaml"),
+        ] {
+            assert!(
+                matches!(
+                    DynamicProvider::from_content(content, Path::new(path)),
+                    Err(ConfigError::ValidationError(_))
+                ),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn reads_its_own_sources_from_a_directory() {
+        let entry = |source_from: &str| ConfigEntry {
+            source_from: source_from.to_string(),
+            ..Default::default()
+        };
+        let mut provider = DynamicProvider {
+            configuration: vec![
+                entry("lint.json"),
+                ConfigEntry {
+                    source_provider: Some("base".to_string()),
+                    ..entry("tsconfig.json")
+                },
+            ],
+            variants: vec![ProviderVariant {
+                name: "strict".to_string(),
+                configuration: vec![entry("strict.json")],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        provider.read_sources_from(Path::new("/work/providers"));
+
+        let dir = Some(PathBuf::from("/work/providers"));
+        assert_eq!(provider.configuration[0].source_dir, dir);
+        assert_eq!(provider.configuration[1].source_dir, None);
+        assert_eq!(provider.variants[0].configuration[0].source_dir, dir);
+    }
+
+    #[test]
+    fn plans_vscode_settings_for_the_workspace_file() {
+        let project = ProjectContext {
+            root: PathBuf::from("/work/app"),
+            ..Default::default()
+        };
+        let provider = DynamicProviderImpl::from(
+            DynamicProvider::from_content(PROVIDER_JSON, Path::new("lint.json")).unwrap(),
+        );
+
+        let writes = provider.planned_writes(&project, None);
+        assert!(writes.contains(&Editor::VsCode.settings_path()));
+
+        let workspace = project.path("app.code-workspace");
+        let writes = provider.planned_writes(&project, Some(&workspace));
+        assert!(writes.contains(&PathBuf::from("app.code-workspace")));
+        assert!(!writes.contains(&Editor::VsCode.settings_path()));
+    }
+
+    #[test]
+    fn orders_dependencies_first() {
+        let registry = registry(&[("lint", &["format"]), ("format", &[]), ("hooks", &["lint"])]);
+
+        assert_eq!(
+            install_order(&registry, &["hooks", "format"]).unwrap(),
+            ["format", "lint", "hooks"]
+        );
+    }
+
+    #[test]
+    fn reports_a_cycle_of_two_providers() {
+        let registry = registry(&[("a", &["b"]), ("b", &["a"])]);
+
+        assert_cycle(install_order(&registry, &["a"]), "a → b → a");
+        assert_cycle(install_order(&registry, &["b"]), "b → a → b");
+    }
+
+    #[test]
+    fn reports_a_cycle_of_three_providers() {
+        let registry = registry(&[
+            ("entry", &["a"]),
+            ("a", &["b"]),
+            ("b", &["c"]),
+            ("c", &["a"]),
+        ]);
+
+        assert_cycle(install_order(&registry, &["entry"]), "a → b → c → a");
+    }
+
+    #[test]
+    fn reports_a_provider_depending_on_itself() {
+        let registry = registry(&[("a", &["a"])]);
+
+        assert_cycle(install_order(&registry, &["a"]), "a → a");
+    }
+}
@@ -0,0 +1,12 @@
+pub mod tailwind;
+pub mod wireit;
+
+use crate::provider::AmarisProvider;
+
+/// Providers compiled into amaya. A dynamic provider with the same name takes precedence.
+pub fn builtin_providers() -> Vec<Box<dyn AmarisProvider>> {
+    vec![
+        Box::<tailwind::TailwindProvider>::default(),
+        Box::<wireit::WireitProvider>::default(),
+    ]
+}
@@ -0,0 +1,665 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{
+    error::ConfigError,
+    merge::merge_three_way,
+    package_manager::{AmarisPackageManager, InstallContext, ResolvedPackageManager},
+    provider::{
+        AmarisProvider, CONDITION_PACKAGE_JSON, CONDITION_PACKAGE_MANAGER, ConfigEntry,
+        ConfigFormat, DetectionResult, InstallOptions, InstallPhase, ModuleSystem, PackageScope,
+        PrerequisiteSuggestion, ProjectContext, option_value,
+    },
+    reporter::Reporter,
+    state::AmarisBaseStore,
+    summary::{AmarisEvents, InstallEvent},
+    utils::{
+        AmarisFileHandler, AmarisInstaller, AmarisPackageJsonHandler,
+        AmarisVisualStudioCodeHandler, Indent, PACKAGE_JSON_FILE_NAME, format_json,
+        strip_json_comments,
+    },
+};
+
+const PROVIDER_NAME: &str = "tailwind";
+const PACKAGE_MANAGER: &str = "bun";
+const PACKAGES: &[&str] = &["tailwindcss"];
+/// Major version installed. The generated config, `@tailwind` directives and
+/// `tailwindcss` PostCSS plugin are Tailwind 3's, version 4 replaced them
+/// with `@import "tailwindcss"` and the `@tailwindcss/postcss` plugin.
+const TAILWIND_MAJOR: u64 = 3;
+const CONFIG_PATH: &str = "tailwind.config.ts";
+/// Variables whose default can be set with [`AmarisProvider::set_option`].
+const OPTIONS: &[&str] = &["content", "dark_mode"];
+const COMPETING_CONFIGS: &[&str] = &[
+    "tailwind.config.js",
+    "tailwind.config.cjs",
+    "tailwind.config.mjs",
+];
+const CSS_ENTRYPOINTS: &[&str] = &["src/styles/globals.css", "src/index.css", "app/globals.css"];
+const POSTCSS_CONFIGS: &[&str] = &[
+    "postcss.config.js",
+    "postcss.config.cjs",
+    "postcss.config.mjs",
+];
+const POSTCSS_JSON_CONFIG: &str = ".postcssrc.json";
+/// Written in the syntax of the project's module system when it has no PostCSS config.
+const POSTCSS_DEFAULT_CONFIG: &str = "postcss.config.js";
+const POSTCSS_PLUGIN_ENTRY: &str = "tailwindcss: {},";
+const DIRECTIVES: &str = "@tailwind base;\n@tailwind components;\n@tailwind utilities;\n";
+const INTELLISENSE_EXTENSION: &str = "bradlc.vscode-tailwindcss";
+
+/// How Tailwind decides when to apply `dark:` variants.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DarkMode {
+    /// Follow the operating system preference.
+    #[default]
+    Media,
+    /// Apply when a `dark` class is present on an ancestor.
+    Class,
+}
+
+impl DarkMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DarkMode::Media => "media",
+            DarkMode::Class => "class",
+        }
+    }
+}
+
+/// Framework of the project, used to pick sensible content globs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    Next,
+    Remix,
+    Astro,
+    Vite,
+    Other,
+}
+
+impl ProjectKind {
+    /// Detects the framework from the dependencies in package.json.
+    pub async fn detect(project: &ProjectContext) -> Result<Self, ConfigError> {
+        let kinds = [
+            (ProjectKind::Next, "next"),
+            (ProjectKind::Remix, "@remix-run/react"),
+            (ProjectKind::Astro, "astro"),
+            (ProjectKind::Vite, "vite"),
+        ];
+
+        for (kind, dependency) in kinds {
+            if AmarisPackageJsonHandler::check_if_any_dependency_exists(project, &[dependency])
+                .await?
+            {
+                return Ok(kind);
+            }
+        }
+
+        Ok(ProjectKind::Other)
+    }
+
+    pub fn content_paths(&self) -> Vec<String> {
+        let paths: &[&str] = match self {
+            ProjectKind::Next => &[
+                "./app/**/*.{js,ts,jsx,tsx,mdx}",
+                "./pages/**/*.{js,ts,jsx,tsx,mdx}",
+                "./components/**/*.{js,ts,jsx,tsx,mdx}",
+                "./src/**/*.{js,ts,jsx,tsx,mdx}",
+            ],
+            ProjectKind::Remix => &["./app/**/*.{js,ts,jsx,tsx}"],
+            ProjectKind::Astro => &["./src/**/*.{astro,html,js,jsx,md,mdx,ts,tsx}"],
+            ProjectKind::Vite => &["./index.html", "./src/**/*.{js,ts,jsx,tsx,vue,svelte}"],
+            ProjectKind::Other => &["./src/**/*.{html,js,ts,jsx,tsx}"],
+        };
+
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TailwindOptions {
+    /// Globs Tailwind scans for class names.
+    pub content_paths: Vec<String>,
+    pub dark_mode: DarkMode,
+}
+
+impl TailwindOptions {
+    /// Reads the `content` (comma separated globs) and `dark_mode` variables,
+    /// falling back to the content globs suggested for the project type.
+    pub fn from_variables(
+        variables: &HashMap<String, String>,
+        project: ProjectKind,
+    ) -> Result<Self, ConfigError> {
+        let content_paths = match variables.get("content") {
+            Some(content) => content
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(String::from)
+                .collect(),
+            None => project.content_paths(),
+        };
+
+        let dark_mode = match variables.get("dark_mode").map(String::as_str) {
+            None | Some("media") => DarkMode::Media,
+            Some("class") => DarkMode::Class,
+            Some(other) => {
+                return Err(ConfigError::ValidationError(format!(
+                    "Unknown dark_mode '{}', expected media or class",
+                    other
+                )));
+            }
+        };
+
+        Ok(Self {
+            content_paths,
+            dark_mode,
+        })
+    }
+}
+
+/// Built-in provider for Tailwind CSS. Unlike dynamic providers, its
+/// configuration is generated from the project instead of copied from a source.
+#[derive(Default)]
+pub struct TailwindProvider {
+    /// Defaults of the `content` and `dark_mode` variables from config.toml.
+    defaults: HashMap<String, String>,
+}
+
+impl TailwindProvider {
+    async fn resolve_options(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<TailwindOptions, ConfigError> {
+        let kind = ProjectKind::detect(project).await?;
+        Reporter::global().info(format!("Detected project type: {:?}", kind));
+
+        let mut variables = self.defaults.clone();
+        variables.extend(options.variables.clone());
+        TailwindOptions::from_variables(&variables, kind)
+    }
+
+    fn render_config(options: &TailwindOptions) -> String {
+        let content: String = options
+            .content_paths
+            .iter()
+            .map(|path| format!("        \"{}\",\n", path))
+            .collect();
+
+        format!(
+            "import type {{ Config }} from \"tailwindcss\";\n\
+             \n\
+             const config: Config = {{\n    \
+                 darkMode: \"{}\",\n    \
+                 content: [\n{}    ],\n    \
+                 theme: {{\n        \
+                     extend: {{}},\n    \
+                 }},\n    \
+                 plugins: [],\n\
+             }};\n\
+             \n\
+             export default config;\n",
+            options.dark_mode.as_str(),
+            content
+        )
+    }
+
+    /// The project-relative path of the stylesheet the directives go in.
+    fn css_entrypoint(project: &ProjectContext) -> Option<PathBuf> {
+        CSS_ENTRYPOINTS
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| project.has_file(path))
+    }
+
+    /// The project-relative path of the PostCSS config, if there is one.
+    fn postcss_config(project: &ProjectContext) -> Option<PathBuf> {
+        POSTCSS_CONFIGS
+            .iter()
+            .chain([&POSTCSS_JSON_CONFIG])
+            .map(PathBuf::from)
+            .find(|path| project.has_file(path))
+    }
+
+    /// The package manager to run, the project's one when it pins or locks one.
+    async fn package_manager(
+        project: &ProjectContext,
+    ) -> Result<ResolvedPackageManager, ConfigError> {
+        AmarisPackageManager::global()
+            .resolve(project, PACKAGE_MANAGER)
+            .await
+    }
+
+    /// The packages to install, pinned to [`TAILWIND_MAJOR`].
+    async fn missing_packages(project: &ProjectContext) -> Result<Vec<String>, ConfigError> {
+        let detection = TailwindProvider::default()
+            .detect(&project.reload().await?)
+            .await?;
+
+        Ok(PACKAGES
+            .iter()
+            .filter(|package| !detection.packages.iter().any(|found| found == *package))
+            .map(|package| format!("{}@{}", package, TAILWIND_MAJOR))
+            .collect())
+    }
+
+    async fn add_directives(project: &ProjectContext) -> Result<(), ConfigError> {
+        let Some(path) = Self::css_entrypoint(project).map(|path| project.path(path)) else {
+            Reporter::global().warn(format!(
+                "Warning: no CSS entrypoint found in {}, add the @tailwind directives manually",
+                CSS_ENTRYPOINTS.join(", ")
+            ));
+            return Ok(());
+        };
+
+        let content = AmarisFileHandler::load_file(&path).await?;
+        if content.contains("@tailwind") {
+            return Ok(());
+        }
+
+        AmarisFileHandler::write_file(project, path, &format!("{}\n{}", DIRECTIVES, content)).await
+    }
+
+    async fn remove_directives(project: &ProjectContext) -> Result<(), ConfigError> {
+        let Some(path) = Self::css_entrypoint(project).map(|path| project.path(path)) else {
+            return Ok(());
+        };
+
+        let content = AmarisFileHandler::load_file(&path).await?;
+        if !content.contains(DIRECTIVES) {
+            return Ok(());
+        }
+
+        let content = content.replacen(&format!("{}\n", DIRECTIVES), "", 1);
+        let content = content.replacen(DIRECTIVES, "", 1);
+
+        AmarisFileHandler::write_file(project, path, &content).await
+    }
+
+    /// Registers Tailwind as a PostCSS plugin when the project uses PostCSS.
+    async fn add_postcss_plugin(project: &ProjectContext) -> Result<(), ConfigError> {
+        let Some(relative) = Self::postcss_config(project) else {
+            if AmarisPackageJsonHandler::check_if_any_dependency_exists(project, &["postcss"])
+                .await?
+            {
+                let export = match ModuleSystem::detect(project) {
+                    ModuleSystem::Esm => "export default",
+                    ModuleSystem::Cjs => "module.exports =",
+                };
+                let content = format!(
+                    "{} {{\n    plugins: {{\n        {}\n    }},\n}};\n",
+                    export, POSTCSS_PLUGIN_ENTRY
+                );
+                AmarisFileHandler::write_file(
+                    project,
+                    project.path(POSTCSS_DEFAULT_CONFIG),
+                    &content,
+                )
+                .await?;
+            }
+            return Ok(());
+        };
+
+        let path = project.path(&relative);
+        let content = AmarisFileHandler::load_file(&path).await?;
+        if content.contains("tailwindcss") {
+            return Ok(());
+        }
+
+        if relative.as_os_str() == POSTCSS_JSON_CONFIG {
+            let mut config: Value = serde_json::from_str(&strip_json_comments(&content))
+                .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))?;
+
+            if !config.get("plugins").is_some_and(Value::is_object) {
+                config["plugins"] = serde_json::json!({});
+            }
+            config["plugins"]["tailwindcss"] = serde_json::json!({});
+
+            return AmarisFileHandler::write_file(
+                project,
+                path,
+                &format_json(&config, Indent::detect(&content)),
+            )
+            .await;
+        }
+
+        let Some(index) = content.find("plugins: {") else {
+            Reporter::global().warn(format!(
+                "Warning: could not find the plugins object in {}, add tailwindcss manually",
+                relative.display()
+            ));
+            return Ok(());
+        };
+
+        let index = index + "plugins: {".len();
+        let content = format!(
+            "{}\n        {}{}",
+            &content[..index],
+            POSTCSS_PLUGIN_ENTRY,
+            &content[index..]
+        );
+
+        AmarisFileHandler::write_file(project, path, &content).await
+    }
+
+    async fn remove_postcss_plugin(project: &ProjectContext) -> Result<(), ConfigError> {
+        let Some(relative) = Self::postcss_config(project) else {
+            return Ok(());
+        };
+
+        let path = project.path(&relative);
+        let content = AmarisFileHandler::load_file(&path).await?;
+
+        if relative.as_os_str() == POSTCSS_JSON_CONFIG {
+            let mut config: Value = serde_json::from_str(&strip_json_comments(&content))
+                .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))?;
+
+            let Some(plugins) = config.get_mut("plugins").and_then(Value::as_object_mut) else {
+                return Ok(());
+            };
+            if plugins.remove("tailwindcss").is_none() {
+                return Ok(());
+            }
+
+            return AmarisFileHandler::write_file(
+                project,
+                path,
+                &format_json(&config, Indent::detect(&content)),
+            )
+            .await;
+        }
+
+        let lines: Vec<&str> = content
+            .lines()
+            .filter(|line| line.trim() != POSTCSS_PLUGIN_ENTRY)
+            .collect();
+
+        if lines.len() == content.lines().count() {
+            return Ok(());
+        }
+
+        AmarisFileHandler::write_file(project, path, &format!("{}\n", lines.join("\n"))).await
+    }
+
+    async fn write_editor_files(project: &ProjectContext) -> Result<(), ConfigError> {
+        Self::add_directives(project).await?;
+        Self::add_postcss_plugin(project).await?;
+        AmarisVisualStudioCodeHandler::add_vscode_extension_recommendation(
+            project,
+            INTELLISENSE_EXTENSION,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl AmarisProvider for TailwindProvider {
+    fn name(&self) -> &'static str {
+        PROVIDER_NAME
+    }
+
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    fn set_option(&mut self, key: &str, value: Value) -> Result<(), ConfigError> {
+        if !OPTIONS.contains(&key) {
+            return Err(ConfigError::ValidationError(format!(
+                "{} has no option '{}', expected one of {}",
+                PROVIDER_NAME,
+                key,
+                OPTIONS.join(", ")
+            )));
+        }
+
+        let value = option_value(PROVIDER_NAME, key, &value)?;
+        self.defaults.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn detect(&self, project: &ProjectContext) -> Result<DetectionResult, ConfigError> {
+        Ok(DetectionResult::from_project(
+            project,
+            &self.packages(),
+            &[CONFIG_PATH.to_string()],
+        ))
+    }
+
+    fn description(&self) -> &'static str {
+        "Tailwind CSS 3"
+    }
+
+    async fn check_prerequisites(&self, project: &ProjectContext) -> Result<(), ConfigError> {
+        let suggestions = self.prerequisite_suggestions();
+
+        if !AmarisPackageJsonHandler::get_default_path(project).exists() {
+            return Err(PrerequisiteSuggestion::apply(
+                &suggestions,
+                CONDITION_PACKAGE_JSON,
+                "package.json not found!".to_string(),
+            ));
+        }
+
+        let manager = Self::package_manager(project).await?;
+        AmarisPackageManager::warn_on_mismatch(PROVIDER_NAME, PACKAGE_MANAGER, &manager);
+
+        if !AmarisPackageManager::global().ensure_available(&manager)? {
+            return Err(PrerequisiteSuggestion::apply(
+                &PrerequisiteSuggestion::for_resolved_manager(&manager),
+                CONDITION_PACKAGE_MANAGER,
+                format!("Package manager '{}' not found", manager.name),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn prerequisite_suggestions(&self) -> Vec<PrerequisiteSuggestion> {
+        PrerequisiteSuggestion::for_package_manager(PACKAGE_MANAGER)
+    }
+
+    async fn check_conflicts(&self, project: &ProjectContext) -> Result<(), ConfigError> {
+        if let Some(version) =
+            AmarisPackageJsonHandler::get_exact_installed_version(project, PACKAGES[0]).await?
+            && version.major != TAILWIND_MAJOR
+        {
+            return Err(ConfigError::ConflictError(format!(
+                "tailwindcss {} is installed, {} sets up Tailwind CSS {}",
+                version, PROVIDER_NAME, TAILWIND_MAJOR
+            )));
+        }
+
+        let existing: Vec<&str> = COMPETING_CONFIGS
+            .iter()
+            .copied()
+            .filter(|file| project.has_file(file))
+            .collect();
+
+        if existing.is_empty() {
+            return Ok(());
+        }
+
+        Err(ConfigError::ConflictError(format!(
+            "{} found, {} writes {}; remove or migrate the existing Tailwind configuration first",
+            existing.join(", "),
+            PROVIDER_NAME,
+            CONFIG_PATH
+        )))
+    }
+
+    fn packages(&self) -> Vec<String> {
+        PACKAGES.iter().map(|package| package.to_string()).collect()
+    }
+
+    fn config_files(&self) -> Vec<ConfigEntry> {
+        vec![ConfigEntry {
+            file_location: CONFIG_PATH.to_string(),
+            file_name: CONFIG_PATH.to_string(),
+            ..Default::default()
+        }]
+    }
+
+    fn managed_files(&self, _project: &ProjectContext) -> Vec<PathBuf> {
+        vec![PathBuf::from(CONFIG_PATH)]
+    }
+
+    fn planned_writes(
+        &self,
+        project: &ProjectContext,
+        _workspace_file: Option<&Path>,
+    ) -> Vec<PathBuf> {
+        let mut paths = vec![
+            PathBuf::from(PACKAGE_JSON_FILE_NAME),
+            PathBuf::from(CONFIG_PATH),
+            project.relative(&AmarisVisualStudioCodeHandler::get_extensions_path(project)),
+        ];
+        paths.extend(Self::css_entrypoint(project));
+        paths.push(Self::postcss_config(project).unwrap_or(PathBuf::from(POSTCSS_DEFAULT_CONFIG)));
+        paths
+    }
+
+    async fn install(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+        let tailwind_options = self.resolve_options(project, options).await?;
+
+        if options.applies(InstallPhase::Packages) {
+            reporter.info("Installing packages...");
+            AmarisEvents::emit(project, InstallEvent::Phase("packages".to_string()));
+            let missing = Self::missing_packages(project).await?;
+            AmarisInstaller::install(
+                project,
+                &Self::package_manager(project).await?.name,
+                PackageScope::Dev,
+                &InstallContext::resolve(project, None, &HashMap::new(), &options.extra_env)
+                    .await?,
+                &missing,
+                options.timeout,
+            )
+            .await?;
+        }
+
+        if options.applies(InstallPhase::Configs) {
+            reporter.info("Writing configurations...");
+            AmarisEvents::emit(project, InstallEvent::Phase("configurations".to_string()));
+            let config = Self::render_config(&tailwind_options);
+            AmarisFileHandler::write_file(project, project.path(CONFIG_PATH), &config).await?;
+            AmarisBaseStore::write(project, PROVIDER_NAME, CONFIG_PATH, &config).await?;
+
+            Self::write_editor_files(project).await?;
+        }
+
+        reporter.success("Done!");
+
+        Ok(())
+    }
+
+    async fn upgrade(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+        let tailwind_options = self.resolve_options(project, options).await?;
+
+        if options.applies(InstallPhase::Packages) {
+            reporter.info("Installing missing packages...");
+            let missing = Self::missing_packages(project).await?;
+            AmarisInstaller::install(
+                project,
+                &Self::package_manager(project).await?.name,
+                PackageScope::Dev,
+                &InstallContext::resolve(project, None, &HashMap::new(), &options.extra_env)
+                    .await?,
+                &missing,
+                options.timeout,
+            )
+            .await?;
+        }
+
+        if !options.applies(InstallPhase::Configs) {
+            reporter.success("Done!");
+            return Ok(());
+        }
+
+        reporter.info("Upgrading configurations...");
+        let theirs = Self::render_config(&tailwind_options);
+        let path = project.path(CONFIG_PATH);
+
+        if path.exists() {
+            let ours = AmarisFileHandler::load_file(&path).await?;
+            let base = AmarisBaseStore::read(project, PROVIDER_NAME, CONFIG_PATH).await?;
+            let merged = merge_three_way(
+                &path,
+                ConfigFormat::Raw,
+                base.as_deref(),
+                &ours,
+                &theirs,
+                options.conflict_resolution,
+            )
+            .await?;
+
+            if merged != ours {
+                AmarisFileHandler::write_file(project, path, &merged).await?;
+            }
+        } else {
+            AmarisFileHandler::write_file(project, path, &theirs).await?;
+        }
+        AmarisBaseStore::write(project, PROVIDER_NAME, CONFIG_PATH, &theirs).await?;
+
+        Self::write_editor_files(project).await?;
+
+        reporter.success("Done!");
+
+        Ok(())
+    }
+
+    async fn remove(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+
+        if options.applies(InstallPhase::Packages) {
+            reporter.info("Removing packages...");
+            AmarisInstaller::remove(
+                project,
+                &Self::package_manager(project).await?.name,
+                PackageScope::Dev,
+                &InstallContext::resolve(project, None, &HashMap::new(), &options.extra_env)
+                    .await?,
+                &self.packages(),
+                options.timeout,
+            )
+            .await?;
+        }
+
+        if options.applies(InstallPhase::Configs) {
+            reporter.info("Removing configurations...");
+            AmarisFileHandler::remove_file(project, project.path(CONFIG_PATH)).await?;
+            Self::remove_directives(project).await?;
+            Self::remove_postcss_plugin(project).await?;
+            AmarisVisualStudioCodeHandler::remove_vscode_extension_recommendation(
+                project,
+                INTELLISENSE_EXTENSION,
+            )
+            .await?;
+        }
+
+        AmarisBaseStore::clear(project, PROVIDER_NAME).await?;
+
+        reporter.success("Done!");
+
+        Ok(())
+    }
+}
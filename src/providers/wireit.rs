@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{
+    error::ConfigError,
+    package_manager::{AmarisPackageManager, InstallContext, ResolvedPackageManager},
+    provider::{
+        AmarisProvider, CONDITION_PACKAGE_JSON, CONDITION_PACKAGE_MANAGER, DetectionResult,
+        InstallOptions, InstallPhase, PackageScope, PrerequisiteSuggestion, ProjectContext,
+        ScriptEntry, option_value,
+    },
+    reporter::Reporter,
+    summary::{AmarisEvents, InstallEvent},
+    utils::{
+        AmarisInstaller, AmarisNodeVersionHandler, AmarisPackageJsonHandler, PACKAGE_JSON_FILE_NAME,
+    },
+};
+
+const PROVIDER_NAME: &str = "wireit";
+const PACKAGE_MANAGER: &str = "bun";
+const PACKAGES: &[&str] = &["wireit"];
+/// Oldest Node.js wireit runs on.
+const MIN_NODE_VERSION: &str = "18";
+/// The script value that hands a script over to its entry in the `wireit` block.
+const WIREIT_COMMAND: &str = "wireit";
+/// Run by the package manager itself, wrapping them would change when they run.
+const LIFECYCLE_SCRIPTS: &[&str] = &[
+    "preinstall",
+    "install",
+    "postinstall",
+    "prepare",
+    "prepublishOnly",
+    "prepack",
+    "postpack",
+];
+const DEFAULT_FILES: &[&str] = &["src/**", "package.json"];
+/// Variables whose default can be set with [`AmarisProvider::set_option`].
+const OPTIONS: &[&str] = &["files", "output"];
+
+#[derive(Debug, Clone)]
+pub struct WireitOptions {
+    /// Globs whose changes invalidate the cached result of a task.
+    pub files: Vec<String>,
+    /// Globs a task produces, restored from the cache.
+    pub output: Vec<String>,
+}
+
+impl Default for WireitOptions {
+    fn default() -> Self {
+        Self {
+            files: DEFAULT_FILES.iter().map(|file| file.to_string()).collect(),
+            output: vec![],
+        }
+    }
+}
+
+impl WireitOptions {
+    /// Reads the `files` and `output` variables, comma separated globs.
+    pub fn from_variables(variables: &HashMap<String, String>) -> Self {
+        let globs = |value: &String| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|glob| !glob.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        let defaults = Self::default();
+
+        Self {
+            files: variables.get("files").map(globs).unwrap_or(defaults.files),
+            output: variables
+                .get("output")
+                .map(globs)
+                .unwrap_or(defaults.output),
+        }
+    }
+
+    /// The `wireit` block of package.json running each of `scripts` as a task.
+    pub fn get_configuration(&self, scripts: &[ScriptEntry]) -> Value {
+        let tasks = scripts
+            .iter()
+            .map(|script| {
+                let task = serde_json::json!({
+                    "command": script.script,
+                    "files": self.files,
+                    "output": self.output,
+                });
+                (script.name.clone(), task)
+            })
+            .collect();
+
+        Value::Object(tasks)
+    }
+}
+
+/// Built-in provider for Wireit. Instead of adding scripts it moves the
+/// existing ones into Wireit tasks, so they are cached and run once per graph.
+#[derive(Default)]
+pub struct WireitProvider {
+    /// Defaults of the `files` and `output` variables from config.toml.
+    defaults: HashMap<String, String>,
+}
+
+impl WireitProvider {
+    /// Scripts of package.json Wireit does not run yet.
+    async fn convertible_scripts(
+        project: &ProjectContext,
+    ) -> Result<Vec<ScriptEntry>, ConfigError> {
+        Ok(AmarisPackageJsonHandler::get_scripts(project)
+            .await?
+            .into_iter()
+            .filter(|(name, script)| {
+                script != WIREIT_COMMAND && !LIFECYCLE_SCRIPTS.contains(&name.as_str())
+            })
+            .map(|(name, script)| ScriptEntry {
+                name,
+                script,
+                condition: None,
+                remove_on_uninstall: true,
+                original_content: None,
+            })
+            .collect())
+    }
+
+    /// The package manager to run, the project's one when it pins or locks one.
+    async fn package_manager(
+        project: &ProjectContext,
+    ) -> Result<ResolvedPackageManager, ConfigError> {
+        AmarisPackageManager::global()
+            .resolve(project, PACKAGE_MANAGER)
+            .await
+    }
+
+    async fn missing_packages(project: &ProjectContext) -> Result<Vec<String>, ConfigError> {
+        let detection = WireitProvider::default()
+            .detect(&project.reload().await?)
+            .await?;
+
+        Ok(PACKAGES
+            .iter()
+            .map(|package| package.to_string())
+            .filter(|package| !detection.packages.contains(package))
+            .collect())
+    }
+
+    /// Moves every convertible script into the `wireit` block, keeping the
+    /// tasks already there.
+    async fn wrap_scripts(
+        project: &ProjectContext,
+        options: &WireitOptions,
+    ) -> Result<(), ConfigError> {
+        let scripts = Self::convertible_scripts(project).await?;
+        if scripts.is_empty() {
+            return Ok(());
+        }
+
+        let mut package_json = AmarisPackageJsonHandler::read(project).await?;
+        if !package_json
+            .get(PROVIDER_NAME)
+            .is_some_and(Value::is_object)
+        {
+            package_json[PROVIDER_NAME] = serde_json::json!({});
+        }
+
+        if let Value::Object(tasks) = options.get_configuration(&scripts) {
+            for (name, task) in tasks {
+                package_json[PROVIDER_NAME][&name] = task;
+            }
+        }
+        for script in &scripts {
+            package_json["scripts"][&script.name] = serde_json::json!(WIREIT_COMMAND);
+            AmarisEvents::emit(project, InstallEvent::ScriptAdded(script.name.clone()));
+        }
+
+        AmarisPackageJsonHandler::write(project, &package_json).await
+    }
+
+    /// Puts the command of every Wireit task back into its script. Tasks
+    /// without a command only group others, their scripts are left as is.
+    async fn unwrap_scripts(project: &ProjectContext) -> Result<(), ConfigError> {
+        let mut package_json = AmarisPackageJsonHandler::read(project).await?;
+        let Some(tasks) = package_json
+            .get(PROVIDER_NAME)
+            .and_then(Value::as_object)
+            .cloned()
+        else {
+            return Ok(());
+        };
+
+        let mut remaining = serde_json::Map::new();
+        for (name, task) in tasks {
+            let command = task.get("command").and_then(Value::as_str);
+            let script = package_json["scripts"].get(&name).and_then(Value::as_str);
+
+            match (command, script) {
+                (Some(command), Some(WIREIT_COMMAND)) => {
+                    package_json["scripts"][&name] = serde_json::json!(command);
+                }
+                (Some(_), _) => {}
+                (None, _) => {
+                    remaining.insert(name, task);
+                }
+            }
+        }
+
+        let Some(package_json_object) = package_json.as_object_mut() else {
+            return Ok(());
+        };
+        match remaining.is_empty() {
+            true => package_json_object.remove(PROVIDER_NAME),
+            false => {
+                package_json_object.insert(PROVIDER_NAME.to_string(), Value::Object(remaining))
+            }
+        };
+
+        AmarisPackageJsonHandler::write(project, &package_json).await
+    }
+}
+
+#[async_trait]
+impl AmarisProvider for WireitProvider {
+    fn name(&self) -> &'static str {
+        PROVIDER_NAME
+    }
+
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    fn set_option(&mut self, key: &str, value: Value) -> Result<(), ConfigError> {
+        if !OPTIONS.contains(&key) {
+            return Err(ConfigError::ValidationError(format!(
+                "{} has no option '{}', expected one of {}",
+                PROVIDER_NAME,
+                key,
+                OPTIONS.join(", ")
+            )));
+        }
+
+        let value = option_value(PROVIDER_NAME, key, &value)?;
+        self.defaults.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Wireit keeps its tasks in package.json, so only its package is looked for.
+    async fn detect(&self, project: &ProjectContext) -> Result<DetectionResult, ConfigError> {
+        Ok(DetectionResult::from_project(
+            project,
+            &self.packages(),
+            &[],
+        ))
+    }
+
+    fn description(&self) -> &'static str {
+        "Wireit script caching"
+    }
+
+    async fn check_prerequisites(&self, project: &ProjectContext) -> Result<(), ConfigError> {
+        let suggestions = self.prerequisite_suggestions();
+
+        if !AmarisPackageJsonHandler::get_default_path(project).exists() {
+            return Err(PrerequisiteSuggestion::apply(
+                &suggestions,
+                CONDITION_PACKAGE_JSON,
+                "package.json not found!".to_string(),
+            ));
+        }
+
+        let manager = Self::package_manager(project).await?;
+        AmarisPackageManager::warn_on_mismatch(PROVIDER_NAME, PACKAGE_MANAGER, &manager);
+
+        if !AmarisPackageManager::global().ensure_available(&manager)? {
+            return Err(PrerequisiteSuggestion::apply(
+                &PrerequisiteSuggestion::for_resolved_manager(&manager),
+                CONDITION_PACKAGE_MANAGER,
+                format!("Package manager '{}' not found", manager.name),
+            ));
+        }
+
+        AmarisNodeVersionHandler::require_minimum(MIN_NODE_VERSION).await?;
+
+        if Self::convertible_scripts(project).await?.is_empty() {
+            Reporter::global().warn(
+                "Warning: package.json has no scripts to convert, add them before installing wireit",
+            );
+        }
+
+        Ok(())
+    }
+
+    fn prerequisite_suggestions(&self) -> Vec<PrerequisiteSuggestion> {
+        PrerequisiteSuggestion::for_package_manager(PACKAGE_MANAGER)
+    }
+
+    async fn check_conflicts(&self, _project: &ProjectContext) -> Result<(), ConfigError> {
+        Ok(())
+    }
+
+    fn packages(&self) -> Vec<String> {
+        PACKAGES.iter().map(|package| package.to_string()).collect()
+    }
+
+    fn managed_files(&self, _project: &ProjectContext) -> Vec<PathBuf> {
+        vec![]
+    }
+
+    fn planned_writes(
+        &self,
+        _project: &ProjectContext,
+        _workspace_file: Option<&Path>,
+    ) -> Vec<PathBuf> {
+        vec![PathBuf::from(PACKAGE_JSON_FILE_NAME)]
+    }
+
+    async fn install(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+
+        if options.applies(InstallPhase::Packages) {
+            reporter.info("Installing packages...");
+            AmarisEvents::emit(project, InstallEvent::Phase("packages".to_string()));
+            let missing = Self::missing_packages(project).await?;
+            AmarisInstaller::install(
+                project,
+                &Self::package_manager(project).await?.name,
+                PackageScope::Dev,
+                &InstallContext::resolve(project, None, &HashMap::new(), &options.extra_env)
+                    .await?,
+                &missing,
+                options.timeout,
+            )
+            .await?;
+        }
+
+        if options.applies(InstallPhase::Scripts) {
+            reporter.info("Converting scripts...");
+            AmarisEvents::emit(project, InstallEvent::Phase("scripts".to_string()));
+            let mut variables = self.defaults.clone();
+            variables.extend(options.variables.clone());
+            Self::wrap_scripts(project, &WireitOptions::from_variables(&variables)).await?;
+        }
+
+        reporter.success("Done!");
+
+        Ok(())
+    }
+
+    async fn upgrade(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        self.install(project, options).await
+    }
+
+    async fn remove(
+        &self,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+
+        if options.applies(InstallPhase::Scripts) {
+            reporter.info("Restoring scripts...");
+            Self::unwrap_scripts(project).await?;
+        }
+
+        if options.applies(InstallPhase::Packages) {
+            reporter.info("Removing packages...");
+            AmarisInstaller::remove(
+                project,
+                &Self::package_manager(project).await?.name,
+                PackageScope::Dev,
+                &InstallContext::resolve(project, None, &HashMap::new(), &options.extra_env)
+                    .await?,
+                &self.packages(),
+                options.timeout,
+            )
+            .await?;
+        }
+
+        reporter.success("Done!");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    fmt::Display,
+    io::IsTerminal,
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use owo_colors::OwoColorize;
+
+static REPORTER: OnceLock<Reporter> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    Auto,
+    Force,
+    Never,
+}
+
+impl ColorMode {
+    /// Resolves the color mode from the `--no-color` flag, the `NO_COLOR` and
+    /// `CLICOLOR_FORCE` environment variables, in that order of precedence.
+    pub fn detect(no_color: bool) -> Self {
+        if no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return ColorMode::Never;
+        }
+
+        if std::env::var("CLICOLOR_FORCE").is_ok_and(|v| !v.is_empty() && v != "0") {
+            return ColorMode::Force;
+        }
+
+        ColorMode::Auto
+    }
+
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorMode::Auto => std::io::stdout().is_terminal(),
+            ColorMode::Force => true,
+            ColorMode::Never => false,
+        }
+    }
+
+    fn enabled_on_stderr(&self) -> bool {
+        match self {
+            ColorMode::Auto => std::io::stderr().is_terminal(),
+            ColorMode::Force => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+pub struct Reporter {
+    color_mode: ColorMode,
+    /// Whether messages go to stderr, leaving stdout to `--output json-lines`.
+    stderr_only: AtomicBool,
+}
+
+impl Reporter {
+    pub fn init(color_mode: ColorMode) {
+        let _ = REPORTER.set(Reporter {
+            color_mode,
+            stderr_only: AtomicBool::new(false),
+        });
+    }
+
+    /// Returns the reporter set up by [`Reporter::init`], falling back to
+    /// automatic color detection if it was never initialized.
+    pub fn global() -> &'static Reporter {
+        REPORTER.get_or_init(|| Reporter {
+            color_mode: ColorMode::detect(false),
+            stderr_only: AtomicBool::new(false),
+        })
+    }
+
+    /// Writes every message to stderr from now on.
+    pub fn use_stderr(&self) {
+        self.stderr_only.store(true, Ordering::Relaxed);
+    }
+
+    fn on_stderr(&self) -> bool {
+        self.stderr_only.load(Ordering::Relaxed)
+    }
+
+    pub fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+
+    pub fn colors_enabled(&self) -> bool {
+        match self.on_stderr() {
+            true => self.color_mode.enabled_on_stderr(),
+            false => self.color_mode.enabled(),
+        }
+    }
+
+    pub fn provider(&self, name: impl Display) -> String {
+        if self.colors_enabled() {
+            name.cyan().to_string()
+        } else {
+            name.to_string()
+        }
+    }
+
+    pub fn info(&self, message: impl Display) {
+        self.print(message);
+    }
+
+    pub fn success(&self, message: impl Display) {
+        if self.colors_enabled() {
+            self.print(message.green());
+        } else {
+            self.print(message);
+        }
+    }
+
+    pub fn warn(&self, message: impl Display) {
+        if self.colors_enabled() {
+            self.print(message.yellow());
+        } else {
+            self.print(message);
+        }
+    }
+
+    pub fn error(&self, message: impl Display) {
+        if self.color_mode.enabled_on_stderr() {
+            eprintln!("{}", message.red());
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    fn print(&self, message: impl Display) {
+        match self.on_stderr() {
+            true => eprintln!("{}", message),
+            false => println!("{}", message),
+        }
+    }
+}
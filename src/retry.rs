@@ -0,0 +1,151 @@
+use std::{future::Future, time::Duration};
+
+use crate::{
+    config::AmayaConfig, error::ConfigError, provider::ProjectContext, reporter::Reporter,
+};
+
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+/// Upper bound for a single wait between attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Markers in package manager output that point at a network problem rather
+/// than a problem with the package or project.
+const NETWORK_ERROR_MARKERS: &[&str] = &[
+    "etimedout",
+    "enotfound",
+    "econnreset",
+    "econnrefused",
+    "eai_again",
+    "socket hang up",
+    "network error",
+    "connection reset",
+];
+
+/// The outcome of a failed attempt, only transient failures are retried.
+pub enum Failure {
+    Transient(ConfigError),
+    Permanent(ConfigError),
+}
+
+/// Retries an operation with exponential backoff and jitter, configured by
+/// the `retry` table of the global config.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub async fn load(project: &ProjectContext) -> Result<Self, ConfigError> {
+        let config = AmayaConfig::load(project).await?;
+
+        Ok(Self {
+            attempts: config.retry.attempts.max(1),
+            backoff: Duration::from_millis(config.retry.backoff_ms),
+        })
+    }
+
+    /// Wait before `attempt` (starting at 1 for the first retry): the
+    /// exponential delay with up to half of it replaced by random jitter.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(MAX_BACKOFF);
+        let half = exponential / 2;
+
+        half + half.mul_f64(fastrand::f64())
+    }
+
+    /// Runs `action` until it succeeds, fails permanently or runs out of
+    /// attempts. The final error lists what every attempt ran into.
+    pub async fn run<T, F, Fut>(&self, operation: &str, mut action: F) -> Result<T, ConfigError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Failure>>,
+    {
+        let mut history: Vec<String> = vec![];
+
+        for attempt in 1..=self.attempts {
+            let error = match action().await {
+                Ok(value) => return Ok(value),
+                Err(Failure::Permanent(e)) => return Err(e),
+                Err(Failure::Transient(e)) => e,
+            };
+
+            let message = match &error {
+                ConfigError::NetworkError(message) | ConfigError::DependencyError(message) => {
+                    message.clone()
+                }
+                e => e.to_string(),
+            };
+            history.push(message.lines().next().unwrap_or_default().to_string());
+
+            if attempt == self.attempts {
+                return Err(Self::exhausted(operation, error, &history));
+            }
+
+            let delay = self.delay(attempt);
+            Reporter::global().warn(format!(
+                "Warning: {} failed ({}), retrying in {:.1}s ({}/{})",
+                operation,
+                history.last().unwrap(),
+                delay.as_secs_f64(),
+                attempt + 1,
+                self.attempts
+            ));
+            tokio::time::sleep(delay).await;
+        }
+
+        unreachable!("attempts is at least 1")
+    }
+
+    fn exhausted(operation: &str, error: ConfigError, history: &[String]) -> ConfigError {
+        if history.len() < 2 {
+            return error;
+        }
+
+        let attempts: Vec<String> = history
+            .iter()
+            .enumerate()
+            .map(|(i, message)| format!("  attempt {}: {}", i + 1, message))
+            .collect();
+        let message = format!(
+            "{} failed after {} attempts:\n{}",
+            operation,
+            history.len(),
+            attempts.join("\n")
+        );
+
+        match error {
+            ConfigError::NetworkError(_) => ConfigError::NetworkError(message),
+            ConfigError::DependencyError(_) => ConfigError::DependencyError(message),
+            e => e,
+        }
+    }
+
+    /// Whether package manager output describes a network failure, e.g. a
+    /// DNS lookup, a dropped connection or a 5xx response from the registry.
+    pub fn is_network_failure(output: &str) -> bool {
+        let output = output.to_lowercase();
+
+        if NETWORK_ERROR_MARKERS
+            .iter()
+            .any(|marker| output.contains(marker))
+        {
+            return true;
+        }
+
+        let mentions_http = ["http", "registry", "status", "fetch"]
+            .iter()
+            .any(|word| output.contains(word));
+
+        mentions_http
+            && output
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .any(|token| {
+                    token.len() == 3 && token.starts_with('5') && token.parse::<u16>().is_ok()
+                })
+    }
+}
@@ -0,0 +1,31 @@
+use std::{path::PathBuf, sync::Mutex, time::Instant};
+
+use crate::{layers::SettingContribution, summary::InstallEvent};
+
+/// What amaya collects while it runs against a project, owned by its
+/// [`ProjectContext`](crate::provider::ProjectContext). Clones of the project
+/// share it, so every handler given the project records into the same run.
+#[derive(Debug, Default)]
+pub struct RunContext {
+    /// Directory used instead of `~/.amaya`, so `amaya selftest` and the
+    /// tests leave the real configuration alone.
+    pub home: Option<PathBuf>,
+    /// Every file written or removed, see [`AmarisJournal`](crate::utils::AmarisJournal).
+    pub journal: Mutex<Vec<PathBuf>>,
+    /// Events of the running install, `None` outside of one so events are
+    /// dropped, see [`AmarisEvents`](crate::summary::AmarisEvents).
+    pub events: Mutex<Option<Vec<(Instant, InstallEvent)>>>,
+    /// Settings merged into shared files by the running install, see
+    /// [`AmarisContributions`](crate::layers::AmarisContributions).
+    pub contributions: Mutex<Vec<SettingContribution>>,
+}
+
+impl RunContext {
+    /// A run using `home` instead of `~/.amaya`.
+    pub fn with_home(home: PathBuf) -> Self {
+        Self {
+            home: Some(home),
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::process::ExitStatus;
+
+use tokio::process::Child;
+
+use crate::{error::ConfigError, utils::AmarisPlatformHandler};
+
+/// Runs package.json scripts through the project's package manager, the way
+/// a teammate would type `bun run lint` or `npm run lint`.
+pub struct AmarisScriptRunner;
+
+impl AmarisScriptRunner {
+    /// Runs `script` with `args` appended, the output goes straight to the
+    /// terminal. Returns the exit code of the script.
+    pub async fn run(manager: &str, script: &str, args: &[String]) -> Result<i32, ConfigError> {
+        let mut command = AmarisPlatformHandler::command(manager);
+        command.arg("run").arg(script);
+
+        // npm would take the arguments as its own without the separator
+        if manager == "npm" && !args.is_empty() {
+            command.arg("--");
+        }
+        command.args(args);
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| ConfigError::DependencyError(format!("{}: {}", manager, e)))?;
+
+        let status = wait_forwarding_signals(&mut child)
+            .await
+            .map_err(|e| ConfigError::DependencyError(format!("{}: {}", manager, e)))?;
+
+        Ok(exit_code(status))
+    }
+}
+
+/// Waits for `child`, passing on the signals asking amaya to stop so the
+/// script can shut down itself.
+#[cfg(unix)]
+async fn wait_forwarding_signals(child: &mut Child) -> std::io::Result<ExitStatus> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    loop {
+        tokio::select! {
+            status = child.wait() => return status,
+            // Ctrl-C reaches the whole foreground process group, the script included
+            _ = interrupt.recv() => {}
+            _ = terminate.recv() => forward_signal(child, libc::SIGTERM),
+            _ = hangup.recv() => forward_signal(child, libc::SIGHUP),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_forwarding_signals(child: &mut Child) -> std::io::Result<ExitStatus> {
+    loop {
+        tokio::select! {
+            status = child.wait() => return status,
+            // Ctrl-C reaches every process attached to the console, the script included
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+}
+
+#[cfg(unix)]
+fn forward_signal(child: &Child, signal: libc::c_int) {
+    if let Some(pid) = child.id() {
+        // SAFETY: kill has no memory effects, a stale pid only makes it fail
+        unsafe {
+            libc::kill(pid as libc::pid_t, signal);
+        }
+    }
+}
+
+/// The exit code of `status`, a script killed by a signal exits with
+/// 128 plus the signal number like it would in a shell.
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+
+    status.code().unwrap_or(1)
+}
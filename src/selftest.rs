@@ -0,0 +1,306 @@
+use std::{path::Path, sync::Arc};
+
+use chrono::Utc;
+
+use crate::{
+    ci::AmarisCi,
+    error::ConfigError,
+    provider::{
+        AmarisProvider, AmarisRegistry, DynamicProvider, InstallOptions, InstallPhase,
+        ProjectContext,
+    },
+    providers::builtin_providers,
+    reporter::Reporter,
+    run::RunContext,
+    state::{AmarisProjectState, InstallRecord},
+    utils::{
+        AmarisInitialConfigHandler, AmarisInstaller, AmarisPackageJsonHandler, CONFIG_DIR_NAME,
+        InitConfig, PACKAGE_JSON_FILE_NAME, PROVIDER_DIR_NAME,
+    },
+};
+
+/// Starter provider installed into the test project.
+const SAMPLE_PROVIDER: &str = "biome";
+
+/// The outcome of one step of `amaya selftest`, `error` is `None` when it passed.
+#[derive(Debug)]
+pub struct SelfTestStep {
+    pub name: &'static str,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct SelfTestReport {
+    pub steps: Vec<SelfTestStep>,
+}
+
+impl SelfTestReport {
+    pub fn success(&self) -> bool {
+        self.steps.iter().all(|step| step.error.is_none())
+    }
+
+    /// Records and prints the outcome of `name`, returning whether it passed.
+    fn record(&mut self, name: &'static str, result: Result<(), ConfigError>) -> bool {
+        let reporter = Reporter::global();
+        let error = result.err().map(|e| e.to_string());
+
+        match &error {
+            Some(error) => reporter.error(format!("Failed: {}: {}", name, error)),
+            None => reporter.success(format!("Passed: {}", name)),
+        }
+
+        self.steps.push(SelfTestStep { name, error });
+        self.steps.last().is_some_and(|step| step.error.is_none())
+    }
+}
+
+/// Runs the core flows, init to remove, against a temporary amaya home and
+/// project, checking what each step leaves on disk.
+pub struct AmarisSelfTest;
+
+impl AmarisSelfTest {
+    /// Runs every step, stopping at the first failure since each builds on
+    /// the previous ones. The temporary directories are deleted afterwards.
+    pub async fn run() -> Result<SelfTestReport, ConfigError> {
+        let root =
+            std::env::temp_dir().join(format!("amaya-selftest-{}", Utc::now().timestamp_millis()));
+        let home = root.join("home");
+        let project = ProjectContext {
+            root: root.join("project"),
+            run: Arc::new(RunContext::with_home(home.clone())),
+            ..Default::default()
+        };
+
+        let report = Self::run_steps(&home, &project).await;
+
+        if root.exists() {
+            tokio::fs::remove_dir_all(&root).await?;
+        }
+
+        Ok(report)
+    }
+
+    async fn run_steps(home: &Path, project: &ProjectContext) -> SelfTestReport {
+        let mut report = SelfTestReport::default();
+        let mut registry = AmarisRegistry::new();
+        let options = InstallOptions {
+            // Installing packages needs the network, they are listed in package.json directly
+            phases: vec![InstallPhase::Configs, InstallPhase::Scripts],
+            ..Default::default()
+        };
+
+        if !report.record("init", Self::init(project, home).await) {
+            return report;
+        }
+        if !report.record(
+            "load providers",
+            Self::load_all(project, &mut registry).await,
+        ) {
+            return report;
+        }
+        let Some(provider) = registry.get_provider(SAMPLE_PROVIDER) else {
+            return report;
+        };
+
+        if !report.record("install", Self::install(provider, project, &options).await) {
+            return report;
+        }
+        if !report.record("status", Self::status(provider, project).await) {
+            return report;
+        }
+        if !report.record("check", Self::check(project, &registry).await) {
+            return report;
+        }
+        report.record("remove", Self::remove(provider, project, &options).await);
+
+        report
+    }
+
+    async fn init(project: &ProjectContext, home: &Path) -> Result<(), ConfigError> {
+        let init = InitConfig {
+            providers: vec![SAMPLE_PROVIDER],
+            ..Default::default()
+        };
+        AmarisInitialConfigHandler::ensure_dirs(project, &init).await?;
+        AmarisInitialConfigHandler::create_initial_config(project, &init).await?;
+
+        expect_exists(
+            &home
+                .join(PROVIDER_DIR_NAME)
+                .join(format!("{}.json", SAMPLE_PROVIDER)),
+        )?;
+        expect_exists(&home.join(CONFIG_DIR_NAME).join(SAMPLE_PROVIDER))
+    }
+
+    async fn load_all(
+        project: &ProjectContext,
+        registry: &mut AmarisRegistry,
+    ) -> Result<(), ConfigError> {
+        for provider in builtin_providers() {
+            registry.register(provider);
+        }
+        for provider in DynamicProvider::load_all(project, None).await? {
+            registry.register(provider.into());
+        }
+
+        match registry.get_provider(SAMPLE_PROVIDER) {
+            Some(provider) if !provider.is_builtin() => Ok(()),
+            _ => Err(ConfigError::ValidationError(format!(
+                "the {} provider written by init was not loaded",
+                SAMPLE_PROVIDER
+            ))),
+        }
+    }
+
+    async fn install(
+        provider: &dyn AmarisProvider,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        tokio::fs::create_dir_all(&project.root).await?;
+        let package_json = serde_json::json!({ "name": "selftest", "type": "module" });
+        tokio::fs::write(
+            project.path(PACKAGE_JSON_FILE_NAME),
+            serde_json::to_string_pretty(&package_json)?,
+        )
+        .await?;
+
+        provider.install(project, options).await?;
+        for package in project_packages(provider) {
+            AmarisPackageJsonHandler::add_dev_dependency(project, &package, "*").await?;
+        }
+
+        let mut state = AmarisProjectState::load(project).await?;
+        state.record_install(InstallRecord {
+            name: provider.name().to_string(),
+            files: provider
+                .managed_files(project)
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+            version: provider.metadata().version,
+            // Recorded with the packages so check verifies them
+            phases: InstallPhase::all(),
+            ..Default::default()
+        });
+        state.save(project).await?;
+
+        for path in provider.managed_files(project) {
+            expect_exists(&project.path(path))?;
+        }
+        let scripts = AmarisPackageJsonHandler::get_scripts(project).await?;
+        for script in provider.scripts() {
+            if !scripts.iter().any(|(name, _)| *name == script.name) {
+                return Err(ConfigError::ValidationError(format!(
+                    "package.json has no {} script",
+                    script.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn status(
+        provider: &dyn AmarisProvider,
+        project: &ProjectContext,
+    ) -> Result<(), ConfigError> {
+        let state = AmarisProjectState::load(project).await?;
+        if !state.is_installed(provider.name()) {
+            return Err(ConfigError::ValidationError(format!(
+                "{} is not recorded as installed",
+                provider.name()
+            )));
+        }
+
+        let detection = provider.detect(&project.reload().await?).await?;
+        if !detection.is_present() {
+            return Err(ConfigError::ValidationError(format!(
+                "{} is not detected in the project",
+                provider.name()
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn check(project: &ProjectContext, registry: &AmarisRegistry) -> Result<(), ConfigError> {
+        let report = AmarisCi::run(project, registry).await?;
+        let problems: Vec<String> = report
+            .providers
+            .iter()
+            .flat_map(|provider| provider.problems())
+            .collect();
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError::ValidationError(problems.join(", "))),
+        }
+    }
+
+    async fn remove(
+        provider: &dyn AmarisProvider,
+        project: &ProjectContext,
+        options: &InstallOptions,
+    ) -> Result<(), ConfigError> {
+        provider.remove(project, options).await?;
+        for package in project_packages(provider) {
+            AmarisPackageJsonHandler::remove_dev_dependency(project, &package).await?;
+        }
+
+        let mut state = AmarisProjectState::load(project).await?;
+        state.record_remove(provider.name());
+        state.save(project).await?;
+
+        if let Some(path) = provider
+            .managed_files(project)
+            .into_iter()
+            .find(|path| project.path(path).exists())
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "{} is still present",
+                path.display()
+            )));
+        }
+        for package in project_packages(provider) {
+            if AmarisInstaller::check_if_package_installed(project, &package).await? {
+                return Err(ConfigError::ValidationError(format!(
+                    "package.json still lists {}",
+                    package
+                )));
+            }
+        }
+        let scripts = AmarisPackageJsonHandler::get_scripts(project).await?;
+        if let Some((name, _)) = scripts
+            .iter()
+            .find(|(name, _)| provider.scripts().iter().any(|script| script.name == *name))
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "package.json still has the {} script",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Packages of `provider` that package.json lists, global ones are not.
+fn project_packages(provider: &dyn AmarisProvider) -> Vec<String> {
+    provider
+        .package_entries()
+        .into_iter()
+        .filter(|package| !package.is_global())
+        .map(|package| package.name)
+        .collect()
+}
+
+fn expect_exists(path: &Path) -> Result<(), ConfigError> {
+    match path.exists() {
+        true => Ok(()),
+        false => Err(ConfigError::ValidationError(format!(
+            "{} was not created",
+            path.display()
+        ))),
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ConfigError,
+    layers::{SettingContribution, merge_contributions},
+    provider::{AmarisAliases, InstallPhase, PackageScope, ProjectContext},
+    utils::AmarisFileHandler,
+};
+
+pub const PROJECT_STATE_FILE_NAME: &str = "amaya.json";
+pub const BASE_STORE_DIR: &str = ".amaya/base";
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct InstallRecord {
+    pub name: String,
+    pub installed_at: DateTime<Utc>,
+    /// Configuration files written by the provider, relative to the project root.
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Variant of the provider that was installed, if it defines any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    /// Version of the provider definition that generated the configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Multi-root workspace file that received the VS Code settings instead
+    /// of `.vscode/settings.json`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_file: Option<String>,
+    /// Phases that were applied, upgrade and removal only touch these.
+    #[serde(default = "InstallPhase::all")]
+    pub phases: Vec<InstallPhase>,
+    /// Settings merged into files other providers may share.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contributions: Vec<SettingContribution>,
+    /// Packages installed as production or global dependencies, keyed by
+    /// package, so removal targets the same place.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub package_scopes: BTreeMap<String, PackageScope>,
+    /// Packages of the provider no longer expected in package.json, set by
+    /// `amaya deps --prune`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub untracked_packages: Vec<String>,
+    /// Scripts package.json had before the provider replaced them, keyed by
+    /// name, so removal restores them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub original_scripts: BTreeMap<String, String>,
+}
+
+/// Tracks which providers were installed into the current project, in install order.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AmarisProjectState {
+    #[serde(default)]
+    pub installed_providers: Vec<InstallRecord>,
+}
+
+impl AmarisProjectState {
+    pub fn get_default_path(project: &ProjectContext) -> PathBuf {
+        project.path(PROJECT_STATE_FILE_NAME)
+    }
+
+    pub async fn load(project: &ProjectContext) -> Result<Self, ConfigError> {
+        let path = Self::get_default_path(project);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = AmarisFileHandler::load_file(&path).await?;
+
+        let mut state: Self = serde_json::from_str(&content)
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", path.display(), e)))?;
+
+        // Providers installed under a name they were renamed from since
+        for record in &mut state.installed_providers {
+            if let Some(current) = AmarisAliases::resolve(&record.name) {
+                record.name = current.to_string();
+            }
+        }
+
+        Ok(state)
+    }
+
+    pub async fn save(&self, project: &ProjectContext) -> Result<(), ConfigError> {
+        let content = serde_json::to_string_pretty(self)?;
+        let path = Self::get_default_path(project);
+
+        AmarisFileHandler::write_file(project, path, &content).await
+    }
+
+    pub fn get(&self, name: &str) -> Option<&InstallRecord> {
+        self.installed_providers
+            .iter()
+            .find(|record| record.name == name)
+    }
+
+    pub fn is_installed(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Adds `record`, replacing an earlier install of the same provider.
+    pub fn record_install(&mut self, record: InstallRecord) {
+        self.record_remove(&record.name);
+        self.installed_providers.push(InstallRecord {
+            installed_at: Utc::now(),
+            ..record
+        });
+    }
+
+    pub fn record_upgrade(
+        &mut self,
+        name: &str,
+        version: Option<String>,
+        files: Vec<String>,
+        contributions: Vec<SettingContribution>,
+    ) {
+        if let Some(record) = self
+            .installed_providers
+            .iter_mut()
+            .find(|record| record.name == name)
+        {
+            record.files = files;
+            record.version = version;
+            merge_contributions(&mut record.contributions, contributions);
+        }
+    }
+
+    /// The workspace file recorded for `name`, `None` for `.vscode/settings.json`.
+    pub fn workspace_file(&self, project: &ProjectContext, name: &str) -> Option<PathBuf> {
+        self.get(name)
+            .and_then(|record| record.workspace_file.as_ref())
+            .map(|file| project.path(file))
+    }
+
+    /// The phases recorded for `name`, every phase when it is not installed.
+    pub fn phases(&self, name: &str) -> Vec<InstallPhase> {
+        self.get(name)
+            .map(|record| record.phases.clone())
+            .unwrap_or_else(InstallPhase::all)
+    }
+
+    /// The package scopes recorded for `name`, see [`InstallRecord::package_scopes`].
+    pub fn package_scopes(&self, name: &str) -> BTreeMap<String, PackageScope> {
+        self.get(name)
+            .map(|record| record.package_scopes.clone())
+            .unwrap_or_default()
+    }
+
+    /// The scripts recorded for `name`, see [`InstallRecord::original_scripts`].
+    pub fn original_scripts(&self, name: &str) -> BTreeMap<String, String> {
+        self.get(name)
+            .map(|record| record.original_scripts.clone())
+            .unwrap_or_default()
+    }
+
+    /// The files recorded for `name`, see [`InstallRecord::files`].
+    pub fn installed_files(&self, name: &str) -> Vec<String> {
+        self.get(name)
+            .map(|record| record.files.clone())
+            .unwrap_or_default()
+    }
+
+    /// Stops expecting `packages` of `name`, see [`InstallRecord::untracked_packages`].
+    pub fn record_untracked(&mut self, name: &str, packages: &[String]) {
+        if let Some(record) = self
+            .installed_providers
+            .iter_mut()
+            .find(|record| record.name == name)
+        {
+            for package in packages {
+                if !record.untracked_packages.contains(package) {
+                    record.untracked_packages.push(package.clone());
+                }
+            }
+        }
+    }
+
+    pub fn record_remove(&mut self, name: &str) {
+        self.installed_providers
+            .retain(|record| record.name != name);
+    }
+}
+
+/// Keeps the content amaya last wrote for each configuration file, the common
+/// ancestor for three-way merges on upgrade.
+pub struct AmarisBaseStore;
+
+impl AmarisBaseStore {
+    pub fn get_path(project: &ProjectContext, provider: &str, file_location: &str) -> PathBuf {
+        Self::provider_dir(project, provider).join(file_location)
+    }
+
+    /// The directory of `provider`, under the name it had when it was
+    /// installed if it was renamed since.
+    fn provider_dir(project: &ProjectContext, provider: &str) -> PathBuf {
+        let dir = project.path(BASE_STORE_DIR);
+        let provider = AmarisAliases::previous_names(provider)
+            .into_iter()
+            .find(|previous| !dir.join(provider).exists() && dir.join(previous).exists())
+            .unwrap_or(provider);
+
+        dir.join(provider)
+    }
+
+    pub async fn read(
+        project: &ProjectContext,
+        provider: &str,
+        file_location: &str,
+    ) -> Result<Option<String>, ConfigError> {
+        let path = Self::get_path(project, provider, file_location);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        AmarisFileHandler::load_file(&path).await.map(Some)
+    }
+
+    pub async fn write(
+        project: &ProjectContext,
+        provider: &str,
+        file_location: &str,
+        content: &str,
+    ) -> Result<(), ConfigError> {
+        let path = Self::get_path(project, provider, file_location);
+        AmarisFileHandler::check_write_target(project, &path)?;
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::write(&path, content)
+            .await
+            .map_err(|e| ConfigError::FileWriteError(e.to_string()))
+    }
+
+    pub async fn clear(project: &ProjectContext, provider: &str) -> Result<(), ConfigError> {
+        let path = Self::provider_dir(project, provider);
+
+        if path.exists() {
+            tokio::fs::remove_dir_all(&path).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    config::ProjectOverrides,
+    provider::{AmarisRegistry, ProjectContext},
+    reporter::Reporter,
+    state::AmarisProjectState,
+    utils::AmarisConfigurationHandler,
+};
+
+/// `amaya status`: the providers installed in the project and whether their
+/// files are complete, along with tools set up without amaya.
+pub struct AmarisStatus;
+
+impl AmarisStatus {
+    pub async fn run(project: &ProjectContext, registry: &AmarisRegistry) -> anyhow::Result<()> {
+        let reporter = Reporter::global();
+        let state = AmarisProjectState::load(project).await?;
+
+        reporter.info(match &project.package_manager {
+            Some(manager) => format!("Project: {} ({})", project.root.display(), manager),
+            None => format!("Project: {}", project.root.display()),
+        });
+
+        let mut detected = vec![];
+        for (name, _) in registry.available_configs_sorted() {
+            let provider = registry.get_provider(name).unwrap();
+            let detection = provider.detect(project).await?;
+
+            if !state.is_installed(name) && detection.is_present() {
+                detected.push((name, detection));
+            }
+        }
+
+        if !detected.is_empty() {
+            reporter.info("Set up without amaya:");
+            for (name, detection) in &detected {
+                let mut found = detection.packages.clone();
+                found.extend(detection.files.clone());
+                reporter.info(format!(
+                    "  {} ({}): {}",
+                    reporter.provider(name),
+                    detection.confidence.as_str(),
+                    found.join(", ")
+                ));
+            }
+        }
+
+        let overrides = ProjectOverrides::load(project).await?.describe();
+        if !overrides.is_empty() {
+            reporter.info("Overrides in effect:");
+            for line in &overrides {
+                reporter.info(format!("  {}", line));
+            }
+        }
+
+        if state.installed_providers.is_empty() {
+            reporter.info("No configurations installed in this project.");
+            return Ok(());
+        }
+
+        for record in &state.installed_providers {
+            let files =
+                AmarisConfigurationHandler::list_managed_files(project, &record.name).await?;
+            let installed_at = record.installed_at.format("%Y-%m-%d %H:%M UTC");
+            let name = match &record.version {
+                Some(version) => format!("{} {}", reporter.provider(&record.name), version),
+                None => reporter.provider(&record.name),
+            };
+
+            if let Some(deprecation) = registry
+                .get_provider(&record.name)
+                .and_then(|provider| provider.metadata().deprecation)
+            {
+                reporter.warn(format!(
+                    "Warning: {} is deprecated, {}{}",
+                    name,
+                    deprecation.describe(),
+                    match &deprecation.replaced_by {
+                        Some(replacement) => format!(
+                            ", migrate with `amaya switch {} {}`",
+                            record.name, replacement
+                        ),
+                        None => String::new(),
+                    }
+                ));
+            }
+
+            if AmarisConfigurationHandler::exists_all(&files) {
+                reporter.success(format!("{} (installed {}): complete", name, installed_at));
+                continue;
+            }
+
+            reporter.warn(format!("{} (installed {}): incomplete", name, installed_at));
+            for file in files.iter().filter(|file| !file.exists()) {
+                reporter.warn(format!("  missing {}", file.display()));
+            }
+        }
+
+        Ok(())
+    }
+}
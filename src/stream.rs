@@ -0,0 +1,183 @@
+use std::{
+    fmt::Display,
+    io::{BufRead, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+use inquire::{Confirm, InquireError, Select};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::summary::FileChange;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_PROMPT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// One line of `--output json-lines`, tagged with its kind in `event`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// The install of `provider` began.
+    Started {
+        provider: String,
+    },
+    PhaseStarted {
+        phase: String,
+    },
+    PhaseFinished {
+        phase: String,
+        elapsed_ms: u128,
+    },
+    PackageInstalled {
+        name: String,
+    },
+    FileWritten {
+        path: PathBuf,
+        change: FileChange,
+    },
+    ScriptAdded {
+        name: String,
+    },
+    /// The install of `provider` completed, `elapsed_ms` after it started.
+    Finished {
+        provider: String,
+        elapsed_ms: u128,
+    },
+    /// A question that blocks until a [`PromptAnswer`] with the same `id` is
+    /// written to stdin. `options` lists the choices of a `select`, and
+    /// `default` is the answer a `confirm` falls back to.
+    Prompt {
+        id: u64,
+        kind: PromptKind,
+        message: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        options: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<bool>,
+    },
+    /// The command failed, it exits with 1 afterwards.
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptKind {
+    Confirm,
+    Select,
+}
+
+/// The line a client writes to stdin to answer a prompt. A `confirm` takes
+/// `true` or `false`, a `select` one of the options or its index.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PromptAnswer {
+    pub id: u64,
+    pub answer: Value,
+}
+
+/// The machine readable output of `--output json-lines`: events are written
+/// to stdout as they happen, one JSON object per line, while the human
+/// readable output moves to stderr. Prompts become events answered on stdin,
+/// so editors and other tools can drive amaya.
+pub struct AmarisStream;
+
+impl AmarisStream {
+    pub fn enable() {
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled() -> bool {
+        ENABLED.load(Ordering::Relaxed)
+    }
+
+    /// Writes `event` to stdout, nothing happens unless the stream is enabled.
+    pub fn send(event: &StreamEvent) {
+        if !Self::is_enabled() {
+            return;
+        }
+
+        let Ok(line) = serde_json::to_string(event) else {
+            return;
+        };
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", line);
+        let _ = stdout.flush();
+    }
+
+    /// Asks `prompt` in the terminal, or through the stream when it is enabled.
+    pub fn confirm(prompt: Confirm) -> anyhow::Result<bool> {
+        if !Self::is_enabled() {
+            return Ok(prompt.prompt()?);
+        }
+
+        let answer = Self::ask(PromptKind::Confirm, prompt.message, vec![], prompt.default)?;
+        match answer {
+            Value::Null => prompt.default.ok_or_else(|| invalid_answer(&answer)),
+            Value::Bool(answer) => Ok(answer),
+            answer => Err(invalid_answer(&answer)),
+        }
+    }
+
+    /// Asks `prompt` in the terminal, or through the stream when it is enabled.
+    pub fn select<T: Display>(prompt: Select<T>) -> anyhow::Result<T> {
+        if !Self::is_enabled() {
+            return Ok(prompt.prompt()?);
+        }
+
+        let labels: Vec<String> = prompt.options.iter().map(ToString::to_string).collect();
+        let answer = Self::ask(PromptKind::Select, prompt.message, labels.clone(), None)?;
+        let index = match &answer {
+            Value::String(label) => labels.iter().position(|option| option == label),
+            Value::Number(index) => index
+                .as_u64()
+                .map(|index| index as usize)
+                .filter(|index| *index < labels.len()),
+            _ => None,
+        };
+
+        index
+            .and_then(|index| prompt.options.into_iter().nth(index))
+            .ok_or_else(|| invalid_answer(&answer))
+    }
+
+    /// Sends a prompt and waits for its answer on stdin. Answers to other
+    /// prompts are skipped, a closed stdin cancels the prompt.
+    fn ask(
+        kind: PromptKind,
+        message: &str,
+        options: Vec<String>,
+        default: Option<bool>,
+    ) -> anyhow::Result<Value> {
+        let id = NEXT_PROMPT_ID.fetch_add(1, Ordering::Relaxed);
+        Self::send(&StreamEvent::Prompt {
+            id,
+            kind,
+            message: message.to_string(),
+            options,
+            default,
+        });
+
+        let mut stdin = std::io::stdin().lock();
+        loop {
+            let mut line = String::new();
+            if stdin.read_line(&mut line)? == 0 {
+                return Err(InquireError::OperationCanceled.into());
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let answer: PromptAnswer = serde_json::from_str(line.trim())
+                .map_err(|e| anyhow::anyhow!("invalid prompt answer '{}': {}", line.trim(), e))?;
+            if answer.id == id {
+                return Ok(answer.answer);
+            }
+        }
+    }
+}
+
+fn invalid_answer(answer: &Value) -> anyhow::Error {
+    anyhow::anyhow!("{} does not answer the prompt", answer)
+}
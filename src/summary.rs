@@ -0,0 +1,280 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ConfigError,
+    provider::ProjectContext,
+    reporter::Reporter,
+    stream::{AmarisStream, StreamEvent},
+    utils::AmarisPackageJsonHandler,
+};
+
+/// Something that happened while installing a provider.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallEvent {
+    Started(String),
+    /// A new step of the install, it lasts until the next one begins.
+    Phase(String),
+    PackageInstalled(String),
+    FileWritten(PathBuf, FileChange),
+    ScriptAdded(String),
+    Finished,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    /// The file already had the content that would have been written.
+    Skipped,
+    Updated,
+    Created,
+}
+
+/// The event stream of the current install, the summary is built from it so
+/// it reports what actually happened.
+pub struct AmarisEvents;
+
+impl AmarisEvents {
+    /// Clears the events of a previous install and starts a new one in `project`.
+    pub fn start(project: &ProjectContext, provider: &str) {
+        let mut events = project.run.events.lock().unwrap();
+
+        *events = Some(vec![]);
+        Self::push(
+            project,
+            &mut events,
+            InstallEvent::Started(provider.to_string()),
+        );
+    }
+
+    pub fn emit(project: &ProjectContext, event: InstallEvent) {
+        Self::push(project, &mut project.run.events.lock().unwrap(), event);
+    }
+
+    fn push(
+        project: &ProjectContext,
+        events: &mut Option<Vec<(Instant, InstallEvent)>>,
+        event: InstallEvent,
+    ) {
+        let Some(events) = events else {
+            return;
+        };
+
+        let event = match event {
+            InstallEvent::FileWritten(path, change) => {
+                let path = path
+                    .strip_prefix(&project.root)
+                    .map(PathBuf::from)
+                    .unwrap_or(path);
+                InstallEvent::FileWritten(path, change)
+            }
+            event => event,
+        };
+        let at = Instant::now();
+        if AmarisStream::is_enabled() {
+            Self::stream(events, &event, at);
+        }
+        events.push((at, event));
+    }
+
+    /// Sends `event` to the json-lines stream, after closing the running
+    /// phase when a new one begins or the install finishes.
+    fn stream(previous: &[(Instant, InstallEvent)], event: &InstallEvent, at: Instant) {
+        if matches!(event, InstallEvent::Phase(_) | InstallEvent::Finished)
+            && let Some((started, InstallEvent::Phase(phase))) = previous
+                .iter()
+                .rev()
+                .find(|(_, event)| matches!(event, InstallEvent::Phase(_)))
+        {
+            AmarisStream::send(&StreamEvent::PhaseFinished {
+                phase: phase.clone(),
+                elapsed_ms: at.duration_since(*started).as_millis(),
+            });
+        }
+
+        let event = match event {
+            InstallEvent::Started(provider) => StreamEvent::Started {
+                provider: provider.clone(),
+            },
+            InstallEvent::Phase(phase) => StreamEvent::PhaseStarted {
+                phase: phase.clone(),
+            },
+            InstallEvent::PackageInstalled(name) => {
+                StreamEvent::PackageInstalled { name: name.clone() }
+            }
+            InstallEvent::FileWritten(path, change) => StreamEvent::FileWritten {
+                path: path.clone(),
+                change: *change,
+            },
+            InstallEvent::ScriptAdded(name) => StreamEvent::ScriptAdded { name: name.clone() },
+            InstallEvent::Finished => match previous.first() {
+                Some((started, InstallEvent::Started(provider))) => StreamEvent::Finished {
+                    provider: provider.clone(),
+                    elapsed_ms: at.duration_since(*started).as_millis(),
+                },
+                _ => return,
+            },
+        };
+        AmarisStream::send(&event);
+    }
+
+    /// Ends the install and returns its events.
+    pub fn take(project: &ProjectContext) -> Vec<(Instant, InstallEvent)> {
+        project
+            .run
+            .events
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct InstallSummary {
+    pub provider: String,
+    pub packages: Vec<PackageSummary>,
+    pub files: Vec<FileSummary>,
+    pub scripts: Vec<String>,
+    pub phases: Vec<PhaseTiming>,
+    pub elapsed_ms: u128,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PackageSummary {
+    pub name: String,
+    /// The version range package.json lists after the install.
+    pub version: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FileSummary {
+    pub path: PathBuf,
+    pub change: FileChange,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PhaseTiming {
+    pub name: String,
+    pub elapsed_ms: u128,
+}
+
+impl InstallSummary {
+    /// Ends the current install and summarizes its events, with package
+    /// versions read back from package.json.
+    pub async fn finish(project: &ProjectContext) -> Result<Self, ConfigError> {
+        AmarisEvents::emit(project, InstallEvent::Finished);
+        let mut summary = Self::from_events(&AmarisEvents::take(project));
+
+        for package in &mut summary.packages {
+            package.version =
+                AmarisPackageJsonHandler::get_dependency_version(project, &package.name).await?;
+        }
+
+        Ok(summary)
+    }
+
+    pub fn from_events(events: &[(Instant, InstallEvent)]) -> Self {
+        let mut summary = InstallSummary::default();
+        let mut phase: Option<(&str, Instant)> = None;
+
+        for (at, event) in events {
+            match event {
+                InstallEvent::Started(provider) => summary.provider = provider.clone(),
+                InstallEvent::Phase(_) | InstallEvent::Finished => {
+                    if let Some((name, started)) = phase.take() {
+                        summary.phases.push(PhaseTiming {
+                            name: name.to_string(),
+                            elapsed_ms: at.duration_since(started).as_millis(),
+                        });
+                    }
+
+                    if let InstallEvent::Phase(name) = event {
+                        phase = Some((name, *at));
+                    }
+                }
+                InstallEvent::PackageInstalled(name) => summary.packages.push(PackageSummary {
+                    name: name.clone(),
+                    version: None,
+                }),
+                InstallEvent::FileWritten(path, change) => {
+                    // A file written twice keeps its most significant change
+                    match summary.files.iter_mut().find(|file| &file.path == path) {
+                        Some(file) => file.change = file.change.max(*change),
+                        None => summary.files.push(FileSummary {
+                            path: path.clone(),
+                            change: *change,
+                        }),
+                    }
+                }
+                InstallEvent::ScriptAdded(name) => {
+                    if !summary.scripts.contains(name) {
+                        summary.scripts.push(name.clone());
+                    }
+                }
+            }
+        }
+
+        if let (Some((first, _)), Some((last, _))) = (events.first(), events.last()) {
+            summary.elapsed_ms = last.duration_since(*first).as_millis();
+        }
+
+        summary
+    }
+
+    /// Prints the summary as a two-column table.
+    pub fn render(&self) {
+        let reporter = Reporter::global();
+        let files = |change: FileChange| -> String {
+            self.files
+                .iter()
+                .filter(|file| file.change == change)
+                .map(|file| file.path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let rows = [
+            (
+                "packages",
+                self.packages
+                    .iter()
+                    .map(|package| match &package.version {
+                        Some(version) => format!("{} {}", package.name, version),
+                        None => package.name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            ("created", files(FileChange::Created)),
+            ("updated", files(FileChange::Updated)),
+            ("skipped", files(FileChange::Skipped)),
+            ("scripts", self.scripts.join(", ")),
+            (
+                "timing",
+                self.phases
+                    .iter()
+                    .map(|phase| format!("{} {}", phase.name, format_ms(phase.elapsed_ms)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        ];
+
+        reporter.info(format!(
+            "Summary for {} ({}):",
+            reporter.provider(&self.provider),
+            format_ms(self.elapsed_ms)
+        ));
+        for (label, value) in rows.iter().filter(|(_, value)| !value.is_empty()) {
+            reporter.info(format!("  {:<10}{}", label, value));
+        }
+    }
+}
+
+fn format_ms(ms: u128) -> String {
+    format!("{:.1}s", Duration::from_millis(ms as u64).as_secs_f64())
+}
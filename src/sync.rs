@@ -0,0 +1,304 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::ConfigError,
+    network::AmarisNetwork,
+    provider::{AmarisRegistry, ConfigEntry, DynamicProvider, MergeStrategy, ProjectContext},
+    reporter::Reporter,
+    state::AmarisProjectState,
+    utils::{
+        AmarisConfigurationHandler, AmarisFileHandler, AmarisGitHandler, AmarisPathHandler,
+        CONFIG_DIR_NAME, PROVIDER_DIR_NAME, sha256_integrity,
+    },
+};
+
+/// Directories of `~/.amaya` shared through the sync repository.
+const SYNCED_DIRS: [&str; 2] = [PROVIDER_DIR_NAME, CONFIG_DIR_NAME];
+const SYNC_COMMIT_MESSAGE: &str = "Update providers with amaya providers sync";
+
+/// A file `amaya providers sync` wrote, relative to the directory it was written in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncChange {
+    pub path: PathBuf,
+    /// Whether the file did not exist before.
+    pub added: bool,
+}
+
+/// Shares providers and their configuration sources between the members of
+/// a team: local edits to managed files flow back into `~/.amaya/configs`,
+/// and `~/.amaya/providers` and `~/.amaya/configs` travel through a git
+/// repository.
+pub struct AmarisSync;
+
+impl AmarisSync {
+    /// Copies the managed configuration files of the project back into the
+    /// sources of the installed providers they were generated from,
+    /// returning the sources that changed. Sources with placeholders and
+    /// entries merged into shared files are skipped, the project file does
+    /// not hold just their content.
+    pub async fn push_project(
+        project: &ProjectContext,
+        registry: &AmarisRegistry,
+    ) -> Result<Vec<PathBuf>, ConfigError> {
+        let reporter = Reporter::global();
+        let state = AmarisProjectState::load(project).await?;
+        let mut updated = vec![];
+        let mut hashes = vec![];
+
+        for record in &state.installed_providers {
+            let Some(provider) = registry.get_provider(&record.name) else {
+                continue;
+            };
+            if provider.is_builtin() {
+                continue;
+            }
+            let variant_provider = record
+                .variant
+                .as_deref()
+                .map(|variant| provider.with_variant(variant))
+                .transpose()?;
+            let provider = variant_provider.as_deref().unwrap_or(provider);
+
+            for config in provider.config_files() {
+                if config.inline_content.is_some() || !config.applies(project) {
+                    continue;
+                }
+
+                let target =
+                    AmarisConfigurationHandler::resolve_target(project, provider.name(), &config)?;
+                let source =
+                    AmarisConfigurationHandler::resolve_source(project, provider.name(), &config)?;
+                if !target.is_file() || !source.is_file() {
+                    continue;
+                }
+
+                let current = AmarisFileHandler::read_text(&source).await?;
+                if config.merge == Some(MergeStrategy::Merge) || current.contains("{{") {
+                    reporter.warn(format!(
+                        "Warning: skipping {}, its source is merged or templated",
+                        project.relative(&target).display()
+                    ));
+                    continue;
+                }
+
+                let content = AmarisFileHandler::read_text(&target).await?;
+                if content == current {
+                    continue;
+                }
+
+                tokio::fs::write(&source, &content).await.map_err(|e| {
+                    ConfigError::FileWriteError(format!("{}: {}", source.display(), e))
+                })?;
+                if config.integrity.is_some() {
+                    hashes.push((
+                        Self::owner(provider.name(), &config),
+                        config.source_from.clone(),
+                        sha256_integrity(content.as_bytes()),
+                    ));
+                }
+                updated.push(source);
+            }
+        }
+
+        Self::update_integrity(project, &hashes).await?;
+        Ok(updated)
+    }
+
+    /// Brings the sync repository up to date, copies the providers and
+    /// sources edited locally since the last sync into it and commits and
+    /// pushes them. Returns the files that changed in the repository, nothing
+    /// is committed when there are none.
+    pub async fn push_remote(
+        project: &ProjectContext,
+        remote: &str,
+    ) -> Result<Vec<SyncChange>, ConfigError> {
+        let repository = Self::open_repository(project, remote).await?;
+        let local = AmarisPathHandler::get_root_config_path(project)?;
+
+        // Compared before pulling, so files others changed since the last
+        // sync are not overwritten with the stale local copy
+        let edited = Self::differences(&local, &repository)?;
+        Self::pull_repository(&repository).await?;
+        let changes = Self::copy(&local, &repository, &edited)?;
+
+        if !changes.is_empty() {
+            AmarisGitHandler::run_in(&repository, &["add", "-A", "--"]).await?;
+            AmarisGitHandler::run_in(
+                &repository,
+                &["commit", "--quiet", "-m", SYNC_COMMIT_MESSAGE],
+            )
+            .await?;
+            AmarisGitHandler::run_in(
+                &repository,
+                &["push", "--quiet", "--set-upstream", "origin", "HEAD"],
+            )
+            .await?;
+        }
+
+        Ok(changes)
+    }
+
+    /// Brings the sync repository up to date and copies its providers and
+    /// sources into `~/.amaya`, returning the files that changed there.
+    pub async fn pull(
+        project: &ProjectContext,
+        remote: &str,
+    ) -> Result<Vec<SyncChange>, ConfigError> {
+        let repository = Self::open_repository(project, remote).await?;
+        let local = AmarisPathHandler::get_root_config_path(project)?;
+
+        Self::pull_repository(&repository).await?;
+        let changed = Self::differences(&repository, &local)?;
+        Self::copy(&repository, &local, &changed)
+    }
+
+    /// Clones `remote` into `~/.amaya/sync` unless it already is, returning
+    /// the path of the clone.
+    async fn open_repository(
+        project: &ProjectContext,
+        remote: &str,
+    ) -> Result<PathBuf, ConfigError> {
+        if AmarisNetwork::global().is_offline() {
+            return Err(ConfigError::NetworkError(
+                "syncing providers needs network access, but --offline is set".to_string(),
+            ));
+        }
+
+        let repository = AmarisPathHandler::get_sync_path(project)?;
+        if !repository.join(".git").exists() {
+            AmarisGitHandler::clone_into(remote, &repository).await?;
+            return Ok(repository);
+        }
+
+        let origin = AmarisGitHandler::output_in(&repository, &["remote", "get-url", "origin"])
+            .await
+            .unwrap_or_default();
+        if origin.trim() != remote {
+            return Err(ConfigError::GitError(format!(
+                "{} is a clone of {}, not of provider_git_remote {}; delete it to clone again",
+                repository.display(),
+                origin.trim(),
+                remote
+            )));
+        }
+
+        Ok(repository)
+    }
+
+    /// Fast-forwards the clone to the remote, which may still be empty.
+    async fn pull_repository(repository: &Path) -> Result<(), ConfigError> {
+        let has_commits =
+            AmarisGitHandler::output_in(repository, &["ls-remote", "--heads", "origin"])
+                .await
+                .is_some_and(|heads| !heads.trim().is_empty());
+        if !has_commits {
+            return Ok(());
+        }
+
+        AmarisGitHandler::run_in(
+            repository,
+            &["pull", "--quiet", "--ff-only", "origin", "HEAD"],
+        )
+        .await
+    }
+
+    /// Paths of the files under [`SYNCED_DIRS`] of `from` that are missing or
+    /// different in `to`, relative to both. Files only `to` has are left out.
+    fn differences(from: &Path, to: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+        let mut paths = vec![];
+        let mut pending: Vec<PathBuf> = SYNCED_DIRS.iter().map(PathBuf::from).collect();
+
+        while let Some(dir) = pending.pop() {
+            if !from.join(&dir).is_dir() {
+                continue;
+            }
+
+            for entry in std::fs::read_dir(from.join(&dir))? {
+                let entry = entry?;
+                let path = dir.join(entry.file_name());
+
+                if entry.file_type()?.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+
+                let target = to.join(&path);
+                if !target.exists() || std::fs::read(entry.path())? != std::fs::read(&target)? {
+                    paths.push(path);
+                }
+            }
+        }
+
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Copies `paths` from `from` to `to`, both relative to them, unless the
+    /// file in `to` is already the same.
+    fn copy(from: &Path, to: &Path, paths: &[PathBuf]) -> Result<Vec<SyncChange>, ConfigError> {
+        let mut changes = vec![];
+
+        for path in paths {
+            let source = from.join(path);
+            let target = to.join(path);
+            if target.exists() && std::fs::read(&source)? == std::fs::read(&target)? {
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            changes.push(SyncChange {
+                path: path.clone(),
+                added: !target.exists(),
+            });
+            std::fs::copy(&source, &target)?;
+        }
+
+        Ok(changes)
+    }
+
+    /// The provider whose configuration directory holds the source of `config`.
+    fn owner(name: &str, config: &ConfigEntry) -> String {
+        config
+            .source_provider
+            .clone()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Records new `integrity` hashes, given as provider, `source_from` and
+    /// hash, in the provider files declaring those sources.
+    async fn update_integrity(
+        project: &ProjectContext,
+        hashes: &[(String, String, String)],
+    ) -> Result<(), ConfigError> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+
+        for mut provider in DynamicProvider::read_all(project, None).await? {
+            let mut changed = false;
+            let entries = provider.configuration.iter_mut().chain(
+                provider
+                    .variants
+                    .iter_mut()
+                    .flat_map(|variant| variant.configuration.iter_mut()),
+            );
+
+            for config in entries.filter(|config| config.integrity.is_some()) {
+                if let Some((_, _, hash)) = hashes.iter().find(|(owner, source_from, _)| {
+                    *owner == provider.name && *source_from == config.source_from
+                }) {
+                    changed |= config.integrity.as_ref() != Some(hash);
+                    config.integrity = Some(hash.clone());
+                }
+            }
+
+            if changed {
+                provider.save().await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::{
+    provider::{ConfigEntry, DynamicProvider, ModuleSystem, ScriptEntry},
+    utils::Editor,
+};
+
+/// Package managers `amaya init` offers, the first one is the default.
+pub const PACKAGE_MANAGERS: &[&str] = &["bun", "npm", "pnpm", "yarn"];
+
+/// A starter provider `amaya init` writes to the provider directory, along
+/// with the sources of its configurations.
+#[derive(Debug, Clone, Copy)]
+pub struct ProviderTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Written by `amaya init --defaults`.
+    pub default: bool,
+    provider: fn() -> DynamicProvider,
+    /// File names and contents, relative to the provider's configuration
+    /// directory, for the given package manager.
+    sources: fn(&str) -> Vec<(&'static str, String)>,
+}
+
+pub const PROVIDER_TEMPLATES: &[ProviderTemplate] = &[
+    ProviderTemplate {
+        name: "biome",
+        description: "Biome",
+        default: true,
+        provider: biome,
+        sources: biome_sources,
+    },
+    ProviderTemplate {
+        name: "prettier_eslint",
+        description: "Prettier + ESLint",
+        default: true,
+        provider: prettier_eslint,
+        sources: prettier_eslint_sources,
+    },
+    ProviderTemplate {
+        name: "tsconfig",
+        description: "TypeScript",
+        default: false,
+        provider: tsconfig,
+        sources: tsconfig_sources,
+    },
+    ProviderTemplate {
+        name: "husky",
+        description: "Husky pre-commit hook",
+        default: false,
+        provider: husky,
+        sources: husky_sources,
+    },
+];
+
+impl ProviderTemplate {
+    pub fn get(name: &str) -> Option<&'static ProviderTemplate> {
+        PROVIDER_TEMPLATES
+            .iter()
+            .find(|template| template.name == name)
+    }
+
+    /// The provider definition, installing its packages with `package_manager`.
+    pub fn provider(&self, package_manager: &str) -> DynamicProvider {
+        DynamicProvider {
+            package_manager: package_manager.to_string(),
+            ..(self.provider)()
+        }
+    }
+
+    pub fn sources(&self, package_manager: &str) -> Vec<(&'static str, String)> {
+        (self.sources)(package_manager)
+    }
+}
+
+/// A configuration copied from the source of the same name to the project root.
+fn config(file: &str) -> ConfigEntry {
+    ConfigEntry {
+        file_location: file.to_string(),
+        file_name: file.to_string(),
+        source_from: file.to_string(),
+        ..Default::default()
+    }
+}
+
+/// The ESM config `source`, written as `.js` in ESM projects and as `.mjs`
+/// in CommonJS ones.
+fn esm_configs(source: &str) -> [ConfigEntry; 2] {
+    [ModuleSystem::Esm, ModuleSystem::Cjs].map(|module| {
+        let file = source.replace(".js", &format!(".{}", module.extension(ModuleSystem::Esm)));
+        ConfigEntry {
+            file_location: file.clone(),
+            file_name: file,
+            source_from: source.to_string(),
+            module: Some(module),
+            ..Default::default()
+        }
+    })
+}
+
+fn script(name: &str, script: &str) -> ScriptEntry {
+    ScriptEntry {
+        name: name.to_string(),
+        script: script.to_string(),
+        condition: None,
+        remove_on_uninstall: true,
+        original_content: None,
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap()
+}
+
+fn biome() -> DynamicProvider {
+    DynamicProvider {
+        name: "biome".to_string(),
+        description: "Biome".to_string(),
+        version: Some("1.0.0".to_string()),
+        packages: vec!["@biomejs/biome".into()],
+        min_node_version: Some("18".to_string()),
+        configuration: vec![config("biome.json")],
+        editor_settings: BTreeMap::from([
+            (
+                Editor::VsCode,
+                serde_json::json!({
+                    "typescript.tsdk": "node_modules/typescript/lib",
+                    "typescript.enablePromptUseWorkspaceTsdk": true,
+                    "editor.defaultFormatter": "biomejs.biome",
+                    "editor.codeActionsOnSave": {
+                        "quickfix.biome": "explicit",
+                        "source.organizeImports.biome": "explicit"
+                    },
+                    "files.exclude": {
+                        "**/node_modules": true
+                    }
+                }),
+            ),
+            (
+                Editor::Zed,
+                serde_json::json!({
+                    "formatter": {
+                        "language_server": {
+                            "name": "biome"
+                        }
+                    },
+                    "format_on_save": "on",
+                    "code_actions_on_format": {
+                        "source.fixAll.biome": true,
+                        "source.organizeImports.biome": true
+                    },
+                    "lsp": {
+                        "biome": {
+                            "settings": {
+                                "require_config_file": true
+                            }
+                        }
+                    }
+                }),
+            ),
+        ]),
+        scripts: vec![
+            script("format", "biome format ."),
+            script("lint", "biome lint ."),
+        ],
+        ..Default::default()
+    }
+}
+
+fn biome_sources(_package_manager: &str) -> Vec<(&'static str, String)> {
+    let biome_config = serde_json::json!({
+        "$schema": "https://biomejs.dev/schemas/1.9.4/schema.json",
+        "extends": [
+            "ultracite"
+        ],
+        "vcs": {
+            "enabled": true,
+            "clientKind": "git",
+            "useIgnoreFile": true,
+            "defaultBranch": "master"
+        },
+        "organizeImports": {
+            "enabled": true
+        },
+        "files": {
+            "ignore": [
+                "node_modules"
+            ]
+        },
+        "formatter": {
+            "enabled": true,
+            "formatWithErrors": false,
+            "indentStyle": "space",
+            "indentWidth": 4,
+            "lineWidth": 120
+        },
+        "linter": {
+            "enabled": true,
+            "rules": {
+                "recommended": true,
+                "style": {
+                    "noNonNullAssertion": "off",
+                    "useForOf": "error",
+                    "useNodejsImportProtocol": "error",
+                    "useNumberNamespace": "error",
+                    "noInferrableTypes": "warn"
+                },
+                "correctness": {
+                    "noUnusedImports": "warn",
+                    "noUnusedVariables": "info",
+                    "noUnusedFunctionParameters": "info",
+                    "useHookAtTopLevel": "off"
+                },
+                "complexity": {
+                    "noStaticOnlyClass": "off",
+                    "noThisInStatic": "off",
+                    "noForEach": "error",
+                    "noUselessSwitchCase": "error",
+                    "useFlatMap": "error"
+                },
+                "suspicious": {
+                    "noConsole": "off",
+                    "noConsoleLog": "off"
+                },
+                "nursery": {
+                    "useConsistentMemberAccessibility": "off",
+                    "noNestedTernary": "off"
+                },
+                "performance": {
+                    "useTopLevelRegex": "off"
+                }
+            }
+        },
+        "javascript": {
+            "formatter": {
+                "quoteStyle": "double",
+                "indentWidth": 4,
+                "lineWidth": 120
+            },
+            "globals": [
+                "Bun"
+            ]
+        },
+        "json": {
+            "formatter": {
+                "indentWidth": 4,
+                "indentStyle": "space"
+            }
+        }
+    });
+
+    vec![("biome.json", biome_config.to_string())]
+}
+
+fn prettier_eslint() -> DynamicProvider {
+    DynamicProvider {
+        name: "prettier_eslint".to_string(),
+        description: "Prettier + ESLint".to_string(),
+        version: Some("1.0.0".to_string()),
+        packages: vec![
+            "prettier".into(),
+            "eslint".into(),
+            "@eslint/js".into(),
+            "eslint-config-prettier".into(),
+            "globals".into(),
+        ],
+        configuration: std::iter::once(config(".prettierrc.json"))
+            .chain(esm_configs("eslint.config.js"))
+            .collect(),
+        editor_settings: BTreeMap::from([
+            (
+                Editor::VsCode,
+                serde_json::json!({
+                    "editor.defaultFormatter": "esbenp.prettier-vscode",
+                    "editor.formatOnSave": true,
+                    "editor.codeActionsOnSave": {
+                        "source.fixAll.eslint": "explicit"
+                    },
+                    "files.exclude": {
+                        "**/node_modules": true
+                    }
+                }),
+            ),
+            (
+                Editor::Zed,
+                serde_json::json!({
+                    "formatter": "prettier",
+                    "format_on_save": "on",
+                    "code_actions_on_format": {
+                        "source.fixAll.eslint": true
+                    }
+                }),
+            ),
+        ]),
+        owned_settings: vec!["editor.codeActionsOnSave.source.fixAll.eslint".to_string()],
+        scripts: vec![
+            script("format", "prettier --write ."),
+            script("lint", "eslint ."),
+        ],
+        ..Default::default()
+    }
+}
+
+fn prettier_eslint_sources(_package_manager: &str) -> Vec<(&'static str, String)> {
+    let prettier_config = serde_json::json!({
+        "$schema": "https://json.schemastore.org/prettierrc",
+        "semi": true,
+        "singleQuote": false,
+        "tabWidth": 4,
+        "printWidth": 120,
+        "trailingComma": "all"
+    });
+
+    let eslint_config = r#"import js from "@eslint/js";
+import prettier from "eslint-config-prettier";
+import globals from "globals";
+
+export default [
+    { ignores: ["node_modules", "dist"] },
+    js.configs.recommended,
+    {
+        languageOptions: {
+            globals: { ...globals.browser, ...globals.node },
+        },
+        rules: {
+            "no-unused-vars": "warn",
+        },
+    },
+    prettier,
+];
+"#;
+
+    vec![
+        (".prettierrc.json", pretty(&prettier_config)),
+        ("eslint.config.js", eslint_config.to_string()),
+    ]
+}
+
+fn tsconfig() -> DynamicProvider {
+    DynamicProvider {
+        name: "tsconfig".to_string(),
+        description: "TypeScript".to_string(),
+        version: Some("1.0.0".to_string()),
+        packages: vec!["typescript".into()],
+        configuration: vec![config("tsconfig.json")],
+        scripts: vec![script("typecheck", "tsc --noEmit")],
+        ..Default::default()
+    }
+}
+
+fn tsconfig_sources(_package_manager: &str) -> Vec<(&'static str, String)> {
+    let tsconfig = serde_json::json!({
+        "compilerOptions": {
+            "target": "ES2022",
+            "module": "ESNext",
+            "moduleResolution": "Bundler",
+            "strict": true,
+            "noEmit": true,
+            "skipLibCheck": true,
+            "esModuleInterop": true,
+            "resolveJsonModule": true,
+            "isolatedModules": true
+        },
+        "exclude": ["node_modules", "dist"]
+    });
+
+    vec![("tsconfig.json", pretty(&tsconfig))]
+}
+
+fn husky() -> DynamicProvider {
+    DynamicProvider {
+        name: "husky".to_string(),
+        description: "Husky pre-commit hook".to_string(),
+        version: Some("1.0.0".to_string()),
+        packages: vec!["husky".into()],
+        configuration: vec![ConfigEntry {
+            file_location: ".husky/pre-commit".to_string(),
+            file_name: "pre-commit".to_string(),
+            source_from: "pre-commit".to_string(),
+            ..Default::default()
+        }],
+        scripts: vec![script("prepare", "husky")],
+        requires_git: true,
+        ..Default::default()
+    }
+}
+
+fn husky_sources(package_manager: &str) -> Vec<(&'static str, String)> {
+    vec![("pre-commit", format!("{} run lint\n", package_manager))]
+}
@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    error::ConfigError,
+    provider::ProjectContext,
+    reporter::Reporter,
+    state::{AmarisProjectState, BASE_STORE_DIR},
+    utils::{
+        AmarisJournal, AmarisPackageJsonHandler, AmarisVisualStudioCodeHandler, Editor,
+        LOCKFILE_NAMES, VsCodeFile,
+    },
+};
+
+/// A step of an [`InstallTransaction`], removals always run before installs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionStep {
+    Remove(String),
+    Install(String),
+}
+
+/// Runs removals and installs as one change: the files they may touch are
+/// read up front, and written back if a step fails.
+#[derive(Debug, Default)]
+pub struct InstallTransaction {
+    steps: Vec<TransactionStep>,
+    /// Content of each file before the transaction, `None` if it did not exist.
+    snapshot: BTreeMap<PathBuf, Option<Vec<u8>>>,
+}
+
+impl InstallTransaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn queue_remove(&mut self, name: &str) {
+        let position = self
+            .steps
+            .iter()
+            .position(|step| matches!(step, TransactionStep::Install(_)))
+            .unwrap_or(self.steps.len());
+
+        self.steps
+            .insert(position, TransactionStep::Remove(name.to_string()));
+    }
+
+    pub fn queue_install(&mut self, name: &str) {
+        self.steps.push(TransactionStep::Install(name.to_string()));
+    }
+
+    pub fn steps(&self) -> &[TransactionStep] {
+        &self.steps
+    }
+
+    /// Reads `paths` along with the files every install touches: package.json,
+    /// lockfiles, editor settings, amaya.json and the base store.
+    pub async fn begin(
+        &mut self,
+        project: &ProjectContext,
+        paths: Vec<PathBuf>,
+    ) -> Result<(), ConfigError> {
+        let mut paths = paths;
+        paths.push(AmarisPackageJsonHandler::get_default_path(project));
+        paths.push(AmarisProjectState::get_default_path(project));
+        paths.extend(LOCKFILE_NAMES.iter().map(|lockfile| project.path(lockfile)));
+        paths.extend(VsCodeFile::ALL.iter().map(|file| project.path(file.path())));
+        paths.extend(
+            Editor::ALL
+                .iter()
+                .map(|editor| project.path(editor.settings_path())),
+        );
+        paths.extend(AmarisVisualStudioCodeHandler::find_workspace_file(project));
+        paths.extend(files_under(project.path(BASE_STORE_DIR)).await?);
+
+        for path in paths {
+            if self.snapshot.contains_key(&path) {
+                continue;
+            }
+
+            let content = match tokio::fs::read(&path).await {
+                Ok(content) => Some(content),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(ConfigError::FileReadError(format!(
+                        "{}: {}",
+                        path.display(),
+                        e
+                    )));
+                }
+            };
+            self.snapshot.insert(path, content);
+        }
+
+        // Only what the steps write is of interest from here on
+        AmarisJournal::take(project);
+
+        Ok(())
+    }
+
+    /// Puts every file back as it was before [`InstallTransaction::begin`].
+    /// Files the steps touched that were not read up front are reported, as
+    /// they cannot be restored.
+    pub async fn rollback(self, project: &ProjectContext) -> Result<(), ConfigError> {
+        let reporter = Reporter::global();
+        let touched = AmarisJournal::take(project);
+
+        let base_store = project.path(BASE_STORE_DIR);
+        for path in files_under(base_store).await? {
+            if !self.snapshot.contains_key(&path) {
+                tokio::fs::remove_file(&path).await?;
+            }
+        }
+
+        for (path, content) in &self.snapshot {
+            match content {
+                Some(content) => {
+                    if let Some(parent) = path.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    tokio::fs::write(path, content).await.map_err(|e| {
+                        ConfigError::FileWriteError(format!("{}: {}", path.display(), e))
+                    })?;
+                }
+                None if path.exists() => tokio::fs::remove_file(path).await.map_err(|e| {
+                    ConfigError::FileWriteError(format!("{}: {}", path.display(), e))
+                })?,
+                None => {}
+            }
+        }
+
+        for path in touched
+            .iter()
+            .filter(|path| !self.snapshot.contains_key(*path))
+        {
+            reporter.warn(format!(
+                "Warning: {} was changed and could not be restored",
+                project.relative(path).display()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Every file below `dir`, nothing when it does not exist.
+async fn files_under(dir: PathBuf) -> Result<Vec<PathBuf>, ConfigError> {
+    let mut files = vec![];
+    let mut pending = vec![dir];
+
+    while let Some(dir) = pending.pop() {
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            match entry.file_type().await?.is_dir() {
+                true => pending.push(path),
+                false => files.push(path),
+            }
+        }
+    }
+
+    Ok(files)
+}
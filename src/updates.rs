@@ -0,0 +1,214 @@
+use futures::future::join_all;
+use reqwest::header;
+use semver::Version;
+use serde_json::Value;
+
+use crate::{
+    error::ConfigError,
+    network::{AmarisNetwork, describe_error},
+    package_manager::PackageRegistry,
+    provider::{AmarisRegistry, InstallPhase, ProjectContext},
+    state::AmarisProjectState,
+    utils::AmarisPackageJsonHandler,
+};
+
+pub const DEFAULT_NPM_REGISTRY: &str = "https://registry.npmjs.org";
+/// The abbreviated metadata, enough for `dist-tags` and much smaller.
+const ABBREVIATED_METADATA: &str = "application/vnd.npm.install-v1+json";
+
+/// How far the latest version is ahead of the installed one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UpdateKind {
+    UpToDate,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl UpdateKind {
+    pub fn between(installed: &Version, latest: &Version) -> Self {
+        if latest <= installed {
+            UpdateKind::UpToDate
+        } else if latest.major != installed.major {
+            UpdateKind::Major
+        } else if latest.minor != installed.minor {
+            UpdateKind::Minor
+        } else {
+            UpdateKind::Patch
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateKind::UpToDate => "up to date",
+            UpdateKind::Patch => "patch",
+            UpdateKind::Minor => "minor",
+            UpdateKind::Major => "major",
+        }
+    }
+}
+
+/// A package of an installed provider with the latest version published.
+#[derive(Debug, Clone)]
+pub struct PackageUpdate {
+    pub package: String,
+    pub provider: String,
+    /// The version specifier in package.json, such as `^1.9.4`.
+    pub installed: String,
+    pub latest: String,
+    pub kind: UpdateKind,
+}
+
+/// Looks up newer versions of the packages installed providers manage.
+pub struct AmarisUpdates;
+
+impl AmarisUpdates {
+    /// The packages of the installed providers, or of `provider` only, that
+    /// package.json lists, with the latest version of each.
+    pub async fn check(
+        project: &ProjectContext,
+        registry: &AmarisRegistry,
+        provider: Option<&str>,
+    ) -> Result<Vec<PackageUpdate>, ConfigError> {
+        let state = AmarisProjectState::load(project).await?;
+        let mut packages = vec![];
+
+        for record in &state.installed_providers {
+            if provider.is_some_and(|provider| provider != record.name)
+                || !record.phases.contains(&InstallPhase::Packages)
+            {
+                continue;
+            }
+
+            let Some(installed_provider) = registry.get_provider(&record.name) else {
+                continue;
+            };
+            for package in installed_provider.package_entries() {
+                if package.is_global() || record.untracked_packages.contains(&package.name) {
+                    continue;
+                }
+
+                if let Some(specifier) =
+                    AmarisPackageJsonHandler::get_dependency_version(project, &package.name).await?
+                {
+                    packages.push((package.name, record.name.clone(), specifier));
+                }
+            }
+        }
+
+        let npm_registry = PackageRegistry::resolve(project, None).await?;
+        let lookups = packages
+            .iter()
+            .map(|(package, _, _)| Self::latest_version(project, npm_registry.as_ref(), package));
+        let latest = join_all(lookups).await;
+
+        let mut updates = vec![];
+        for ((package, provider, installed), latest) in packages.into_iter().zip(latest) {
+            let latest = latest?;
+            let kind = match Self::minimum_version(&installed) {
+                Some(minimum) => UpdateKind::between(&minimum, &latest),
+                None => UpdateKind::UpToDate,
+            };
+
+            updates.push(PackageUpdate {
+                package,
+                provider,
+                installed,
+                latest: latest.to_string(),
+                kind,
+            });
+        }
+
+        Ok(updates)
+    }
+
+    /// The `latest` dist-tag of `package`, from the configured registry or
+    /// the public npm registry.
+    async fn latest_version(
+        project: &ProjectContext,
+        npm_registry: Option<&PackageRegistry>,
+        package: &str,
+    ) -> Result<Version, ConfigError> {
+        Self::fetch_metadata(project, npm_registry, package)
+            .await?
+            .pointer("/dist-tags/latest")
+            .and_then(Value::as_str)
+            .and_then(|latest| Version::parse(latest).ok())
+            .ok_or_else(|| ConfigError::NetworkError(format!("{} has no latest version", package)))
+    }
+
+    /// The abbreviated registry metadata of `package`, with its `dist-tags`
+    /// and the `dist` of every version.
+    pub async fn fetch_metadata(
+        project: &ProjectContext,
+        npm_registry: Option<&PackageRegistry>,
+        package: &str,
+    ) -> Result<Value, ConfigError> {
+        let operation = "fetching package metadata";
+        let network = AmarisNetwork::global();
+        let base = npm_registry
+            .map(|registry| registry.url.as_str())
+            .unwrap_or(DEFAULT_NPM_REGISTRY);
+        // Scoped packages keep their `@` but escape the `/`
+        let url = format!("{}/{}", base, package.replace('/', "%2F"));
+
+        let mut request = network
+            .client(project, operation)
+            .await?
+            .get(&url)
+            .header(header::ACCEPT, ABBREVIATED_METADATA);
+        if let Some(token) = Self::token(npm_registry) {
+            request = request.bearer_auth(token);
+        }
+
+        let response = network.send(project, operation, request).await?;
+        if !response.status().is_success() {
+            return Err(ConfigError::NetworkError(format!(
+                "{} returned {}",
+                url,
+                response.status()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ConfigError::NetworkError(format!("{}: {}", url, describe_error(&e))))?;
+
+        serde_json::from_slice(&body)
+            .map_err(|e| ConfigError::NetworkError(format!("{}: {}", url, e)))
+    }
+
+    /// The auth token of `npm_registry`, read from the variable `npm.token_env` names.
+    pub fn token(npm_registry: Option<&PackageRegistry>) -> Option<String> {
+        npm_registry
+            .and_then(|registry| registry.token_env.as_ref())
+            .and_then(|token_env| std::env::var(token_env).ok())
+    }
+
+    /// The lowest version `specifier` accepts, `^1.9` gives 1.9.0. `None` for
+    /// tags, URLs and workspace references.
+    pub fn minimum_version(specifier: &str) -> Option<Version> {
+        let version = specifier
+            .trim()
+            .trim_start_matches(['^', '~', '>', '=', 'v', ' '])
+            .split([' ', '|'])
+            .next()?;
+
+        let mut parts: Vec<&str> = version.split('-').next()?.split('.').collect();
+        if parts.is_empty() || parts.len() > 3 {
+            return None;
+        }
+        parts.resize(3, "0");
+
+        let parts: Vec<&str> = parts
+            .into_iter()
+            .map(|part| match part {
+                "x" | "X" | "*" => "0",
+                part => part,
+            })
+            .collect();
+
+        Version::parse(&parts.join(".")).ok()
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use crate::{
+    args::{UpgradeArgs, commit_changes, confirm_dirty_worktree, install_options, managed_files},
+    config::AmayaConfig,
+    layers::AmarisContributions,
+    merge::ConflictResolution,
+    provider::{AmarisRegistry, InstallOptions, InstallPhase, ProjectContext},
+    reporter::Reporter,
+    state::AmarisProjectState,
+};
+
+/// `amaya upgrade`: re-applies an installed provider, merging its changes
+/// with local edits to the files it manages.
+pub struct AmarisUpgrade;
+
+impl AmarisUpgrade {
+    pub async fn run(
+        project: &ProjectContext,
+        registry: &AmarisRegistry,
+        args: &UpgradeArgs,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let reporter = Reporter::global();
+        let UpgradeArgs {
+            config,
+            ours,
+            theirs,
+            allow_dirty,
+            commit,
+            variables,
+            env,
+            editors,
+        } = args;
+
+        if let Some(provider) = registry.get_provider(config) {
+            let state = AmarisProjectState::load(project).await?;
+            let Some(record) = state.get(provider.name()) else {
+                anyhow::bail!(
+                    "{} is not installed in this project, use `amaya install` instead",
+                    provider.name()
+                );
+            };
+
+            let variant_provider = record
+                .variant
+                .as_deref()
+                .map(|variant| provider.with_variant(variant))
+                .transpose()?;
+            let provider = variant_provider.as_deref().unwrap_or(provider);
+
+            provider.check_prerequisites(project).await?;
+
+            if !*allow_dirty
+                && !confirm_dirty_worktree(
+                    project,
+                    &[(
+                        provider,
+                        state.workspace_file(project, provider.name()).as_deref(),
+                    )],
+                )
+                .await?
+            {
+                reporter.warn("Aborted.");
+                return Ok(());
+            }
+
+            let conflict_resolution = match (*ours, *theirs) {
+                (true, _) => Some(ConflictResolution::Ours),
+                (_, true) => Some(ConflictResolution::Theirs),
+                _ => None,
+            };
+
+            let options = InstallOptions {
+                conflict_resolution,
+                workspace_file: state.workspace_file(project, provider.name()),
+                phases: state.phases(provider.name()),
+                ..install_options(
+                    &AmayaConfig::load(project).await?,
+                    timeout,
+                    variables,
+                    env,
+                    editors,
+                )
+            };
+
+            AmarisContributions::start(project);
+            provider.upgrade(project, &options).await?;
+
+            let mut state = AmarisProjectState::load(project).await?;
+            let files = match options.applies(InstallPhase::Configs) {
+                true => managed_files(project, provider),
+                false => vec![],
+            };
+            state.record_upgrade(
+                provider.name(),
+                provider.metadata().version,
+                files,
+                AmarisContributions::take(project),
+            );
+            state.save(project).await?;
+
+            if options.verify && options.applies(InstallPhase::Configs) {
+                for problem in provider.verify(project, &options).await? {
+                    reporter.warn(format!("Warning: {}", problem));
+                }
+            }
+
+            commit_changes(
+                project,
+                *commit,
+                "upgrade",
+                provider.name(),
+                provider.metadata().version.as_deref(),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{
+    args::check_provider_sources,
+    config::ProjectOverrides,
+    provider::{AmarisRegistry, DynamicProvider, ProjectContext},
+    reporter::Reporter,
+};
+
+/// `amaya validate`: checks every provider file and the project overrides.
+pub struct AmarisValidate;
+
+impl AmarisValidate {
+    /// Reports each problem found and returns how many there were.
+    pub async fn run(
+        project: &ProjectContext,
+        registry: &AmarisRegistry,
+        update_hashes: bool,
+    ) -> anyhow::Result<usize> {
+        let reporter = Reporter::global();
+        let mut problems = 0;
+
+        for provider in DynamicProvider::read_each(project, None).await? {
+            let mut provider = match provider {
+                Ok(provider) => provider,
+                Err(e) => {
+                    reporter.error(e);
+                    problems += 1;
+                    continue;
+                }
+            };
+
+            let issues = provider.validate();
+            for issue in &issues {
+                reporter.error(format!("{}: {}", provider.file_label(), issue));
+            }
+            if !issues.is_empty() {
+                problems += issues.len();
+                continue;
+            }
+
+            problems += check_provider_sources(project, &mut provider, update_hashes).await?;
+        }
+
+        let overrides_path = ProjectOverrides::get_default_path(project);
+        match ProjectOverrides::load(project).await {
+            Ok(overrides) => {
+                for issue in overrides.issues(registry) {
+                    reporter.error(format!("{}: {}", overrides_path.display(), issue));
+                    problems += 1;
+                }
+            }
+            Err(e) => {
+                reporter.error(e);
+                problems += 1;
+            }
+        }
+
+        Ok(problems)
+    }
+}
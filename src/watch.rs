@@ -0,0 +1,133 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use similar::{ChangeTag, TextDiff};
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use crate::{error::ConfigError, reporter::Reporter};
+
+/// How long events have to stop before a change is reported, editors often
+/// write a file several times when saving.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Changed lines shown per file before the rest is summarized.
+const MAX_DIFF_LINES: usize = 20;
+/// Unchanged lines shown around each change of a unified diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Watches a provider file and its configuration directory.
+pub struct AmarisWatcher {
+    // Events stop once the watcher is dropped
+    _watcher: RecommendedWatcher,
+    events: UnboundedReceiver<Vec<PathBuf>>,
+}
+
+impl AmarisWatcher {
+    /// Watches `provider_file` and everything under `config_dir`. The
+    /// directory of the provider file is watched, so editors replacing the
+    /// file on save are noticed too.
+    pub fn new(provider_file: &Path, config_dir: &Path) -> Result<Self, ConfigError> {
+        let (sender, events) = unbounded_channel();
+        let watched_file = provider_file.to_path_buf();
+        let watched_dir = config_dir.to_path_buf();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+
+                let paths: Vec<PathBuf> = event
+                    .paths
+                    .into_iter()
+                    .filter(|path| *path == watched_file || path.starts_with(&watched_dir))
+                    .collect();
+                if !paths.is_empty() {
+                    let _ = sender.send(paths);
+                }
+            })
+            .map_err(watch_error)?;
+
+        let provider_dir = provider_file.parent().unwrap_or(Path::new("."));
+        watcher
+            .watch(provider_dir, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+        watcher
+            .watch(config_dir, RecursiveMode::Recursive)
+            .map_err(watch_error)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Waits for the next change and for the events after it to settle.
+    /// Returns the changed paths, `None` once the watcher has stopped.
+    pub async fn next_change(&mut self) -> Option<Vec<PathBuf>> {
+        let mut changed = self.events.recv().await?;
+
+        while let Ok(Some(paths)) = tokio::time::timeout(DEBOUNCE, self.events.recv()).await {
+            changed.extend(paths);
+        }
+
+        changed.sort();
+        changed.dedup();
+        Some(changed)
+    }
+}
+
+/// Prints the lines that differ between `old` and `new`, without context.
+pub fn print_diff(path: &Path, old: &str, new: &str) {
+    let reporter = Reporter::global();
+    let diff = TextDiff::from_lines(old, new);
+    let changes: Vec<_> = diff
+        .iter_all_changes()
+        .filter(|change| change.tag() != ChangeTag::Equal)
+        .collect();
+
+    reporter.info(format!("~ {}", path.display()));
+    for change in changes.iter().take(MAX_DIFF_LINES) {
+        let line = change.value().trim_end_matches(['\r', '\n']);
+        match change.tag() {
+            ChangeTag::Insert => reporter.success(format!("  + {}", line)),
+            _ => reporter.warn(format!("  - {}", line)),
+        }
+    }
+
+    if changes.len() > MAX_DIFF_LINES {
+        reporter.info(format!(
+            "  ... {} more changed lines",
+            changes.len() - MAX_DIFF_LINES
+        ));
+    }
+}
+
+/// Prints a unified diff from `old` to `new`, indented under the file name.
+pub fn print_unified_diff(path: &Path, old: &str, new: &str) {
+    let reporter = Reporter::global();
+    let name = path.display().to_string();
+    let diff = TextDiff::from_lines(old, new);
+    let unified = diff
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .header(&name, &name)
+        .to_string();
+
+    for line in unified.lines() {
+        match line.chars().next() {
+            Some('+') if !line.starts_with("+++") => reporter.success(format!("  {}", line)),
+            Some('-') if !line.starts_with("---") => reporter.warn(format!("  {}", line)),
+            _ => reporter.info(format!("  {}", line)),
+        }
+    }
+}
+
+fn watch_error(error: notify::Error) -> ConfigError {
+    ConfigError::PathError(format!("cannot watch for changes: {}", error))
+}
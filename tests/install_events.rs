@@ -0,0 +1,60 @@
+#![cfg(unix)]
+
+use std::{os::unix::fs::PermissionsExt, path::Path, process::Command};
+
+use regex::Regex;
+
+/// A `bun` that accepts every command without touching the network.
+fn fake_bun(dir: &Path) {
+    let bun = dir.join("bun");
+    std::fs::write(&bun, "#!/bin/sh\nexit 0\n").unwrap();
+    std::fs::set_permissions(&bun, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// The event lines of `output`, with timings zeroed so runs compare equal.
+fn events(output: &[u8]) -> String {
+    let elapsed = Regex::new(r#""elapsed_ms":\d+"#).unwrap();
+    let output = String::from_utf8_lossy(output);
+
+    for line in output.lines() {
+        serde_json::from_str::<serde_json::Value>(line).unwrap();
+    }
+    elapsed
+        .replace_all(&output, r#""elapsed_ms":0"#)
+        .into_owned()
+}
+
+#[test]
+fn install_streams_its_events_as_json_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let (bin, home, project) = (
+        dir.path().join("bin"),
+        dir.path().join("home"),
+        dir.path().join("project"),
+    );
+    for path in [&bin, &home, &project] {
+        std::fs::create_dir(path).unwrap();
+    }
+    fake_bun(&bin);
+    std::fs::write(
+        project.join("package.json"),
+        r#"{ "name": "fixture", "version": "1.0.0" }"#,
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_amaya"))
+        .args(["install", "--output", "json-lines", "--from-file"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/provider.json"))
+        .current_dir(&project)
+        .env("PATH", format!("{}:/usr/bin:/bin", bin.display()))
+        .env("HOME", &home)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    insta::assert_snapshot!(events(&output.stdout));
+}